@@ -30,9 +30,11 @@ fn main() {
         .expect("Failed to serialize schema")
         .replace("#/components/schemas/", "#/definitions/");
 
-    let schema: serde_json::Value =
+    let mut schema: serde_json::Value =
         serde_json::from_str(&json_schema_str).expect("Failed to parse converted schema");
 
+    name_state_change_payloads(&mut schema);
+
     // Generate Rust types with typify
     let mut type_space = typify::TypeSpace::default();
     type_space
@@ -50,6 +52,35 @@ fn main() {
     fs::write(out_path, stripped).expect("Failed to write generated.rs");
 }
 
+/// Give each `StateChangeWithCauseView` variant's inline `change` payload
+/// its own title.
+///
+/// Untitled, typify names all of them `StateChangeWithCauseViewChange` and
+/// keeps only the first, so every variant ends up with the account update
+/// payload. Titled, each becomes its own type, e.g. `DataUpdateChange`.
+fn name_state_change_payloads(schema: &mut serde_json::Value) {
+    let Some(variants) = schema
+        .pointer_mut("/definitions/StateChangeWithCauseView/oneOf")
+        .and_then(|v| v.as_array_mut())
+    else {
+        return;
+    };
+
+    for variant in variants {
+        let Some(title) = variant.get("title").and_then(|t| t.as_str()) else {
+            continue;
+        };
+        let title = format!("{title}Change");
+        if let Some(change) = variant
+            .pointer_mut("/properties/change")
+            .and_then(|c| c.as_object_mut())
+            && !change.contains_key("$ref")
+        {
+            change.insert("title".to_string(), title.into());
+        }
+    }
+}
+
 fn prettyplease_format(code: &str) -> Option<String> {
     let syntax_tree = syn::parse_file(code).ok()?;
     Some(prettyplease::unparse(&syntax_tree))
@@ -0,0 +1,342 @@
+//! Pre-flight access key permission checks.
+//!
+//! Nodes reject a transaction signed with an insufficient access key only after
+//! it has been submitted. [`check_access_key`] applies the same rules nearcore's
+//! runtime verifier uses, so callers can find out beforehand — and with every
+//! failing reason at once instead of the first one the node hits.
+
+use crate::types::{AccessKeyPermissionView, AccountId, NearToken, NonDelegateAction};
+
+/// A reason an access key may not sign a planned transaction.
+///
+/// Mirrors the variants of the generated [`InvalidAccessKeyError`](crate::types::InvalidAccessKeyError),
+/// with extra detail about which action failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PermissionDenial {
+    /// Function-call keys can only sign transactions with exactly one action.
+    #[error("function-call access keys allow exactly one action, transaction has {count}")]
+    RequiresFullAccess { count: usize },
+    /// Function-call keys can only sign `FunctionCall` actions.
+    #[error("action #{index} ({action}) requires a full access key")]
+    NotAFunctionCall { index: usize, action: &'static str },
+    /// The transaction receiver is not the key's `receiver_id`.
+    #[error("access key is restricted to {ak_receiver}, transaction receiver is {tx_receiver}")]
+    ReceiverMismatch {
+        ak_receiver: String,
+        tx_receiver: AccountId,
+    },
+    /// The called method is not in the key's `method_names`.
+    #[error("method {method_name} is not allowed by the access key (allowed: {allowed:?})")]
+    MethodNameMismatch {
+        method_name: String,
+        allowed: Vec<String>,
+    },
+    /// Function-call keys cannot attach a deposit.
+    #[error(
+        "function call {method_name} attaches {deposit} yoctoNEAR; function-call keys require a zero deposit"
+    )]
+    DepositWithFunctionCall {
+        method_name: String,
+        deposit: NearToken,
+    },
+    /// The key's remaining allowance does not cover the estimated cost.
+    #[error("allowance {allowance} yoctoNEAR does not cover estimated cost {cost} yoctoNEAR")]
+    NotEnoughAllowance {
+        allowance: NearToken,
+        cost: NearToken,
+    },
+    /// An amount in the permission or an action is not a valid yoctoNEAR value.
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+}
+
+/// Checks whether an access key with `permission` may sign a transaction
+/// sending `actions` to `receiver_id`.
+///
+/// `estimated_cost` is the total yoctoNEAR the transaction is expected to burn
+/// (see [`GasEstimate::gas_cost`](crate::fees::GasEstimate::gas_cost)); pass
+/// `None` to skip the allowance check. Returns every reason the key is insufficient; an empty list
+/// means the transaction is allowed.
+///
+/// # Example
+///
+/// ```
+/// use near_openrpc_client::access_key::{PermissionDenial, check_access_key};
+/// use near_openrpc_client::types::*;
+///
+/// let permission = AccessKeyPermissionView::FunctionCall {
+///     allowance: Some(NearToken("250000000000000000000000".to_string())),
+///     method_names: vec!["add_message".to_string()],
+///     receiver_id: "guestbook.near".to_string(),
+/// };
+/// let actions = vec![NonDelegateAction::FunctionCall(FunctionCallAction {
+///     args: String::new(),
+///     deposit: NearToken("1".to_string()),
+///     gas: NearGas(30_000_000_000_000),
+///     method_name: "add_message".to_string(),
+/// })];
+///
+/// let denials = check_access_key(&permission, &"guestbook.near".parse().unwrap(), &actions, None);
+/// assert!(matches!(denials[..], [PermissionDenial::DepositWithFunctionCall { .. }]));
+/// ```
+pub fn check_access_key(
+    permission: &AccessKeyPermissionView,
+    receiver_id: &AccountId,
+    actions: &[NonDelegateAction],
+    estimated_cost: Option<u128>,
+) -> Vec<PermissionDenial> {
+    let (allowance, method_names, ak_receiver) = match permission {
+        AccessKeyPermissionView::FullAccess | AccessKeyPermissionView::GasKeyFullAccess { .. } => {
+            return Vec::new();
+        }
+        AccessKeyPermissionView::FunctionCall {
+            allowance,
+            method_names,
+            receiver_id,
+        }
+        | AccessKeyPermissionView::GasKeyFunctionCall {
+            allowance,
+            method_names,
+            receiver_id,
+            ..
+        } => (allowance, method_names, receiver_id),
+    };
+
+    let mut denials = Vec::new();
+
+    if actions.len() != 1 {
+        denials.push(PermissionDenial::RequiresFullAccess {
+            count: actions.len(),
+        });
+    }
+
+    if receiver_id.as_str() != ak_receiver {
+        denials.push(PermissionDenial::ReceiverMismatch {
+            ak_receiver: ak_receiver.clone(),
+            tx_receiver: receiver_id.clone(),
+        });
+    }
+
+    for (index, action) in actions.iter().enumerate() {
+        let NonDelegateAction::FunctionCall(call) = action else {
+            denials.push(PermissionDenial::NotAFunctionCall {
+                index,
+                action: action_name(action),
+            });
+            continue;
+        };
+
+        match call.deposit.parse::<u128>() {
+            Ok(0) => {}
+            Ok(_) => denials.push(PermissionDenial::DepositWithFunctionCall {
+                method_name: call.method_name.clone(),
+                deposit: call.deposit.clone(),
+            }),
+            Err(_) => denials.push(PermissionDenial::InvalidAmount(call.deposit.0.clone())),
+        }
+
+        if !method_names.is_empty() && !method_names.contains(&call.method_name) {
+            denials.push(PermissionDenial::MethodNameMismatch {
+                method_name: call.method_name.clone(),
+                allowed: method_names.clone(),
+            });
+        }
+    }
+
+    if let (Some(allowance), Some(cost)) = (allowance, estimated_cost) {
+        match allowance.parse::<u128>() {
+            Ok(remaining) if remaining < cost => {
+                denials.push(PermissionDenial::NotEnoughAllowance {
+                    allowance: allowance.clone(),
+                    cost: NearToken(cost.to_string()),
+                });
+            }
+            Ok(_) => {}
+            Err(_) => denials.push(PermissionDenial::InvalidAmount(allowance.0.clone())),
+        }
+    }
+
+    denials
+}
+
+/// Returns the variant name of an action, for error messages.
+pub(crate) fn action_name(action: &NonDelegateAction) -> &'static str {
+    match action {
+        NonDelegateAction::CreateAccount(_) => "CreateAccount",
+        NonDelegateAction::DeployContract(_) => "DeployContract",
+        NonDelegateAction::FunctionCall(_) => "FunctionCall",
+        NonDelegateAction::Transfer(_) => "Transfer",
+        NonDelegateAction::Stake(_) => "Stake",
+        NonDelegateAction::AddKey(_) => "AddKey",
+        NonDelegateAction::DeleteKey(_) => "DeleteKey",
+        NonDelegateAction::DeleteAccount(_) => "DeleteAccount",
+        NonDelegateAction::DeployGlobalContract(_) => "DeployGlobalContract",
+        NonDelegateAction::UseGlobalContract(_) => "UseGlobalContract",
+        NonDelegateAction::DeterministicStateInit(_) => "DeterministicStateInit",
+        NonDelegateAction::TransferToGasKey(_) => "TransferToGasKey",
+        NonDelegateAction::WithdrawFromGasKey(_) => "WithdrawFromGasKey",
+    }
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use super::{PermissionDenial, check_access_key};
+    use crate::client::{NearRpcClient, Result};
+    use crate::types::*;
+
+    impl NearRpcClient {
+        /// Fetches `public_key` of `signer_id` at final finality and checks it
+        /// against a planned transaction with [`check_access_key`].
+        ///
+        /// Fails with the usual RPC error (e.g. `UNKNOWN_ACCESS_KEY`) if the key
+        /// does not exist.
+        pub async fn preflight_access_key(
+            &self,
+            signer_id: &AccountId,
+            public_key: &PublicKey,
+            receiver_id: &AccountId,
+            actions: &[NonDelegateAction],
+            estimated_cost: Option<u128>,
+        ) -> Result<Vec<PermissionDenial>> {
+            let access_key = self
+                .view_access_key(RpcViewAccessKeyRequest::FinalityAccountId {
+                    account_id: signer_id.clone(),
+                    finality: Finality::Final,
+                    public_key: public_key.clone(),
+                })
+                .await?;
+            Ok(check_access_key(
+                &access_key.permission,
+                receiver_id,
+                actions,
+                estimated_cost,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+
+    fn function_call(method_name: &str, deposit: &str) -> NonDelegateAction {
+        NonDelegateAction::FunctionCall(FunctionCallAction {
+            args: String::new(),
+            deposit: NearToken(deposit.to_string()),
+            gas: NearGas(30_000_000_000_000),
+            method_name: method_name.to_string(),
+        })
+    }
+
+    fn permission(allowance: Option<&str>, method_names: &[&str]) -> AccessKeyPermissionView {
+        AccessKeyPermissionView::FunctionCall {
+            allowance: allowance.map(|a| NearToken(a.to_string())),
+            method_names: method_names.iter().map(|m| m.to_string()).collect(),
+            receiver_id: "app.near".to_string(),
+        }
+    }
+
+    fn receiver(id: &str) -> AccountId {
+        id.parse().unwrap()
+    }
+
+    #[test]
+    fn full_access_allows_anything() {
+        let actions = vec![
+            NonDelegateAction::Transfer(TransferAction {
+                deposit: NearToken("5".to_string()),
+            }),
+            function_call("anything", "1"),
+        ];
+        assert!(
+            check_access_key(
+                &AccessKeyPermissionView::FullAccess,
+                &receiver("other.near"),
+                &actions,
+                Some(u128::MAX),
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn matching_function_call_is_allowed() {
+        let denials = check_access_key(
+            &permission(Some("1000"), &["set", "get"]),
+            &receiver("app.near"),
+            &[function_call("set", "0")],
+            Some(1000),
+        );
+        assert!(denials.is_empty(), "{denials:?}");
+    }
+
+    #[test]
+    fn empty_method_list_allows_any_method() {
+        let denials = check_access_key(
+            &permission(None, &[]),
+            &receiver("app.near"),
+            &[function_call("whatever", "0")],
+            Some(u128::MAX),
+        );
+        assert!(denials.is_empty(), "{denials:?}");
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let denials = check_access_key(
+            &permission(Some("10"), &["set"]),
+            &receiver("other.near"),
+            &[function_call("delete", "1")],
+            Some(11),
+        );
+        assert_eq!(
+            denials,
+            vec![
+                PermissionDenial::ReceiverMismatch {
+                    ak_receiver: "app.near".to_string(),
+                    tx_receiver: receiver("other.near"),
+                },
+                PermissionDenial::DepositWithFunctionCall {
+                    method_name: "delete".to_string(),
+                    deposit: NearToken("1".to_string()),
+                },
+                PermissionDenial::MethodNameMismatch {
+                    method_name: "delete".to_string(),
+                    allowed: vec!["set".to_string()],
+                },
+                PermissionDenial::NotEnoughAllowance {
+                    allowance: NearToken("10".to_string()),
+                    cost: NearToken("11".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn non_function_call_actions_require_full_access() {
+        let denials = check_access_key(
+            &permission(None, &[]),
+            &receiver("app.near"),
+            &[
+                function_call("set", "0"),
+                NonDelegateAction::Transfer(TransferAction {
+                    deposit: NearToken("1".to_string()),
+                }),
+            ],
+            None,
+        );
+        assert_eq!(
+            denials,
+            vec![
+                PermissionDenial::RequiresFullAccess { count: 2 },
+                PermissionDenial::NotAFunctionCall {
+                    index: 1,
+                    action: "Transfer",
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,593 @@
+//! Transaction and receipt streaming per account.
+//!
+//! [`NearRpcClient::account_stream`] scans the chunks of every block from a
+//! [block stream](crate::block_stream) and yields the transactions and receipts
+//! that touch a set of accounts — as signer, receiver or predecessor — joined
+//! with their [`ExecutionOutcomeWithIdView`]s.
+//!
+//! Outcomes are looked up when the item is streamed: transaction outcomes via
+//! `tx`, receipt outcomes via `light_client_proof` against the latest final
+//! block. A receipt executes in a later block than the chunk that carries it,
+//! so when the stream is close to the head its outcome may not exist yet and
+//! is reported as `None`. A lookup that fails for another reason leaves the
+//! outcome `None` too, and its error is yielded right after the event, so no
+//! matched event is lost to it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use near_openrpc_client::{NearRpcClient, account_stream::*, block_stream::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     let config = AccountStreamConfig::new(
+//!         BlockStreamConfig::new(StartAt::Height(150_000_000)).with_end_height(150_000_100),
+//!         ["wrap.near".parse().unwrap()],
+//!     );
+//!
+//!     let mut activity = std::pin::pin!(client.account_stream(config));
+//!     while let Some(event) = activity.next().await {
+//!         match event? {
+//!             AccountEvent::Transaction(tx) => println!("tx {}", tx.transaction.hash),
+//!             AccountEvent::Receipt(receipt) => println!("receipt {}", receipt.receipt.receipt_id),
+//!             AccountEvent::Reorg { .. } => println!("reorg"),
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt, stream};
+
+use crate::block_stream::{BlockCheckpoint, BlockEvent, BlockStreamConfig, StreamedBlock};
+use crate::client::{Error, NearRpcClient, Result};
+use crate::types::*;
+
+/// How an account takes part in a transaction or receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountRole {
+    /// Signed the transaction, or the transaction that led to the receipt.
+    Signer,
+    /// Receives the transaction or receipt.
+    Receiver,
+    /// Sent the receipt.
+    Predecessor,
+}
+
+/// A transaction touching a watched account.
+#[derive(Debug, Clone)]
+pub struct TransactionActivity {
+    /// The block whose chunk includes the transaction.
+    pub block: BlockCheckpoint,
+    pub shard_id: ShardId,
+    pub transaction: SignedTransactionView,
+    /// Watched accounts and the roles they play.
+    pub matches: Vec<(AccountId, AccountRole)>,
+    pub outcome: Option<ExecutionOutcomeWithIdView>,
+}
+
+/// A receipt touching a watched account.
+#[derive(Debug, Clone)]
+pub struct ReceiptActivity {
+    /// The block whose chunk includes the receipt.
+    pub block: BlockCheckpoint,
+    pub shard_id: ShardId,
+    pub receipt: ReceiptView,
+    /// Watched accounts and the roles they play.
+    pub matches: Vec<(AccountId, AccountRole)>,
+    pub outcome: Option<ExecutionOutcomeWithIdView>,
+}
+
+/// An item of an account stream.
+#[derive(Debug, Clone)]
+pub enum AccountEvent {
+    Transaction(Box<TransactionActivity>),
+    Receipt(Box<ReceiptActivity>),
+    /// Passed through from the block stream: events from the `dropped` blocks
+    /// are no longer part of the chain.
+    Reorg {
+        common_ancestor: BlockCheckpoint,
+        dropped: Vec<BlockCheckpoint>,
+    },
+}
+
+/// Configuration for [`NearRpcClient::account_stream`].
+#[derive(Debug, Clone)]
+pub struct AccountStreamConfig {
+    /// The underlying block stream. Chunks are always fetched.
+    pub blocks: BlockStreamConfig,
+    pub accounts: HashSet<AccountId>,
+    /// Look up execution outcomes for each matched item.
+    pub fetch_outcomes: bool,
+    /// Maximum concurrent outcome lookups per block.
+    pub outcome_concurrency: usize,
+}
+
+impl AccountStreamConfig {
+    /// Create a config watching `accounts` over the blocks of `blocks`, with
+    /// outcome lookups enabled.
+    pub fn new(blocks: BlockStreamConfig, accounts: impl IntoIterator<Item = AccountId>) -> Self {
+        Self {
+            blocks: blocks.with_chunks(true),
+            accounts: accounts.into_iter().collect(),
+            fetch_outcomes: true,
+            outcome_concurrency: 8,
+        }
+    }
+
+    /// Enable or disable execution outcome lookups.
+    pub fn with_outcomes(mut self, fetch_outcomes: bool) -> Self {
+        self.fetch_outcomes = fetch_outcomes;
+        self
+    }
+
+    /// Look up at most `concurrency` outcomes at once.
+    pub fn with_outcome_concurrency(mut self, concurrency: usize) -> Self {
+        self.outcome_concurrency = concurrency.max(1);
+        self
+    }
+}
+
+impl NearRpcClient {
+    /// Returns a stream of the transactions and receipts touching the accounts
+    /// in `config`, in block and shard order.
+    ///
+    /// Errors are yielded as `Err` items; see [`NearRpcClient::block_stream`].
+    pub fn account_stream(
+        &self,
+        config: AccountStreamConfig,
+    ) -> impl Stream<Item = Result<AccountEvent>> + '_ {
+        let AccountStreamConfig {
+            blocks,
+            accounts,
+            fetch_outcomes,
+            outcome_concurrency,
+        } = config;
+        let accounts = Arc::new(accounts);
+
+        self.block_stream(blocks.with_chunks(true))
+            .then(move |event| {
+                let accounts = Arc::clone(&accounts);
+                async move {
+                    let events = match event {
+                        Err(err) => vec![Err(err)],
+                        Ok(BlockEvent::Reorg {
+                            common_ancestor,
+                            dropped,
+                        }) => vec![Ok(AccountEvent::Reorg {
+                            common_ancestor,
+                            dropped,
+                        })],
+                        Ok(BlockEvent::Block(block)) => {
+                            let events = match_block(&block, &accounts);
+                            if fetch_outcomes && !events.is_empty() {
+                                self.join_outcomes(events, outcome_concurrency).await
+                            } else {
+                                events.into_iter().map(Ok).collect()
+                            }
+                        }
+                    };
+                    stream::iter(events)
+                }
+            })
+            .flatten()
+    }
+
+    /// Joins each event with its outcome, yielding each failed lookup as an
+    /// `Err` after the event it was for.
+    async fn join_outcomes(
+        &self,
+        events: Vec<AccountEvent>,
+        concurrency: usize,
+    ) -> Vec<Result<AccountEvent>> {
+        let mut items = Vec::with_capacity(events.len());
+        let needs_head = events
+            .iter()
+            .any(|event| matches!(event, AccountEvent::Receipt(_)));
+        let head = if needs_head {
+            match self.block(RpcBlockRequest::Finality(Finality::Final)).await {
+                Ok(head) => Some(head.header.hash),
+                Err(err) => {
+                    items.push(Err(err));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let outcomes: Vec<Result<Option<ExecutionOutcomeWithIdView>>> = stream::iter(events.iter())
+            .map(|event| self.lookup_outcome(event, head.as_ref()))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+
+        for (mut event, outcome) in events.into_iter().zip(outcomes) {
+            let (outcome, err) = match outcome {
+                Ok(outcome) => (outcome, None),
+                Err(err) => (None, Some(err)),
+            };
+            match &mut event {
+                AccountEvent::Transaction(tx) => tx.outcome = outcome,
+                AccountEvent::Receipt(receipt) => receipt.outcome = outcome,
+                AccountEvent::Reorg { .. } => {}
+            }
+            items.push(Ok(event));
+            items.extend(err.map(Err));
+        }
+        items
+    }
+
+    async fn lookup_outcome(
+        &self,
+        event: &AccountEvent,
+        head: Option<&CryptoHash>,
+    ) -> Result<Option<ExecutionOutcomeWithIdView>> {
+        // Causes meaning the node knows nothing about this execution (yet).
+        let (result, unknown_causes): (_, &[&str]) = match (event, head) {
+            (AccountEvent::Transaction(tx), _) => (
+                self.tx(RpcTransactionStatusRequest::TxHashSenderAccountId {
+                    sender_account_id: tx.transaction.signer_id.clone(),
+                    tx_hash: tx.transaction.hash.clone(),
+                    wait_until: TxExecutionStatus::IncludedFinal,
+                })
+                .await
+                .map(|response| match response {
+                    RpcTransactionResponse::FinalExecutionOutcomeWithReceiptView {
+                        transaction_outcome,
+                        ..
+                    }
+                    | RpcTransactionResponse::FinalExecutionOutcomeView {
+                        transaction_outcome,
+                        ..
+                    } => Some(transaction_outcome),
+                    RpcTransactionResponse::Empty { .. } => None,
+                }),
+                &["UNKNOWN_TRANSACTION"],
+            ),
+            // `light_client_proof` reports an unknown receipt as
+            // UNKNOWN_TRANSACTION_OR_RECEIPT, and one executed after `head`
+            // as NOT_CONFIRMED.
+            (AccountEvent::Receipt(receipt), Some(head)) => (
+                self.light_client_proof(RpcLightClientExecutionProofRequest::Receipt {
+                    light_client_head: head.clone(),
+                    receipt_id: receipt.receipt.receipt_id.clone(),
+                    receiver_id: receipt.receipt.receiver_id.clone(),
+                })
+                .await
+                .map(|proof| Some(proof.outcome_proof)),
+                &[
+                    "UNKNOWN_RECEIPT",
+                    "UNKNOWN_TRANSACTION_OR_RECEIPT",
+                    "NOT_CONFIRMED",
+                ],
+            ),
+            _ => (Ok(None), &[]),
+        };
+
+        match result {
+            Err(Error::Rpc(err))
+                if err.is_handler_error()
+                    && err
+                        .cause_name()
+                        .is_some_and(|cause| unknown_causes.contains(&cause)) =>
+            {
+                Ok(None)
+            }
+            other => other,
+        }
+    }
+}
+
+/// Returns the watched accounts in `candidates`, each with its role.
+fn roles(
+    accounts: &HashSet<AccountId>,
+    candidates: &[(&AccountId, AccountRole)],
+) -> Vec<(AccountId, AccountRole)> {
+    let mut matches = Vec::new();
+    for (account_id, role) in candidates {
+        if accounts.contains(*account_id) && !matches.contains(&((*account_id).clone(), *role)) {
+            matches.push(((*account_id).clone(), *role));
+        }
+    }
+    matches
+}
+
+/// Finds the transactions and receipts in `block`'s chunks that touch `accounts`.
+fn match_block(block: &StreamedBlock, accounts: &HashSet<AccountId>) -> Vec<AccountEvent> {
+    let checkpoint = block.checkpoint();
+    let mut events = Vec::new();
+
+    for chunk in &block.chunks {
+        let shard_id = chunk.header.shard_id.clone();
+
+        for transaction in &chunk.transactions {
+            let matches = roles(
+                accounts,
+                &[
+                    (&transaction.signer_id, AccountRole::Signer),
+                    (&transaction.receiver_id, AccountRole::Receiver),
+                ],
+            );
+            if !matches.is_empty() {
+                events.push(AccountEvent::Transaction(Box::new(TransactionActivity {
+                    block: checkpoint.clone(),
+                    shard_id: shard_id.clone(),
+                    transaction: transaction.clone(),
+                    matches,
+                    outcome: None,
+                })));
+            }
+        }
+
+        for receipt in &chunk.receipts {
+            let mut candidates = vec![
+                (&receipt.predecessor_id, AccountRole::Predecessor),
+                (&receipt.receiver_id, AccountRole::Receiver),
+            ];
+            if let ReceiptEnumView::Action { signer_id, .. } = &receipt.receipt {
+                candidates.push((signer_id, AccountRole::Signer));
+            }
+            let matches = roles(accounts, &candidates);
+            if !matches.is_empty() {
+                events.push(AccountEvent::Receipt(Box::new(ReceiptActivity {
+                    block: checkpoint.clone(),
+                    shard_id: shard_id.clone(),
+                    receipt: receipt.clone(),
+                    matches,
+                    outcome: None,
+                })));
+            }
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "testing")]
+    use crate::test_utils::{HASH, MockChain, block, chunk};
+    use crate::test_utils::{block_header, chunk_header};
+    #[cfg(feature = "testing")]
+    use crate::testing::{MockRpc, Reply};
+
+    fn streamed_block() -> StreamedBlock {
+        let chunk: RpcChunkResponse = serde_json::from_value(serde_json::json!({
+            "author": "node.near",
+            "header": chunk_header(3, 10),
+            "transactions": [
+                {
+                    "actions": [],
+                    "hash": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1",
+                    "nonce": 1,
+                    "public_key": "ed25519:11111111111111111111111111111111",
+                    "receiver_id": "app.near",
+                    "signature": "ed25519:1111111111111111111111111111111111111111111111111111111111111111",
+                    "signer_id": "alice.near"
+                },
+                {
+                    "actions": [],
+                    "hash": "4reLvkAWfqk5fsqio1KLudk46cqRz9erQdaHkWZKMJDZ",
+                    "nonce": 2,
+                    "public_key": "ed25519:11111111111111111111111111111111",
+                    "receiver_id": "other.near",
+                    "signature": "ed25519:1111111111111111111111111111111111111111111111111111111111111111",
+                    "signer_id": "carol.near"
+                }
+            ],
+            "receipts": [
+                {
+                    "predecessor_id": "app.near",
+                    "receipt": {
+                        "Action": {
+                            "actions": [],
+                            "gas_price": "100",
+                            "input_data_ids": [],
+                            "output_data_receivers": [],
+                            "signer_id": "alice.near",
+                            "signer_public_key": "ed25519:11111111111111111111111111111111"
+                        }
+                    },
+                    "receipt_id": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1",
+                    "receiver_id": "bob.near"
+                }
+            ]
+        }))
+        .unwrap();
+
+        let block: RpcBlockResponse = serde_json::from_value(serde_json::json!({
+            "author": "node.near",
+            "chunks": [chunk_header(3, 10)],
+            "header": block_header(10, &[true]),
+        }))
+        .unwrap();
+
+        StreamedBlock {
+            block,
+            chunks: vec![chunk],
+        }
+    }
+
+    fn accounts(ids: &[&str]) -> HashSet<AccountId> {
+        ids.iter().map(|id| id.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn matches_transactions_and_receipts_by_role() {
+        let events = match_block(&streamed_block(), &accounts(&["alice.near"]));
+        assert_eq!(events.len(), 2);
+
+        let AccountEvent::Transaction(tx) = &events[0] else {
+            panic!("expected transaction, got {:?}", events[0]);
+        };
+        assert_eq!(tx.block.height, 10);
+        assert_eq!(tx.shard_id.0, 3);
+        assert_eq!(
+            tx.matches,
+            vec![("alice.near".parse().unwrap(), AccountRole::Signer)]
+        );
+
+        let AccountEvent::Receipt(receipt) = &events[1] else {
+            panic!("expected receipt, got {:?}", events[1]);
+        };
+        assert_eq!(
+            receipt.matches,
+            vec![("alice.near".parse().unwrap(), AccountRole::Signer)]
+        );
+    }
+
+    #[test]
+    fn matches_receipt_predecessor_and_receiver() {
+        let events = match_block(&streamed_block(), &accounts(&["app.near", "bob.near"]));
+        let roles: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                AccountEvent::Transaction(tx) => tx.matches.clone(),
+                AccountEvent::Receipt(receipt) => receipt.matches.clone(),
+                AccountEvent::Reorg { .. } => Vec::new(),
+            })
+            .collect();
+        assert_eq!(
+            roles,
+            vec![
+                vec![("app.near".parse().unwrap(), AccountRole::Receiver)],
+                vec![
+                    ("app.near".parse().unwrap(), AccountRole::Predecessor),
+                    ("bob.near".parse().unwrap(), AccountRole::Receiver),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_unrelated_accounts() {
+        assert!(match_block(&streamed_block(), &accounts(&["dave.near"])).is_empty());
+    }
+
+    /// A mock node whose only block, at height 10, holds a transaction and
+    /// a receipt signed by alice.near.
+    #[cfg(feature = "testing")]
+    fn mock_node() -> MockRpc {
+        let mock = MockRpc::new();
+        let chain = MockChain::default();
+        chain.serve(&mock);
+        chain.push(block(10, "block10", "block9"));
+
+        let block = streamed_block();
+        mock.on(
+            "chunk",
+            Reply::Result(chunk(
+                chunk_header(0, 10),
+                serde_json::to_value(&block.chunks[0].transactions[..1]).unwrap(),
+                serde_json::to_value(&block.chunks[0].receipts).unwrap(),
+            )),
+        );
+        mock
+    }
+
+    #[cfg(feature = "testing")]
+    fn tx_response() -> serde_json::Value {
+        let transaction = &streamed_block().chunks[0].transactions[0];
+        serde_json::json!({
+            "final_execution_status": "FINAL",
+            "receipts_outcome": [],
+            "status": { "SuccessValue": "" },
+            "transaction": transaction,
+            "transaction_outcome": {
+                "block_hash": HASH,
+                "id": transaction.hash,
+                "outcome": {
+                    "executor_id": "alice.near",
+                    "gas_burnt": 1,
+                    "logs": [],
+                    "receipt_ids": [],
+                    "status": { "SuccessValue": "" },
+                    "tokens_burnt": "0"
+                },
+                "proof": []
+            }
+        })
+    }
+
+    #[cfg(feature = "testing")]
+    fn config() -> AccountStreamConfig {
+        AccountStreamConfig::new(
+            BlockStreamConfig::new(crate::block_stream::StartAt::Height(10)).with_end_height(10),
+            ["alice.near".parse().unwrap()],
+        )
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn reports_unknown_executions_without_outcomes() {
+        let mock = mock_node();
+        mock.on("tx", Reply::result(tx_response())).on(
+            "light_client_proof",
+            Reply::handler_error(
+                "UNKNOWN_TRANSACTION_OR_RECEIPT",
+                serde_json::json!({ "transaction_or_receipt_id": HASH }),
+            ),
+        );
+        let client = mock.client();
+
+        let events: Vec<_> = client.account_stream(config()).collect().await;
+        let [
+            Ok(AccountEvent::Transaction(tx)),
+            Ok(AccountEvent::Receipt(receipt)),
+        ] = &events[..]
+        else {
+            panic!("expected a transaction and a receipt, got {events:?}");
+        };
+        assert_eq!(
+            tx.outcome.as_ref().unwrap().id,
+            tx.transaction.hash,
+            "transaction outcome is joined"
+        );
+        assert!(receipt.outcome.is_none());
+        assert_eq!(
+            mock.params("light_client_proof")[0]["light_client_head"],
+            "block10"
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn yields_other_lookup_errors_after_their_events() {
+        let mock = mock_node();
+        mock.on(
+            "tx",
+            Reply::handler_error(
+                "UNKNOWN_TRANSACTION",
+                serde_json::json!({ "requested_transaction_hash": HASH }),
+            ),
+        )
+        .on(
+            "light_client_proof",
+            Reply::handler_error(
+                "UNAVAILABLE_SHARD",
+                serde_json::json!({ "transaction_or_receipt_id": HASH, "shard_id": 0 }),
+            ),
+        );
+        let client = mock.client();
+
+        let events: Vec<_> = client.account_stream(config()).collect().await;
+        let [
+            Ok(AccountEvent::Transaction(tx)),
+            Ok(AccountEvent::Receipt(receipt)),
+            Err(Error::Rpc(err)),
+        ] = &events[..]
+        else {
+            panic!("expected both events and then the lookup error, got {events:?}");
+        };
+        assert!(tx.outcome.is_none());
+        assert!(receipt.outcome.is_none());
+        assert_eq!(err.cause_name(), Some("UNAVAILABLE_SHARD"));
+    }
+}
@@ -0,0 +1,669 @@
+//! Notifications for changes to watched accounts.
+//!
+//! The watcher follows final blocks and asks `EXPERIMENTAL_changes_in_block`
+//! which accounts each block touched. Only for watched accounts that were
+//! touched does it fetch the detailed changes (see
+//! [`NearRpcClient::changes_for`]), which it turns into [`AccountActivity`]
+//! events: balance changes, keys added or removed, contract deployments and
+//! storage writes.
+//!
+//! [`NearRpcClient::account_activity`] returns the events as a stream;
+//! [`spawn_account_watcher`] runs it on a background task and delivers them
+//! on a channel.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use near_openrpc_client::{NearRpcClient, account_watcher::*, block_stream::StartAt};
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let client = Arc::new(NearRpcClient::mainnet());
+//!     let config = AccountWatcherConfig::new(StartAt::Head, ["wrap.near".parse().unwrap()]);
+//!
+//!     let mut events = spawn_account_watcher(client, config, 64);
+//!     while let Some(event) = events.recv().await {
+//!         match event {
+//!             Ok(activity) => println!("{}: {:?}", activity.account_id, activity.kind),
+//!             Err(err) => eprintln!("watcher error: {err}"),
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use futures::{Stream, StreamExt, stream};
+use tokio::sync::mpsc;
+
+use crate::block_stream::{BlockCheckpoint, BlockEvent, BlockStreamConfig, StartAt};
+use crate::client::{Error, NearRpcClient, Result};
+use crate::state_changes::{AccessKeyChange, AccountChange, CodeChange, DataChange, StateChanges};
+use crate::types::*;
+
+/// Errors specific to the account watcher.
+#[derive(Debug, thiserror::Error)]
+pub enum AccountWatcherError {
+    #[error("invalid balance: {0}")]
+    InvalidBalance(String),
+}
+
+/// What happened to a watched account.
+#[derive(Debug, Clone)]
+pub enum ActivityKind {
+    /// The account's liquid balance changed over the block.
+    BalanceChanged {
+        before: u128,
+        after: u128,
+    },
+    AccountDeleted,
+    KeyAdded {
+        public_key: PublicKey,
+        access_key: AccessKeyView,
+    },
+    KeyRemoved {
+        public_key: PublicKey,
+    },
+    CodeDeployed {
+        code: Vec<u8>,
+    },
+    CodeRemoved,
+    DataWritten {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    DataDeleted {
+        key: Vec<u8>,
+    },
+}
+
+/// A change to a watched account.
+#[derive(Debug, Clone)]
+pub struct AccountActivity {
+    /// The block in which the change happened.
+    pub block: BlockCheckpoint,
+    pub account_id: AccountId,
+    /// What caused the change; for balance changes, the last change in the block.
+    pub cause: StateChangeCauseView,
+    pub kind: ActivityKind,
+}
+
+impl AccountActivity {
+    /// For balance changes, returns `after - before` in yoctoNEAR.
+    pub fn balance_delta(&self) -> Option<i128> {
+        match self.kind {
+            ActivityKind::BalanceChanged { before, after } => Some(after as i128 - before as i128),
+            _ => None,
+        }
+    }
+}
+
+/// Configuration for [`NearRpcClient::account_activity`].
+#[derive(Debug, Clone)]
+pub struct AccountWatcherConfig {
+    /// The underlying block stream. Blocks are always followed at final
+    /// finality, without chunks.
+    pub blocks: BlockStreamConfig,
+    pub accounts: HashSet<AccountId>,
+    /// Only report storage writes under this key prefix.
+    pub data_prefix: Vec<u8>,
+}
+
+impl AccountWatcherConfig {
+    /// Create a config watching `accounts` from `start`.
+    pub fn new(start: StartAt, accounts: impl IntoIterator<Item = AccountId>) -> Self {
+        Self {
+            blocks: BlockStreamConfig::new(start),
+            accounts: accounts.into_iter().collect(),
+            data_prefix: Vec::new(),
+        }
+    }
+
+    /// Use `blocks` for the underlying block stream.
+    pub fn with_blocks(mut self, blocks: BlockStreamConfig) -> Self {
+        self.blocks = blocks;
+        self
+    }
+
+    /// Only report storage writes to keys starting with `prefix`.
+    pub fn with_data_prefix(mut self, prefix: impl Into<Vec<u8>>) -> Self {
+        self.data_prefix = prefix.into();
+        self
+    }
+}
+
+/// Runs [`NearRpcClient::account_activity`] on a background task and sends
+/// its events on a channel with room for `capacity` events.
+///
+/// The task stops when the receiver is dropped.
+pub fn spawn_account_watcher(
+    client: Arc<NearRpcClient>,
+    config: AccountWatcherConfig,
+    capacity: usize,
+) -> mpsc::Receiver<Result<AccountActivity>> {
+    let (sender, receiver) = mpsc::channel(capacity.max(1));
+    tokio::spawn(async move {
+        let mut events = std::pin::pin!(client.account_activity(config));
+        while let Some(event) = events.next().await {
+            if sender.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+struct Watcher<'a> {
+    client: &'a NearRpcClient,
+    accounts: HashSet<AccountId>,
+    data_prefix: StoreKey,
+    /// Last known liquid balance of watched accounts.
+    balances: HashMap<AccountId, u128>,
+    /// Last known access keys of watched accounts.
+    keys: HashMap<AccountId, HashSet<PublicKey>>,
+}
+
+impl NearRpcClient {
+    /// Returns a stream of changes to the accounts in `config`, in block
+    /// order.
+    ///
+    /// Errors are yielded as `Err` items; see [`NearRpcClient::block_stream`].
+    pub fn account_activity(
+        &self,
+        config: AccountWatcherConfig,
+    ) -> impl Stream<Item = Result<AccountActivity>> + '_ {
+        let watcher = Watcher {
+            client: self,
+            accounts: config.accounts,
+            data_prefix: StoreKey(STANDARD.encode(&config.data_prefix)),
+            balances: HashMap::new(),
+            keys: HashMap::new(),
+        };
+        let blocks = self.block_stream(
+            config
+                .blocks
+                .with_finality(Finality::Final)
+                .with_chunks(false),
+        );
+
+        // A block whose activity failed is retried on the next poll, since
+        // the block stream has already moved past it.
+        stream::unfold(
+            (blocks.boxed(), watcher, None),
+            |(mut blocks, mut watcher, failed)| async move {
+                let block = match failed {
+                    Some(block) => block,
+                    None => match blocks.next().await? {
+                        Ok(BlockEvent::Block(block)) => block.block,
+                        // Final blocks are never reorganised.
+                        Ok(BlockEvent::Reorg { .. }) => {
+                            return Some((stream::iter(Vec::new()), (blocks, watcher, None)));
+                        }
+                        Err(err) => {
+                            return Some((stream::iter(vec![Err(err)]), (blocks, watcher, None)));
+                        }
+                    },
+                };
+                let (events, failed) = match watcher.block_activity(&block).await {
+                    Ok(events) => (events.into_iter().map(Ok).collect(), None),
+                    Err(err) => (vec![Err(err)], Some(block)),
+                };
+                Some((stream::iter(events), (blocks, watcher, failed)))
+            },
+        )
+        .flatten()
+    }
+}
+
+impl Watcher<'_> {
+    /// Returns the activity of watched accounts in `block`. If this fails,
+    /// the known balances and keys are left as they were, so the block can
+    /// be retried.
+    async fn block_activity(&mut self, block: &RpcBlockResponse) -> Result<Vec<AccountActivity>> {
+        let balances = self.balances.clone();
+        let keys = self.keys.clone();
+        let result = self.try_block_activity(block).await;
+        if result.is_err() {
+            self.balances = balances;
+            self.keys = keys;
+        }
+        result
+    }
+
+    async fn try_block_activity(
+        &mut self,
+        block: &RpcBlockResponse,
+    ) -> Result<Vec<AccountActivity>> {
+        let block_id = BlockId::CryptoHash(block.header.hash.clone());
+        let touched = self
+            .client
+            .changes_in_block(RpcStateChangesInBlockRequest::BlockId(block_id.clone()))
+            .await?;
+
+        let mut account_ids = Vec::new();
+        let mut key_ids = Vec::new();
+        let mut code_ids = Vec::new();
+        let mut data_ids = Vec::new();
+        for kind in touched.changes {
+            let (ids, account_id) = match kind {
+                StateChangeKindView::AccountTouched(id) => (&mut account_ids, id),
+                StateChangeKindView::AccessKeyTouched(id) => (&mut key_ids, id),
+                StateChangeKindView::ContractCodeTouched(id) => (&mut code_ids, id),
+                StateChangeKindView::DataTouched(id) => (&mut data_ids, id),
+            };
+            if self.accounts.contains(&account_id) && !ids.contains(&account_id) {
+                ids.push(account_id);
+            }
+        }
+
+        let requests = [
+            (!account_ids.is_empty())
+                .then_some(StateChangesRequestView::AccountChanges { account_ids }),
+            (!key_ids.is_empty()).then_some(StateChangesRequestView::AllAccessKeyChanges {
+                account_ids: key_ids,
+            }),
+            (!code_ids.is_empty()).then_some(StateChangesRequestView::ContractCodeChanges {
+                account_ids: code_ids,
+            }),
+            (!data_ids.is_empty()).then(|| StateChangesRequestView::DataChanges {
+                account_ids: data_ids,
+                key_prefix_base64: self.data_prefix.clone(),
+            }),
+        ];
+
+        let checkpoint = BlockCheckpoint {
+            height: block.header.height,
+            hash: block.header.hash.clone(),
+        };
+        let mut events = Vec::new();
+        for request in requests.into_iter().flatten() {
+            let changes = self
+                .client
+                .changes_for(RpcBlockRequest::BlockId(block_id.clone()), request)
+                .await?;
+            self.collect(&checkpoint, &block.header.prev_hash, changes, &mut events)
+                .await?;
+        }
+        Ok(events)
+    }
+
+    async fn collect(
+        &mut self,
+        block: &BlockCheckpoint,
+        prev_hash: &CryptoHash,
+        changes: StateChanges,
+        events: &mut Vec<AccountActivity>,
+    ) -> Result<()> {
+        for (account_id, account) in changes.accounts {
+            let mut push = |cause, kind| {
+                events.push(AccountActivity {
+                    block: block.clone(),
+                    account_id: account_id.clone(),
+                    cause,
+                    kind,
+                })
+            };
+
+            if let Some(last) = account.account.last() {
+                match &last.change {
+                    AccountChange::Update(view) => {
+                        let after = parse_balance(&view.amount)?;
+                        let before = match self.balances.get(&account_id) {
+                            Some(balance) => *balance,
+                            None => self.client.balance_at(&account_id, prev_hash).await?,
+                        };
+                        self.balances.insert(account_id.clone(), after);
+                        if before != after {
+                            push(
+                                last.cause.clone(),
+                                ActivityKind::BalanceChanged { before, after },
+                            );
+                        }
+                    }
+                    AccountChange::Deletion => {
+                        self.balances.remove(&account_id);
+                        push(last.cause.clone(), ActivityKind::AccountDeleted);
+                    }
+                }
+            }
+
+            if !account.access_keys.is_empty() && !self.keys.contains_key(&account_id) {
+                let keys = self.client.keys_at(&account_id, prev_hash).await?;
+                self.keys.insert(account_id.clone(), keys);
+            }
+            let keys = self.keys.entry(account_id.clone()).or_default();
+            for change in account.access_keys {
+                match change.change {
+                    // Updates to known keys are nonce bumps and allowance
+                    // changes (spent by signing, refunded by receipts).
+                    AccessKeyChange::Update {
+                        public_key,
+                        access_key,
+                    } => {
+                        if keys.insert(public_key.clone()) {
+                            push(
+                                change.cause,
+                                ActivityKind::KeyAdded {
+                                    public_key,
+                                    access_key,
+                                },
+                            );
+                        }
+                    }
+                    AccessKeyChange::Deletion { public_key } => {
+                        keys.remove(&public_key);
+                        push(change.cause, ActivityKind::KeyRemoved { public_key });
+                    }
+                    AccessKeyChange::GasKeyNonceUpdate { .. } => {}
+                }
+            }
+
+            for change in account.code {
+                let kind = match change.change {
+                    CodeChange::Update(code) => ActivityKind::CodeDeployed { code },
+                    CodeChange::Deletion => ActivityKind::CodeRemoved,
+                };
+                push(change.cause, kind);
+            }
+
+            for change in account.data {
+                let kind = match change.change {
+                    DataChange::Update { key, value } => ActivityKind::DataWritten { key, value },
+                    DataChange::Deletion { key } => ActivityKind::DataDeleted { key },
+                };
+                push(change.cause, kind);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NearRpcClient {
+    /// Returns the liquid balance of `account_id` at `block_hash`, or zero if
+    /// the account did not exist yet.
+    async fn balance_at(&self, account_id: &AccountId, block_hash: &CryptoHash) -> Result<u128> {
+        let result = self
+            .view_account(RpcViewAccountRequest::BlockIdAccountId {
+                account_id: account_id.clone(),
+                block_id: BlockId::CryptoHash(block_hash.clone()),
+            })
+            .await;
+        match result {
+            Ok(account) => Ok(parse_balance(&account.amount)?),
+            Err(Error::Rpc(err)) if err.cause_name() == Some("UNKNOWN_ACCOUNT") => Ok(0),
+            Err(Error::LegacyQueryResult(err)) if err.error.contains("does not exist") => Ok(0),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl NearRpcClient {
+    /// Returns the public keys of `account_id` at `block_hash`, or none if
+    /// the account did not exist yet.
+    async fn keys_at(
+        &self,
+        account_id: &AccountId,
+        block_hash: &CryptoHash,
+    ) -> Result<HashSet<PublicKey>> {
+        let result = self
+            .view_access_key_list(RpcViewAccessKeyListRequest::BlockIdAccountId {
+                account_id: account_id.clone(),
+                block_id: BlockId::CryptoHash(block_hash.clone()),
+            })
+            .await;
+        match result {
+            Ok(list) => Ok(list.keys.into_iter().map(|key| key.public_key).collect()),
+            Err(Error::Rpc(err)) if err.cause_name() == Some("UNKNOWN_ACCOUNT") => {
+                Ok(HashSet::new())
+            }
+            Err(Error::LegacyQueryResult(err)) if err.error.contains("does not exist") => {
+                Ok(HashSet::new())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn parse_balance(amount: &NearToken) -> std::result::Result<u128, AccountWatcherError> {
+    amount
+        .parse()
+        .map_err(|_| AccountWatcherError::InvalidBalance(amount.0.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "testing")]
+    use crate::state_changes::{AccountStateChanges, Caused};
+    #[cfg(all(feature = "stream", feature = "testing"))]
+    use crate::test_utils::{HASH, MockChain, block};
+    #[cfg(feature = "testing")]
+    use crate::testing::{MockRpc, Reply};
+
+    #[test]
+    fn balance_delta_is_signed() {
+        let activity = |kind| AccountActivity {
+            block: BlockCheckpoint {
+                height: 1,
+                hash: CryptoHash("11111111111111111111111111111111".to_string()),
+            },
+            account_id: "app.near".parse().unwrap(),
+            cause: StateChangeCauseView::Migration,
+            kind,
+        };
+        assert_eq!(
+            activity(ActivityKind::BalanceChanged {
+                before: 10,
+                after: 4
+            })
+            .balance_delta(),
+            Some(-6)
+        );
+        assert_eq!(activity(ActivityKind::CodeRemoved).balance_delta(), None);
+    }
+
+    #[test]
+    fn config_encodes_data_prefix() {
+        let config = AccountWatcherConfig::new(StartAt::Height(5), ["app.near".parse().unwrap()])
+            .with_data_prefix(b"STATE".to_vec());
+        assert_eq!(config.data_prefix, b"STATE");
+        assert!(
+            config
+                .accounts
+                .contains(&"app.near".parse::<AccountId>().unwrap())
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn reports_only_new_and_removed_keys() {
+        let hash = || CryptoHash("11111111111111111111111111111111".to_string());
+        let key = |byte: char| PublicKey(format!("ed25519:{}", byte.to_string().repeat(32)));
+        let access_key = || -> AccessKeyView {
+            serde_json::from_value(serde_json::json!({
+                "nonce": 7,
+                "permission": { "FunctionCall": {
+                    "allowance": "250000000000000000000000",
+                    "method_names": [],
+                    "receiver_id": "app.near"
+                } }
+            }))
+            .unwrap()
+        };
+        let receipt = || StateChangeCauseView::ReceiptProcessing {
+            receipt_hash: hash(),
+        };
+
+        let mock = MockRpc::new();
+        mock.on(
+            "EXPERIMENTAL_view_access_key_list",
+            Reply::result(serde_json::json!({
+                "block_hash": hash(),
+                "block_height": 9,
+                "keys": [{ "public_key": key('2'), "access_key": access_key() }]
+            })),
+        );
+        let client = mock.client();
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let mut watcher = Watcher {
+            client: &client,
+            accounts: HashSet::from([account_id.clone()]),
+            data_prefix: StoreKey(String::new()),
+            balances: HashMap::new(),
+            keys: HashMap::new(),
+        };
+
+        let update = |public_key, cause| Caused {
+            cause,
+            change: AccessKeyChange::Update {
+                public_key,
+                access_key: access_key(),
+            },
+        };
+        let changes = StateChanges {
+            block_hash: hash(),
+            accounts: [(
+                account_id,
+                AccountStateChanges {
+                    access_keys: vec![
+                        // A gas refund to an existing function call key.
+                        update(key('2'), receipt()),
+                        update(key('3'), receipt()),
+                        Caused {
+                            cause: receipt(),
+                            change: AccessKeyChange::Deletion {
+                                public_key: key('2'),
+                            },
+                        },
+                    ],
+                    ..Default::default()
+                },
+            )]
+            .into(),
+        };
+        let block = BlockCheckpoint {
+            height: 10,
+            hash: hash(),
+        };
+        let mut events = Vec::new();
+        watcher
+            .collect(&block, &hash(), changes, &mut events)
+            .await
+            .unwrap();
+
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|event| match &event.kind {
+                ActivityKind::KeyAdded { public_key, .. } => format!("added {}", public_key.0),
+                ActivityKind::KeyRemoved { public_key } => format!("removed {}", public_key.0),
+                other => format!("{other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                format!("added {}", key('3').0),
+                format!("removed {}", key('2').0)
+            ]
+        );
+        assert_eq!(mock.params("EXPERIMENTAL_view_access_key_list").len(), 1);
+    }
+
+    #[cfg(all(feature = "stream", feature = "testing"))]
+    #[tokio::test]
+    async fn retries_a_failed_block_from_the_balances_before_it() {
+        let mock = MockRpc::new();
+        let chain = MockChain::default();
+        chain.serve(&mock);
+        chain.push(block(10, "block10", "block9"));
+
+        let touched =
+            |account_id| serde_json::json!({ "type": "account_touched", "account_id": account_id });
+        mock.on(
+            "EXPERIMENTAL_changes_in_block",
+            Reply::result(serde_json::json!({
+                "block_hash": "block10",
+                "changes": [touched("alice.near"), touched("bob.near")]
+            })),
+        );
+        let update = |account_id| {
+            serde_json::json!({
+                "type": "account_update",
+                "cause": { "type": "receipt_processing", "receipt_hash": HASH },
+                "change": {
+                    "account_id": account_id,
+                    "amount": "100",
+                    "code_hash": HASH,
+                    "locked": "0",
+                    "storage_usage": 182
+                }
+            })
+        };
+        mock.on(
+            "EXPERIMENTAL_changes",
+            Reply::result(serde_json::json!({
+                "block_hash": "block10",
+                "changes": [update("alice.near"), update("bob.near")]
+            })),
+        );
+        let account = serde_json::json!({
+            "amount": "40",
+            "block_hash": "block9",
+            "block_height": 9,
+            "code_hash": HASH,
+            "locked": "0",
+            "storage_paid_at": 0,
+            "storage_usage": 182
+        });
+        // bob.near's balance lookup fails once, after alice.near's succeeded.
+        let bob_failed = std::sync::atomic::AtomicBool::new(false);
+        mock.on_call("EXPERIMENTAL_view_account", move |params| {
+            if params["account_id"] == "bob.near"
+                && !bob_failed.swap(true, std::sync::atomic::Ordering::Relaxed)
+            {
+                return Reply::error("INTERNAL_ERROR", "INTERNAL_ERROR", serde_json::json!({}));
+            }
+            Reply::result(&account)
+        });
+        let client = mock.client();
+
+        let config = AccountWatcherConfig::new(
+            StartAt::Height(10),
+            ["alice.near".parse().unwrap(), "bob.near".parse().unwrap()],
+        );
+        let config = config
+            .clone()
+            .with_blocks(config.blocks.with_end_height(10));
+        let events: Vec<_> = client.account_activity(config).collect().await;
+        let [Err(Error::Rpc(_)), Ok(alice), Ok(bob)] = &events[..] else {
+            panic!("expected the error and then both balance changes, got {events:?}");
+        };
+        for (activity, account_id) in [(alice, "alice.near"), (bob, "bob.near")] {
+            assert_eq!(activity.account_id.as_str(), account_id);
+            assert_eq!(activity.block.height, 10);
+            assert_eq!(activity.balance_delta(), Some(60));
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn finds_no_keys_for_missing_accounts() {
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let hash = CryptoHash("11111111111111111111111111111111".to_string());
+        for reply in [
+            Reply::handler_error(
+                "UNKNOWN_ACCOUNT",
+                serde_json::json!({ "requested_account_id": "alice.near" }),
+            ),
+            Reply::legacy_query_error("account alice.near does not exist while viewing"),
+        ] {
+            let mock = MockRpc::new();
+            mock.on("EXPERIMENTAL_view_access_key_list", reply);
+            let keys = mock.client().keys_at(&account_id, &hash).await.unwrap();
+            assert!(keys.is_empty());
+        }
+    }
+}
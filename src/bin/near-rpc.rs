@@ -0,0 +1,6 @@
+//! `near-rpc`, a command-line client for NEAR RPC nodes. See
+//! `near_openrpc_client::cli`.
+
+fn main() -> std::process::ExitCode {
+    near_openrpc_client::cli::main()
+}
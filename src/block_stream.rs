@@ -0,0 +1,525 @@
+//! Block streaming for indexers.
+//!
+//! [`NearRpcClient::block_stream`] follows the chain at a chosen [`Finality`],
+//! yielding every block in height order starting at a given height, the current
+//! head, or a saved [`BlockCheckpoint`]. Heights where no block was produced are
+//! skipped. At non-final finality the chain can reorganise; the stream detects
+//! this by checking each block's `prev_hash` against the previous block and
+//! emits a [`BlockEvent::Reorg`] before the blocks of the new fork.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use near_openrpc_client::{NearRpcClient, block_stream::*, types::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     let config = BlockStreamConfig::new(StartAt::Head)
+//!         .with_finality(Finality::Final)
+//!         .with_chunks(true);
+//!
+//!     let mut blocks = std::pin::pin!(client.block_stream(config));
+//!     while let Some(event) = blocks.next().await {
+//!         if let BlockEvent::Block(block) = event? {
+//!             println!("{} with {} chunks", block.block.header.height, block.chunks.len());
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::{Stream, stream};
+use serde::{Deserialize, Serialize};
+
+use crate::client::{Error, NearRpcClient, Result};
+use crate::errors::RpcBlockError;
+use crate::types::*;
+
+/// How many recent blocks are remembered to find the fork point of a reorg.
+const REORG_WINDOW: usize = 64;
+
+/// Errors specific to block streaming.
+#[derive(Debug, thiserror::Error)]
+pub enum BlockStreamError {
+    #[error("reorg at height {height} is deeper than the {REORG_WINDOW} remembered blocks")]
+    ReorgTooDeep { height: u64 },
+}
+
+/// Height and hash of the last block a consumer has processed.
+///
+/// Persist it after handling each event and pass it to [`StartAt::Checkpoint`]
+/// to resume; the stream continues at the next height. If a reorg replaced
+/// the checkpointed block meanwhile, the stream looks up its ancestors by
+/// hash to find where the new fork starts, and reports the reorg as usual.
+/// This needs the node to still serve the abandoned blocks, which nodes keep
+/// until they are garbage collected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockCheckpoint {
+    pub height: u64,
+    pub hash: CryptoHash,
+}
+
+/// Where a block stream starts.
+#[derive(Debug, Clone)]
+pub enum StartAt {
+    /// The current head at the stream's finality.
+    Head,
+    /// The first block at or after this height.
+    Height(u64),
+    /// The block after a previously processed block.
+    Checkpoint(BlockCheckpoint),
+}
+
+/// Configuration for [`NearRpcClient::block_stream`].
+#[derive(Debug, Clone)]
+pub struct BlockStreamConfig {
+    pub start: StartAt,
+    pub finality: Finality,
+    pub fetch_chunks: bool,
+    pub chunk_concurrency: usize,
+    pub poll_interval: Duration,
+    /// Last height to stream; `None` follows the chain indefinitely.
+    pub end_height: Option<u64>,
+}
+
+impl BlockStreamConfig {
+    /// Create a config following final blocks from `start`, without chunks,
+    /// polling for new blocks every second.
+    pub fn new(start: StartAt) -> Self {
+        Self {
+            start,
+            finality: Finality::Final,
+            fetch_chunks: false,
+            chunk_concurrency: 8,
+            poll_interval: Duration::from_secs(1),
+            end_height: None,
+        }
+    }
+
+    /// Follow the chain at `finality` instead of [`Finality::Final`].
+    pub fn with_finality(mut self, finality: Finality) -> Self {
+        self.finality = finality;
+        self
+    }
+
+    /// Fetch the full chunks produced in each block.
+    pub fn with_chunks(mut self, fetch_chunks: bool) -> Self {
+        self.fetch_chunks = fetch_chunks;
+        self
+    }
+
+    /// Fetch at most `concurrency` chunks of a block at once.
+    pub fn with_chunk_concurrency(mut self, concurrency: usize) -> Self {
+        self.chunk_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Wait `interval` before asking for a new head once caught up.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// End the stream after the block at `height` (inclusive), waiting for
+    /// the chain to reach it if needed.
+    pub fn with_end_height(mut self, height: u64) -> Self {
+        self.end_height = Some(height);
+        self
+    }
+}
+
+/// A block, with its new chunks if the stream fetches chunks.
+#[derive(Debug, Clone)]
+pub struct StreamedBlock {
+    pub block: RpcBlockResponse,
+    /// Chunks produced in this block (`height_included == height`), in shard
+    /// order. Empty unless [`BlockStreamConfig::fetch_chunks`] is set.
+    pub chunks: Vec<RpcChunkResponse>,
+}
+
+impl StreamedBlock {
+    /// Returns the checkpoint for this block.
+    pub fn checkpoint(&self) -> BlockCheckpoint {
+        BlockCheckpoint {
+            height: self.block.header.height,
+            hash: self.block.header.hash.clone(),
+        }
+    }
+}
+
+/// An item of a block stream.
+#[derive(Debug, Clone)]
+pub enum BlockEvent {
+    /// The next block of the canonical chain.
+    Block(Box<StreamedBlock>),
+    /// Previously streamed blocks were replaced by a fork. `dropped` lists the
+    /// abandoned blocks, highest first; the new fork's blocks follow as
+    /// [`BlockEvent::Block`] events starting after `common_ancestor`.
+    Reorg {
+        common_ancestor: BlockCheckpoint,
+        dropped: Vec<BlockCheckpoint>,
+    },
+}
+
+struct State<'a> {
+    client: &'a NearRpcClient,
+    config: BlockStreamConfig,
+    /// `None` until the start height is resolved from the head.
+    next_height: Option<u64>,
+    /// Recently streamed blocks, oldest first.
+    recent: VecDeque<BlockCheckpoint>,
+    pending: VecDeque<BlockEvent>,
+    head_height: u64,
+}
+
+impl NearRpcClient {
+    /// Returns a stream of blocks following the chain as described by `config`.
+    ///
+    /// Unless [`BlockStreamConfig::end_height`] is set, the stream never ends
+    /// on its own. RPC errors are yielded as `Err` items
+    /// and the failed step is retried on the next poll, so consumers can log
+    /// transient failures and keep polling.
+    pub fn block_stream(
+        &self,
+        config: BlockStreamConfig,
+    ) -> impl Stream<Item = Result<BlockEvent>> + '_ {
+        let (next_height, recent) = match &config.start {
+            StartAt::Head => (None, VecDeque::new()),
+            StartAt::Height(height) => (Some(*height), VecDeque::new()),
+            StartAt::Checkpoint(checkpoint) => (
+                Some(checkpoint.height + 1),
+                VecDeque::from([checkpoint.clone()]),
+            ),
+        };
+        let state = State {
+            client: self,
+            config,
+            next_height,
+            recent,
+            pending: VecDeque::new(),
+            head_height: 0,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let item = state.next_event().await.transpose()?;
+            Some((item, state))
+        })
+    }
+
+    async fn block_at_height(&self, height: u64) -> Result<Option<RpcBlockResponse>> {
+        match self
+            .block(RpcBlockRequest::BlockId(BlockId::BlockHeight(height)))
+            .await
+        {
+            Ok(block) => Ok(Some(block)),
+            Err(Error::Rpc(err))
+                if matches!(
+                    err.try_cause_as::<RpcBlockError>(),
+                    Some(Ok(RpcBlockError::UnknownBlock { .. }))
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl State<'_> {
+    async fn next_event(&mut self) -> Result<Option<BlockEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            let next_height = match self.next_height {
+                Some(height) => height,
+                None => {
+                    let head = self.head().await?;
+                    self.head_height = head.header.height;
+                    self.next_height = Some(head.header.height);
+                    head.header.height
+                }
+            };
+
+            if self.config.end_height.is_some_and(|end| next_height > end) {
+                return Ok(None);
+            }
+
+            if next_height > self.head_height {
+                self.head_height = self.head().await?.header.height;
+                if next_height > self.head_height {
+                    tokio::time::sleep(self.config.poll_interval).await;
+                    continue;
+                }
+            }
+
+            let Some(block) = self.client.block_at_height(next_height).await? else {
+                // No block was produced at this height.
+                self.next_height = Some(next_height + 1);
+                continue;
+            };
+
+            let expected_prev = self.recent.back().map(|last| last.hash.clone());
+            if expected_prev.is_some_and(|prev| prev != block.header.prev_hash) {
+                self.handle_reorg(block).await?;
+            } else {
+                let streamed = self.fetch_chunks(block).await?;
+                self.push_block(streamed);
+            }
+        }
+    }
+
+    async fn head(&self) -> Result<RpcBlockResponse> {
+        self.client
+            .block(RpcBlockRequest::Finality(self.config.finality))
+            .await
+    }
+
+    /// Walks back from `block` along `prev_hash` until it reaches a remembered
+    /// block, then queues the reorg and the new fork's blocks. Nothing is
+    /// queued unless every block of the fork was fetched, so a failed attempt
+    /// is retried from scratch.
+    ///
+    /// Once the fork reaches below every remembered block, as when resuming
+    /// from a checkpoint that was reorganised away, the parents of the oldest
+    /// remembered block are looked up by hash and remembered too.
+    async fn handle_reorg(&mut self, block: RpcBlockResponse) -> Result<()> {
+        let height = block.header.height;
+        let mut fork = vec![block];
+        let mut looked_up = 0;
+        let ancestor_index = loop {
+            let lowest = &fork.last().expect("fork is never empty").header;
+            if let Some(index) = self.recent.iter().rposition(|b| b.hash == lowest.prev_hash) {
+                break index;
+            }
+            if fork.len() + looked_up > REORG_WINDOW {
+                return Err(BlockStreamError::ReorgTooDeep { height }.into());
+            }
+            if let Some(oldest) = self
+                .recent
+                .front()
+                .filter(|oldest| oldest.height >= lowest.height)
+            {
+                let parent = self.parent(&oldest.hash).await?;
+                self.recent.push_front(BlockCheckpoint {
+                    height: parent.header.height,
+                    hash: parent.header.hash,
+                });
+                looked_up += 1;
+                continue;
+            }
+            let prev_hash = &lowest.prev_hash;
+            let prev = self
+                .client
+                .block(RpcBlockRequest::BlockId(BlockId::CryptoHash(
+                    prev_hash.clone(),
+                )))
+                .await?;
+            fork.push(prev);
+        };
+
+        let mut streamed = Vec::with_capacity(fork.len());
+        for block in fork.into_iter().rev() {
+            streamed.push(self.fetch_chunks(block).await?);
+        }
+
+        let dropped: Vec<_> = self.recent.drain(ancestor_index + 1..).rev().collect();
+        let common_ancestor = self.recent[ancestor_index].clone();
+        self.pending.push_back(BlockEvent::Reorg {
+            common_ancestor,
+            dropped,
+        });
+        for block in streamed {
+            self.push_block(block);
+        }
+        Ok(())
+    }
+
+    /// The parent of the block with `hash`, which need not be canonical.
+    async fn parent(&self, hash: &CryptoHash) -> Result<RpcBlockResponse> {
+        let block = self
+            .client
+            .block(RpcBlockRequest::BlockId(BlockId::CryptoHash(hash.clone())))
+            .await?;
+        self.client
+            .block(RpcBlockRequest::BlockId(BlockId::CryptoHash(
+                block.header.prev_hash,
+            )))
+            .await
+    }
+
+    async fn fetch_chunks(&self, block: RpcBlockResponse) -> Result<StreamedBlock> {
+        let chunks = if self.config.fetch_chunks {
+            self.client
+                .new_chunks(&block, self.config.chunk_concurrency)
+                .await?
+        } else {
+            Vec::new()
+        };
+        Ok(StreamedBlock { block, chunks })
+    }
+
+    fn push_block(&mut self, streamed: StreamedBlock) {
+        self.next_height = Some(streamed.block.header.height + 1);
+        self.recent.push_back(streamed.checkpoint());
+        if self.recent.len() > REORG_WINDOW {
+            self.recent.pop_front();
+        }
+        self.pending
+            .push_back(BlockEvent::Block(Box::new(streamed)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "testing")]
+    use crate::test_utils::{MockChain, block};
+    #[cfg(feature = "testing")]
+    use crate::testing::{MockRpc, Reply};
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let checkpoint = BlockCheckpoint {
+            height: 42,
+            hash: CryptoHash("9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1".to_string()),
+        };
+        let json = serde_json::to_value(&checkpoint).unwrap();
+        assert_eq!(json["height"], 42);
+        assert_eq!(
+            serde_json::from_value::<BlockCheckpoint>(json).unwrap(),
+            checkpoint
+        );
+    }
+
+    #[test]
+    fn config_defaults() {
+        let config = BlockStreamConfig::new(StartAt::Height(10)).with_chunk_concurrency(0);
+        assert!(matches!(config.finality, Finality::Final));
+        assert!(!config.fetch_chunks);
+        assert_eq!(config.chunk_concurrency, 1);
+    }
+
+    /// A chain of blocks `block<height>` from 10 to 13, served by a mock node.
+    #[cfg(feature = "testing")]
+    fn mock_chain() -> (MockRpc, MockChain) {
+        let mock = MockRpc::new();
+        let chain = MockChain::default();
+        chain.serve(&mock);
+        chain.push(block(10, "block10", "block9"));
+        chain.push(block(11, "block11", "block10"));
+        chain.push(block(13, "block13", "block11"));
+        (mock, chain)
+    }
+
+    #[cfg(feature = "testing")]
+    fn describe(event: Result<BlockEvent>) -> String {
+        match event {
+            Ok(BlockEvent::Block(block)) => block.block.header.hash.0.clone(),
+            Ok(BlockEvent::Reorg {
+                common_ancestor,
+                dropped,
+            }) => {
+                let dropped: Vec<_> = dropped.iter().map(|b| b.hash.0.as_str()).collect();
+                format!("reorg to {} dropping {dropped:?}", common_ancestor.hash.0)
+            }
+            Err(_) => "error".to_string(),
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn streams_blocks_skipping_missing_heights() {
+        use futures::TryStreamExt;
+
+        let (mock, _chain) = mock_chain();
+        let client = mock.client();
+        let config = BlockStreamConfig::new(StartAt::Height(10))
+            .with_chunks(true)
+            .with_end_height(13);
+
+        let events: Vec<_> = client.block_stream(config).try_collect().await.unwrap();
+        let blocks: Vec<_> = events
+            .into_iter()
+            .map(|event| match event {
+                BlockEvent::Block(block) => (block.block.header.height, block.chunks.len()),
+                BlockEvent::Reorg { .. } => panic!("unexpected reorg"),
+            })
+            .collect();
+        assert_eq!(blocks, [(10, 1), (11, 1), (13, 1)]);
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn retries_a_failed_reorg_without_reporting_it_twice() {
+        use futures::StreamExt;
+
+        let (mock, chain) = mock_chain();
+        let client = mock.client();
+        let config = BlockStreamConfig::new(StartAt::Height(10))
+            .with_chunks(true)
+            .with_end_height(14);
+        let mut stream = std::pin::pin!(client.block_stream(config));
+        for expected in ["block10", "block11", "block13"] {
+            assert_eq!(describe(stream.next().await.unwrap()), expected);
+        }
+
+        // A fork from block11 replaces block13; fetching the chunk of its
+        // first block fails once.
+        chain.rewind(11);
+        chain.push(block(12, "fork12", "block11"));
+        chain.push(block(14, "fork14", "fork12"));
+        mock.once(
+            "chunk",
+            Reply::error("INTERNAL_ERROR", "INTERNAL_ERROR", serde_json::json!({})),
+        );
+
+        let events: Vec<_> = stream.map(describe).collect().await;
+        assert_eq!(
+            events,
+            [
+                "error",
+                "reorg to block11 dropping [\"block13\"]",
+                "fork12",
+                "fork14",
+            ]
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn resumes_from_a_checkpoint_that_was_reorganised_away() {
+        use futures::StreamExt;
+
+        // The consumer processed block11 and block13 before stopping; then a
+        // fork from block10 replaced both.
+        let (mock, chain) = mock_chain();
+        chain.rewind(10);
+        chain.push(block(11, "fork11", "block10"));
+        chain.push(block(12, "fork12", "fork11"));
+        chain.push(block(14, "fork14", "fork12"));
+        let client = mock.client();
+
+        let checkpoint = BlockCheckpoint {
+            height: 13,
+            hash: CryptoHash("block13".to_string()),
+        };
+        let config = BlockStreamConfig::new(StartAt::Checkpoint(checkpoint)).with_end_height(14);
+        let events: Vec<_> = client.block_stream(config).map(describe).collect().await;
+        assert_eq!(
+            events,
+            [
+                "reorg to block10 dropping [\"block13\", \"block11\"]",
+                "fork11",
+                "fork12",
+                "fork14",
+            ]
+        );
+    }
+}
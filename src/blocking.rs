@@ -0,0 +1,188 @@
+//! A synchronous JSON-RPC client for NEAR Protocol.
+//!
+//! [`NearRpcClient`] has the same RPC methods as the
+//! [async client](crate::client::NearRpcClient) and returns the same
+//! [`Error`] type, blocking the calling thread until the response arrives. It
+//! wraps an async client and drives it on its own single-threaded tokio
+//! runtime, so calls must not be made from inside an async runtime (they
+//! panic there, as with `reqwest::blocking`).
+//!
+//! Helpers built on top of the async client, such as `block_with_chunks` or
+//! `epoch_cursor`, are reachable through [`NearRpcClient::block_on`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::blocking::NearRpcClient;
+//! use near_openrpc_client::types::*;
+//!
+//! fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     let status = client.status()?;
+//!     println!("Chain ID: {}", status.chain_id);
+//!
+//!     let block = client.block_on(|client| {
+//!         client.block_with_chunks(RpcBlockRequest::Finality(Finality::Final))
+//!     })?;
+//!     println!("{} shards", block.shards.len());
+//!     Ok(())
+//! }
+//! ```
+
+use std::future::Future;
+
+use tokio::runtime::Runtime;
+
+use crate::cache::CacheBackend;
+use crate::client::rpc_methods;
+use crate::middleware::Middleware;
+use crate::types::*;
+
+pub use crate::client::{Error, Result};
+
+/// Blocking client for the NEAR Protocol JSON-RPC API.
+pub struct NearRpcClient {
+    inner: crate::client::NearRpcClient,
+    runtime: Runtime,
+}
+
+impl NearRpcClient {
+    /// Create a new client with a custom URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::from_async(crate::client::NearRpcClient::new(url))
+    }
+
+    /// Create a client for NEAR Mainnet.
+    pub fn mainnet() -> Self {
+        Self::from_async(crate::client::NearRpcClient::mainnet())
+    }
+
+    /// Create a client for NEAR Testnet.
+    pub fn testnet() -> Self {
+        Self::from_async(crate::client::NearRpcClient::testnet())
+    }
+
+    /// Create a client for NEAR Betanet.
+    pub fn betanet() -> Self {
+        Self::from_async(crate::client::NearRpcClient::betanet())
+    }
+
+    /// Create a client for local development (localhost:3030).
+    pub fn local() -> Self {
+        Self::from_async(crate::client::NearRpcClient::local())
+    }
+
+    /// Wrap a configured async client.
+    ///
+    /// # Panics
+    ///
+    /// If the tokio runtime cannot be started.
+    pub fn from_async(inner: crate::client::NearRpcClient) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the blocking client's runtime");
+        Self { inner, runtime }
+    }
+
+    /// See [`NearRpcClient::with_network`](crate::client::NearRpcClient::with_network).
+    pub fn with_network(self, network: impl Into<String>) -> Self {
+        Self {
+            inner: self.inner.with_network(network),
+            ..self
+        }
+    }
+
+    /// See [`NearRpcClient::with_cache`](crate::client::NearRpcClient::with_cache).
+    pub fn with_cache(self, backend: impl CacheBackend + 'static) -> Self {
+        Self {
+            inner: self.inner.with_cache(backend),
+            ..self
+        }
+    }
+
+    /// See [`NearRpcClient::with_middleware`](crate::client::NearRpcClient::with_middleware).
+    pub fn with_middleware(self, middleware: impl Middleware + 'static) -> Self {
+        Self {
+            inner: self.inner.with_middleware(middleware),
+            ..self
+        }
+    }
+
+    /// Returns the network name, if known.
+    pub fn network(&self) -> Option<&str> {
+        self.inner.network()
+    }
+
+    /// The wrapped async client.
+    pub fn as_async(&self) -> &crate::client::NearRpcClient {
+        &self.inner
+    }
+
+    /// Runs an async operation on the wrapped client to completion, e.g. one
+    /// of the helpers in other modules that has no blocking counterpart.
+    pub fn block_on<'a, F, T>(&'a self, f: impl FnOnce(&'a crate::client::NearRpcClient) -> F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        self.runtime.block_on(f(&self.inner))
+    }
+}
+
+macro_rules! blocking_methods {
+    ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $request:ty)?) -> $response:ty = $method:literal;)*) => {
+        impl NearRpcClient {
+            $(
+                $(#[$attr])*
+                pub fn $name(&self $(, $arg: $request)?) -> Result<$response> {
+                    self.runtime.block_on(self.inner.$name($($arg)?))
+                }
+            )*
+        }
+    };
+}
+
+rpc_methods!(blocking_methods);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::middleware::{HandleFuture, Next, RpcCall};
+
+    /// Answers `status` with a node error, without touching the network.
+    struct Offline;
+
+    impl Middleware for Offline {
+        fn handle<'a>(&'a self, call: RpcCall, _next: Next<'a>) -> HandleFuture<'a> {
+            Box::pin(async move {
+                Ok(json!({
+                    "jsonrpc": "2.0",
+                    "id": call.id,
+                    "error": {
+                        "code": -32000,
+                        "message": "Server error",
+                        "name": "INTERNAL_ERROR",
+                        "cause": { "name": "NODE_SYNCING", "info": {} },
+                        "data": call.method
+                    }
+                }))
+            })
+        }
+    }
+
+    #[test]
+    fn calls_block_and_return_client_errors() {
+        let client = NearRpcClient::new("http://127.0.0.1:9")
+            .with_network("local")
+            .with_middleware(Offline);
+        assert_eq!(client.network(), Some("local"));
+
+        let Err(Error::Rpc(err)) = client.status() else {
+            panic!("expected an RPC error");
+        };
+        assert_eq!(err.cause_name(), Some("NODE_SYNCING"));
+        assert_eq!(err.data, Some(json!("status")));
+    }
+}
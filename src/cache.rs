@@ -0,0 +1,303 @@
+//! Opt-in caching of immutable RPC responses.
+//!
+//! Some responses can never change once the node has returned them: a block
+//! or chunk looked up by hash, a receipt, the genesis config, a transaction
+//! whose execution is final. [`NearRpcClient::with_cache`] keeps such
+//! responses in a [`CacheBackend`] and serves repeated calls from it.
+//!
+//! Only these responses are cached:
+//!
+//! | Method | Cached when |
+//! |---|---|
+//! | `block`, `EXPERIMENTAL_protocol_config` | the request names a block by hash, or by a height at or below the highest final block seen so far |
+//! | `chunk` | the request names the chunk by hash, or its block as for `block` |
+//! | `tx`, `EXPERIMENTAL_tx_status` | the response's `final_execution_status` is `FINAL` |
+//! | `EXPERIMENTAL_receipt` | always |
+//! | `genesis_config`, `EXPERIMENTAL_genesis_config` | always |
+//!
+//! Requests by [`Finality`](crate::types::Finality) (optimistic or final) or sync checkpoint are never
+//! cached, since the block they resolve to moves. The highest final height is
+//! learned from `block` responses requested at `Finality::Final`; until one
+//! has been seen, lookups by height are not cached.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::{NearRpcClient, cache::LruCache, types::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet().with_cache(LruCache::new(1024));
+//!
+//!     let head = client.block(RpcBlockRequest::Finality(Finality::Final)).await?;
+//!     let by_hash = RpcBlockRequest::BlockId(BlockId::CryptoHash(head.header.hash));
+//!     client.block(by_hash.clone()).await?; // fetched
+//!     client.block(by_hash).await?; // served from the cache
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::Value;
+
+/// Storage for cached responses, keyed by method and parameters.
+///
+/// Implement this to keep responses somewhere other than memory, e.g. on
+/// disk or in a shared store. Values are the JSON `result` of a response.
+pub trait CacheBackend: Send + Sync {
+    fn get(&self, key: &str) -> Option<Value>;
+    fn put(&self, key: String, value: Value);
+}
+
+/// An in-memory [`CacheBackend`] holding at most `capacity` responses,
+/// evicting the least recently used one when full.
+pub struct LruCache {
+    capacity: usize,
+    inner: Mutex<LruInner>,
+}
+
+#[derive(Default)]
+struct LruInner {
+    tick: u64,
+    entries: HashMap<String, (Value, u64)>,
+    /// Keys by the tick they were last used at.
+    order: BTreeMap<u64, String>,
+}
+
+impl LruCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(LruInner::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheBackend for LruCache {
+    fn get(&self, key: &str) -> Option<Value> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let (value, used) = inner.entries.get_mut(key)?;
+        let value = value.clone();
+        let previous = std::mem::replace(used, tick);
+        inner.order.remove(&previous);
+        inner.order.insert(tick, key.to_string());
+        Some(value)
+    }
+
+    fn put(&self, key: String, value: Value) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        if let Some((_, previous)) = inner.entries.insert(key.clone(), (value, tick)) {
+            inner.order.remove(&previous);
+        }
+        inner.order.insert(tick, key);
+        while inner.entries.len() > self.capacity {
+            let Some((_, oldest)) = inner.order.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+    }
+}
+
+/// A [`CacheBackend`] together with the state needed to decide what is
+/// immutable.
+pub(crate) struct ResponseCache {
+    backend: Box<dyn CacheBackend>,
+    final_height: AtomicU64,
+}
+
+/// Whether a request's response may be cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    Never,
+    Always,
+    /// Cacheable once the block at this height is known to be final.
+    AtHeight(u64),
+    /// Cacheable if the transaction's execution is final.
+    FinalOutcome,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(backend: impl CacheBackend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+            final_height: AtomicU64::new(0),
+        }
+    }
+
+    /// The cache key of a request, or `None` if its response is never cached.
+    pub(crate) fn key(&self, method: &str, params: &Value) -> Option<String> {
+        (policy(method, params) != Policy::Never).then(|| format!("{method}:{params}"))
+    }
+
+    /// Returns a cached result for a request, if one is stored.
+    pub(crate) fn get(&self, key: &str) -> Option<Value> {
+        self.backend.get(key)
+    }
+
+    /// Stores `result` if the response to the request is immutable, and
+    /// learns the final height from final block responses.
+    pub(crate) fn store(&self, method: &str, params: &Value, key: Option<String>, result: &Value) {
+        if method == "block"
+            && params.get("finality").and_then(Value::as_str) == Some("final")
+            && let Some(height) = result.pointer("/header/height").and_then(Value::as_u64)
+        {
+            self.final_height.fetch_max(height, Ordering::Relaxed);
+        }
+
+        let Some(key) = key else {
+            return;
+        };
+        let cacheable = match policy(method, params) {
+            Policy::Never => false,
+            Policy::Always => true,
+            Policy::AtHeight(height) => height <= self.final_height.load(Ordering::Relaxed),
+            Policy::FinalOutcome => {
+                result.get("final_execution_status").and_then(Value::as_str) == Some("FINAL")
+            }
+        };
+        if cacheable {
+            self.backend.put(key, result.clone());
+        }
+    }
+}
+
+fn policy(method: &str, params: &Value) -> Policy {
+    match method {
+        "genesis_config" | "EXPERIMENTAL_genesis_config" | "EXPERIMENTAL_receipt" => Policy::Always,
+        "tx" | "EXPERIMENTAL_tx_status" => Policy::FinalOutcome,
+        "chunk" if params.get("chunk_id").is_some() => Policy::Always,
+        "block" | "chunk" | "EXPERIMENTAL_protocol_config" => match params.get("block_id") {
+            Some(Value::String(_)) => Policy::Always,
+            Some(Value::Number(height)) => height.as_u64().map_or(Policy::Never, Policy::AtHeight),
+            _ => Policy::Never,
+        },
+        _ => Policy::Never,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::NearRpcClient;
+    use crate::middleware::{HandleFuture, Middleware, Next, RpcCall};
+    use crate::test_utils::{HASH, block};
+    use crate::types::{BlockId, CryptoHash, Finality, RpcBlockRequest};
+    use serde_json::json;
+
+    /// Answers every call with a block and counts the calls that reach it.
+    struct CountBlocks {
+        calls: Arc<AtomicU64>,
+    }
+
+    impl Middleware for CountBlocks {
+        fn handle<'a>(&'a self, call: RpcCall, _next: Next<'a>) -> HandleFuture<'a> {
+            Box::pin(async move {
+                self.calls.fetch_add(1, Ordering::Relaxed);
+                Ok(json!({
+                    "jsonrpc": "2.0",
+                    "id": call.id,
+                    "result": block(100, "4reLvkAWfqk5fsqio1KLudk46cqRz9erQdaHkWZKMJDZ", HASH),
+                }))
+            })
+        }
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let cache = LruCache::new(2);
+        cache.put("a".into(), json!(1));
+        cache.put("b".into(), json!(2));
+        assert_eq!(cache.get("a"), Some(json!(1)));
+        cache.put("c".into(), json!(3));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(json!(1)));
+        assert_eq!(cache.get("c"), Some(json!(3)));
+    }
+
+    #[test]
+    fn caches_only_immutable_responses() {
+        let cache = ResponseCache::new(LruCache::new(16));
+        let block = json!({ "header": { "height": 100 } });
+
+        let by_hash = json!({ "block_id": "4reLvkAWfqk5fsqio1KLudk46cqRz9erQdaHkWZKMJDZ" });
+        let key = cache.key("block", &by_hash);
+        cache.store("block", &by_hash, key.clone(), &block);
+        assert_eq!(cache.get(&key.unwrap()), Some(block.clone()));
+
+        assert_eq!(
+            cache.key("block", &json!({ "finality": "optimistic" })),
+            None
+        );
+        assert_eq!(cache.key("block", &json!({ "finality": "final" })), None);
+        assert_eq!(cache.key("status", &json!([])), None);
+
+        // Heights are cached only once a final block at or above them is seen.
+        let by_height = json!({ "block_id": 90 });
+        let key = cache.key("block", &by_height).unwrap();
+        cache.store("block", &by_height, Some(key.clone()), &block);
+        assert_eq!(cache.get(&key), None);
+        cache.store("block", &json!({ "finality": "final" }), None, &block);
+        cache.store("block", &by_height, Some(key.clone()), &block);
+        assert_eq!(cache.get(&key), Some(block));
+
+        let tx = json!({ "tx_hash": "4reLvkAWfqk5fsqio1KLudk46cqRz9erQdaHkWZKMJDZ", "sender_account_id": "alice.near" });
+        let key = cache.key("tx", &tx).unwrap();
+        cache.store(
+            "tx",
+            &tx,
+            Some(key.clone()),
+            &json!({ "final_execution_status": "EXECUTED" }),
+        );
+        assert_eq!(cache.get(&key), None);
+        cache.store(
+            "tx",
+            &tx,
+            Some(key.clone()),
+            &json!({ "final_execution_status": "FINAL" }),
+        );
+        assert!(cache.get(&key).is_some());
+    }
+
+    #[tokio::test]
+    async fn serves_repeated_calls_from_the_cache() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let client = NearRpcClient::new("http://localhost:0")
+            .with_cache(LruCache::new(16))
+            .with_middleware(CountBlocks {
+                calls: calls.clone(),
+            });
+
+        let by_hash = RpcBlockRequest::BlockId(BlockId::CryptoHash(CryptoHash(
+            "4reLvkAWfqk5fsqio1KLudk46cqRz9erQdaHkWZKMJDZ".into(),
+        )));
+        client.block(by_hash.clone()).await.unwrap();
+        let cached = client.block(by_hash).await.unwrap();
+        assert_eq!(cached.header.height, 100);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        let optimistic = RpcBlockRequest::Finality(Finality::Optimistic);
+        client.block(optimistic.clone()).await.unwrap();
+        client.block(optimistic).await.unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+}
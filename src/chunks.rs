@@ -0,0 +1,228 @@
+//! Blocks together with their chunks.
+//!
+//! A block carries one chunk header per shard, but a shard whose chunk was
+//! not produced in time keeps the header of its last included chunk, with an
+//! older `height_included`. [`NearRpcClient::block_with_chunks`] fetches the
+//! chunks that are new in a block concurrently and marks the other shards as
+//! missing, so their transactions and receipts are not counted twice.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::{NearRpcClient, types::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     let block = client
+//!         .block_with_chunks(RpcBlockRequest::Finality(Finality::Final))
+//!         .await?;
+//!
+//!     for shard in &block.shards {
+//!         println!(
+//!             "shard {}: {} transactions, {} receipts{}",
+//!             shard.shard_id,
+//!             shard.transactions().len(),
+//!             shard.receipts().len(),
+//!             if shard.is_missing() { " (missing)" } else { "" },
+//!         );
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use futures::{StreamExt, TryStreamExt, stream};
+
+use crate::client::{NearRpcClient, Result};
+use crate::shard_layout::ShardLayoutError;
+use crate::types::*;
+
+/// How many chunks [`NearRpcClient::block_with_chunks`] fetches at once.
+const DEFAULT_CHUNK_CONCURRENCY: usize = 8;
+
+/// The chunk of one shard in a block.
+#[derive(Debug, Clone)]
+pub struct ShardChunk {
+    pub shard_id: ShardId,
+    /// The header in the block; for a missing chunk, the header of the
+    /// shard's last included chunk.
+    pub header: ChunkHeaderView,
+    /// The full chunk, or `None` if the shard had no new chunk in this block.
+    pub chunk: Option<RpcChunkResponse>,
+}
+
+impl ShardChunk {
+    /// Whether the shard's chunk was not produced for this block.
+    pub fn is_missing(&self) -> bool {
+        self.chunk.is_none()
+    }
+
+    /// The transactions included in this shard in this block.
+    pub fn transactions(&self) -> &[SignedTransactionView] {
+        self.chunk
+            .as_ref()
+            .map_or(&[], |chunk| chunk.transactions.as_slice())
+    }
+
+    /// The receipts included in this shard in this block.
+    pub fn receipts(&self) -> &[ReceiptView] {
+        self.chunk
+            .as_ref()
+            .map_or(&[], |chunk| chunk.receipts.as_slice())
+    }
+}
+
+/// A block with the chunk of every shard.
+#[derive(Debug, Clone)]
+pub struct BlockWithChunks {
+    pub block: RpcBlockResponse,
+    /// One entry per shard, in the order of the block's chunk headers.
+    pub shards: Vec<ShardChunk>,
+}
+
+impl BlockWithChunks {
+    pub fn shard(&self, shard_id: &ShardId) -> Option<&ShardChunk> {
+        self.shards
+            .iter()
+            .find(|shard| shard.shard_id.0 == shard_id.0)
+    }
+
+    /// The chunk of the shard holding `account_id` under `layout`, which
+    /// should be the shard layout of the block's epoch.
+    pub fn shard_for_account(
+        &self,
+        layout: &ShardLayout,
+        account_id: &AccountId,
+    ) -> std::result::Result<Option<&ShardChunk>, ShardLayoutError> {
+        Ok(self.shard(&layout.account_id_to_shard_id(account_id)?))
+    }
+
+    /// Shards whose chunk was not produced for this block.
+    pub fn missing_shards(&self) -> Vec<ShardId> {
+        self.shards
+            .iter()
+            .filter(|shard| shard.is_missing())
+            .map(|shard| shard.shard_id.clone())
+            .collect()
+    }
+
+    /// All transactions of the block, with the shard they were included in.
+    pub fn transactions(&self) -> impl Iterator<Item = (&ShardId, &SignedTransactionView)> {
+        self.shards.iter().flat_map(|shard| {
+            shard
+                .transactions()
+                .iter()
+                .map(move |tx| (&shard.shard_id, tx))
+        })
+    }
+
+    /// All receipts of the block, with the shard they were included in.
+    pub fn receipts(&self) -> impl Iterator<Item = (&ShardId, &ReceiptView)> {
+        self.shards.iter().flat_map(|shard| {
+            shard
+                .receipts()
+                .iter()
+                .map(move |receipt| (&shard.shard_id, receipt))
+        })
+    }
+
+    fn assemble(block: RpcBlockResponse, chunks: Vec<RpcChunkResponse>) -> Self {
+        let height = block.header.height;
+        let mut chunks = chunks.into_iter();
+        let shards = block
+            .chunks
+            .iter()
+            .map(|header| ShardChunk {
+                shard_id: header.shard_id.clone(),
+                header: header.clone(),
+                chunk: if header.height_included == height {
+                    chunks.next()
+                } else {
+                    None
+                },
+            })
+            .collect();
+        Self { block, shards }
+    }
+}
+
+impl NearRpcClient {
+    /// Fetches a block and the chunks produced in it.
+    pub async fn block_with_chunks(&self, request: RpcBlockRequest) -> Result<BlockWithChunks> {
+        let block = self.block(request).await?;
+        let chunks = self.new_chunks(&block, DEFAULT_CHUNK_CONCURRENCY).await?;
+        Ok(BlockWithChunks::assemble(block, chunks))
+    }
+
+    /// Fetches the chunks produced in `block` (skipping chunks carried over
+    /// from earlier heights), at most `concurrency` at a time.
+    pub(crate) async fn new_chunks(
+        &self,
+        block: &RpcBlockResponse,
+        concurrency: usize,
+    ) -> Result<Vec<RpcChunkResponse>> {
+        let height = block.header.height;
+        // Owned ids keep the returned future `Send` (borrowing closures here
+        // trip rustc's higher-ranked lifetime inference).
+        let chunk_ids: Vec<CryptoHash> = block
+            .chunks
+            .iter()
+            .filter(|header| header.height_included == height)
+            .map(|header| header.chunk_hash.clone())
+            .collect();
+        stream::iter(chunk_ids)
+            .map(|chunk_id| self.chunk(RpcChunkRequest::ChunkHash { chunk_id }))
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{block_header, chunk_header};
+
+    #[test]
+    fn marks_carried_over_chunks_missing() {
+        let block: RpcBlockResponse = serde_json::from_value(serde_json::json!({
+            "author": "node.near",
+            "chunks": [chunk_header(0, 10), chunk_header(1, 9), chunk_header(2, 10)],
+            "header": block_header(10, &[true, false, true]),
+        }))
+        .unwrap();
+        let empty_chunk = serde_json::from_value(serde_json::json!({
+            "author": "node.near",
+            "header": chunk_header(0, 10),
+            "transactions": [],
+            "receipts": []
+        }))
+        .unwrap();
+        let chunk_with_tx = serde_json::from_value(serde_json::json!({
+            "author": "node.near",
+            "header": chunk_header(2, 10),
+            "transactions": [{
+                "actions": [],
+                "hash": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1",
+                "nonce": 1,
+                "public_key": "ed25519:11111111111111111111111111111111",
+                "receiver_id": "app.near",
+                "signature": "ed25519:1111111111111111111111111111111111111111111111111111111111111111",
+                "signer_id": "alice.near"
+            }],
+            "receipts": []
+        }))
+        .unwrap();
+
+        let block = BlockWithChunks::assemble(block, vec![empty_chunk, chunk_with_tx]);
+
+        let missing: Vec<u64> = block.missing_shards().iter().map(|id| id.0).collect();
+        assert_eq!(missing, [1]);
+        assert!(block.shard(&ShardId(1)).unwrap().transactions().is_empty());
+        let txs: Vec<(u64, &str)> = block
+            .transactions()
+            .map(|(shard_id, tx)| (shard_id.0, tx.signer_id.as_str()))
+            .collect();
+        assert_eq!(txs, [(2, "alice.near")]);
+    }
+}
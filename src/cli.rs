@@ -0,0 +1,689 @@
+//! `near-rpc`, a command-line client for NEAR RPC nodes.
+//!
+//! Every RPC method of the [client](crate::client::NearRpcClient) is a
+//! subcommand, named after the method in kebab case. The usual params have
+//! their own arguments, and any params can be given as a JSON object with
+//! `--params`, which the other arguments then override. Queries about a block
+//! default to the final block.
+//!
+//! ```text
+//! near-rpc block --finality final
+//! near-rpc view-account near --block-id 130000000
+//! near-rpc call-function wrap.near ft_balance_of --args '{"account_id": "near"}'
+//! near-rpc --network testnet --output table validators
+//! near-rpc --url http://localhost:3030 status
+//! ```
+//!
+//! Results are printed as JSON, or with `--output table` as one row per
+//! field. The value a view function returns from `call-function` is printed
+//! decoded from JSON (or UTF-8) rather than as raw bytes.
+//!
+//! Install with `cargo install near-openrpc-client --features cli`.
+
+use std::process::ExitCode;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::blocking::NearRpcClient;
+use crate::client::rpc_methods;
+use crate::errors::LegacyQueryError;
+use crate::types::*;
+
+/// Errors from running a command, after its arguments parsed.
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("invalid JSON in --{arg}: {source}")]
+    InvalidJson {
+        arg: &'static str,
+        source: serde_json::Error,
+    },
+    #[error("invalid params for {method}: {source}")]
+    InvalidParams {
+        method: &'static str,
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Client(Box<crate::client::Error>),
+}
+
+impl From<crate::client::Error> for CliError {
+    fn from(err: crate::client::Error) -> Self {
+        Self::Client(Box::new(err))
+    }
+}
+
+/// An RPC method exposed as a subcommand.
+#[derive(Debug)]
+struct Method {
+    /// The client method, e.g. `view_account`.
+    name: &'static str,
+    about: &'static str,
+    takes_request: bool,
+}
+
+impl Method {
+    fn subcommand(&self) -> String {
+        self.name.replace('_', "-")
+    }
+}
+
+/// How a subcommand's arguments become params.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Param {
+    /// A positional argument, sent as this field.
+    Arg(&'static str),
+    /// An optional `--flag`, sent as this field.
+    Flag(&'static str),
+    /// A repeatable `--flag`, sent as a list in the second field.
+    List(&'static str, &'static str),
+    /// A `--flag` without a value, sent as `true` in this field.
+    Switch(&'static str),
+    /// `--finality` or `--block-id`; the final block by default.
+    Block,
+    /// `--epoch-id` or `--block-id`; the latest epoch by default.
+    Epoch,
+    /// `--prefix`, a UTF-8 key prefix sent base64-encoded in this field;
+    /// empty by default.
+    Prefix(&'static str),
+    /// `--args`, JSON function arguments sent base64-encoded as
+    /// `args_base64`; `{}` by default.
+    FunctionArgs,
+}
+
+/// The arguments of the subcommand for client method `name`, besides
+/// `--params`.
+fn params_of(name: &str) -> &'static [Param] {
+    use Param::*;
+    match name {
+        "block" | "changes_in_block" | "block_effects" | "protocol_config" => &[Block],
+        "chunk" | "congestion_level" => &[Flag("chunk_id"), Flag("block_id"), Flag("shard_id")],
+        "gas_price" | "validators_ordered" => &[Flag("block_id")],
+        "view_account" | "view_code" | "view_access_key_list" => &[Arg("account_id"), Block],
+        "view_state" => &[
+            Arg("account_id"),
+            Prefix("prefix_base64"),
+            Switch("include_proof"),
+            Block,
+        ],
+        "view_access_key" => &[Arg("account_id"), Arg("public_key"), Block],
+        "call_function" => &[Arg("account_id"), Arg("method_name"), FunctionArgs, Block],
+        "broadcast_tx_async" | "broadcast_tx_commit" | "send_tx" => {
+            &[Arg("signed_tx_base64"), Flag("wait_until")]
+        }
+        "tx" => &[Arg("tx_hash"), Arg("sender_account_id"), Flag("wait_until")],
+        "validators" => &[Epoch],
+        "light_client_proof" => &[
+            Flag("type"),
+            Flag("light_client_head"),
+            Flag("transaction_hash"),
+            Flag("sender_id"),
+            Flag("receipt_id"),
+            Flag("receiver_id"),
+        ],
+        "next_light_client_block" => &[Arg("last_block_hash")],
+        "light_client_block_proof" => &[Arg("block_hash"), Arg("light_client_head")],
+        "changes" => &[
+            Flag("changes_type"),
+            List("account_id", "account_ids"),
+            Flag("key_prefix_base64"),
+            Block,
+        ],
+        "receipt" => &[Arg("receipt_id")],
+        _ => &[],
+    }
+}
+
+impl Param {
+    fn add_to(self, command: Command) -> Command {
+        match self {
+            Param::Arg(field) => command.arg(
+                Arg::new(field)
+                    .required(true)
+                    .value_name(field.to_uppercase()),
+            ),
+            Param::Flag(field) => command.arg(Arg::new(field).long(field.replace('_', "-"))),
+            Param::List(flag, field) => command.arg(
+                Arg::new(field)
+                    .long(flag.replace('_', "-"))
+                    .action(ArgAction::Append),
+            ),
+            Param::Switch(field) => command.arg(
+                Arg::new(field)
+                    .long(field.replace('_', "-"))
+                    .action(ArgAction::SetTrue),
+            ),
+            Param::Block => command
+                .arg(
+                    Arg::new("finality")
+                        .long("finality")
+                        .value_parser(["optimistic", "near-final", "final"])
+                        .conflicts_with("block_id"),
+                )
+                .arg(block_id()),
+            Param::Epoch => command
+                .arg(
+                    Arg::new("epoch_id")
+                        .long("epoch-id")
+                        .conflicts_with("block_id"),
+                )
+                .arg(block_id()),
+            Param::Prefix(_) => command.arg(
+                Arg::new("prefix")
+                    .long("prefix")
+                    .help("Only keys starting with this UTF-8 prefix"),
+            ),
+            Param::FunctionArgs => command.arg(
+                Arg::new("args")
+                    .long("args")
+                    .value_name("JSON")
+                    .help("The function's arguments, as JSON"),
+            ),
+        }
+    }
+
+    fn apply(self, matches: &ArgMatches, params: &mut Map<String, Value>) -> Result<(), CliError> {
+        match self {
+            Param::Arg(field) | Param::Flag(field) => {
+                if let Some(raw) = matches.get_one::<String>(field) {
+                    params.insert(field.to_string(), field_value(field, raw));
+                }
+            }
+            Param::List(_, field) => {
+                if let Some(values) = matches.get_many::<String>(field) {
+                    params.insert(
+                        field.to_string(),
+                        values.map(|value| json!(value)).collect(),
+                    );
+                }
+            }
+            Param::Switch(field) => {
+                if matches.get_flag(field) {
+                    params.insert(field.to_string(), json!(true));
+                }
+            }
+            Param::Block => {
+                if let Some(finality) = matches.get_one::<String>("finality") {
+                    params.insert("finality".to_string(), json!(finality));
+                } else if let Some(block_id) = matches.get_one::<String>("block_id") {
+                    params.insert("block_id".to_string(), field_value("block_id", block_id));
+                } else if !["finality", "block_id", "sync_checkpoint"]
+                    .iter()
+                    .any(|field| params.contains_key(*field))
+                {
+                    params.insert("finality".to_string(), json!("final"));
+                }
+            }
+            Param::Epoch => {
+                for field in ["epoch_id", "block_id"] {
+                    if let Some(raw) = matches.get_one::<String>(field) {
+                        params.insert(field.to_string(), field_value(field, raw));
+                    }
+                }
+            }
+            Param::Prefix(field) => {
+                let prefix = matches.get_one::<String>("prefix");
+                if prefix.is_some() || !params.contains_key(field) {
+                    let prefix = prefix.map_or("", String::as_str);
+                    params.insert(field.to_string(), json!(STANDARD.encode(prefix)));
+                }
+            }
+            Param::FunctionArgs => {
+                let args = match matches.get_one::<String>("args") {
+                    Some(raw) => Some(serde_json::from_str::<Value>(raw).map_err(|source| {
+                        CliError::InvalidJson {
+                            arg: "args",
+                            source,
+                        }
+                    })?),
+                    None if params.contains_key("args_base64") => None,
+                    None => Some(json!({})),
+                };
+                if let Some(args) = args {
+                    params.insert(
+                        "args_base64".to_string(),
+                        json!(STANDARD.encode(args.to_string())),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn block_id() -> Arg {
+    Arg::new("block_id")
+        .long("block-id")
+        .value_name("HEIGHT|HASH")
+}
+
+/// The JSON for a `field` given on the command line: heights and shard ids
+/// are numbers, anything else a string.
+fn field_value(field: &str, raw: &str) -> Value {
+    match (field, raw.parse::<u64>()) {
+        ("block_id" | "shard_id", Ok(number)) => json!(number),
+        _ => json!(raw),
+    }
+}
+
+/// Whether a method takes a request.
+macro_rules! takes_request {
+    () => {
+        false
+    };
+    ($request:ty) => {
+        true
+    };
+}
+
+macro_rules! cli_methods {
+    ($(#[doc = $doc:literal] fn $name:ident($($arg:ident: $request:ty)?) -> $response:ty = $method:literal;)*) => {
+        const METHODS: &[Method] = &[$(
+            Method {
+                name: stringify!($name),
+                about: $doc,
+                takes_request: takes_request!($($request)?),
+            },
+        )*];
+
+        /// Checks that `params` deserialize into the request of client method
+        /// `name`, so mistakes are reported before anything is sent.
+        fn validate(name: &str, params: &Value) -> Result<(), CliError> {
+            match name {
+                $($(stringify!($name) => check::<$request>(stringify!($name), params),)?)*
+                _ => Ok(()),
+            }
+        }
+
+        /// Calls client method `name`, returning its result as the node sent
+        /// it.
+        ///
+        /// `params` are sent as given rather than re-serialized from the
+        /// request type, which cannot tell some of nearcore's request shapes
+        /// apart, and the result is not decoded into the response type, so
+        /// fields the generated types do not know about are kept.
+        async fn call(
+            client: &crate::client::NearRpcClient,
+            name: &str,
+            params: Value,
+        ) -> Result<Value, CliError> {
+            match name {
+                $(stringify!($name) => {
+                    let result: Value = client.call($method, params).await?;
+                    match legacy_query_error::<$response>(&result) {
+                        Some(legacy) => Err(crate::client::Error::LegacyQueryResult(Box::new(legacy)).into()),
+                        None => Ok(result),
+                    }
+                })*
+                _ => unreachable!("subcommands are built from METHODS"),
+            }
+        }
+    };
+}
+
+rpc_methods!(cli_methods);
+
+/// The error nearcore returns as a result for some failed queries, which
+/// the client only recognizes when decoding into the response type `R`.
+fn legacy_query_error<'de, R: Deserialize<'de>>(result: &'de Value) -> Option<LegacyQueryError> {
+    result.get("error")?;
+    if R::deserialize(result).is_ok() {
+        return None;
+    }
+    LegacyQueryError::deserialize(result).ok()
+}
+
+fn check<'de, R: Deserialize<'de>>(
+    method: &'static str,
+    params: &'de Value,
+) -> Result<(), CliError> {
+    R::deserialize(params)
+        .map(drop)
+        .map_err(|source| CliError::InvalidParams { method, source })
+}
+
+/// The `near-rpc` command line.
+pub fn command() -> Command {
+    let mut command = Command::new("near-rpc")
+        .about("Calls the methods of a NEAR RPC node")
+        .version(env!("CARGO_PKG_VERSION"))
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("network")
+                .long("network")
+                .global(true)
+                .value_parser(["mainnet", "testnet", "betanet", "local"])
+                .default_value("mainnet")
+                .help("The network to query"),
+        )
+        .arg(
+            Arg::new("url")
+                .long("url")
+                .global(true)
+                .conflicts_with("network")
+                .help("The RPC endpoint to query, instead of a network's"),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .short('o')
+                .global(true)
+                .value_parser(["json", "table"])
+                .default_value("json")
+                .help("Print results as JSON or as a table of fields"),
+        );
+    for method in METHODS {
+        let mut subcommand = Command::new(method.subcommand()).about(method.about.trim());
+        if method.takes_request {
+            subcommand = subcommand.arg(
+                Arg::new("params")
+                    .long("params")
+                    .value_name("JSON")
+                    .help("Params as a JSON object, which the other arguments override"),
+            );
+            for param in params_of(method.name) {
+                subcommand = param.add_to(subcommand);
+            }
+        }
+        command = command.subcommand(subcommand);
+    }
+    command
+}
+
+/// Runs `near-rpc` with the process's arguments, printing the result or
+/// error.
+pub fn main() -> ExitCode {
+    match run(&command().get_matches()) {
+        Ok(output) => {
+            println!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the command in `matches`, returning what to print.
+pub fn run(matches: &ArgMatches) -> Result<String, CliError> {
+    let (method, params, matches) = invocation(matches)?;
+    let client = match matches.get_one::<String>("url") {
+        Some(url) => NearRpcClient::new(url.as_str()),
+        None => match matches.get_one::<String>("network").map(String::as_str) {
+            Some("testnet") => NearRpcClient::testnet(),
+            Some("betanet") => NearRpcClient::betanet(),
+            Some("local") => NearRpcClient::local(),
+            _ => NearRpcClient::mainnet(),
+        },
+    };
+    let mut result = client.block_on(|client| call(client, method.name, params))?;
+    if method.name == "call_function" {
+        decode_call_result(&mut result);
+    }
+    Ok(
+        match matches.get_one::<String>("output").map(String::as_str) {
+            Some("table") => table(&result),
+            _ => serde_json::to_string_pretty(&result).expect("JSON values serialize"),
+        },
+    )
+}
+
+/// The method a command calls, its params and the subcommand's matches.
+fn invocation(matches: &ArgMatches) -> Result<(&'static Method, Value, &ArgMatches), CliError> {
+    let (subcommand, matches) = matches.subcommand().expect("a subcommand is required");
+    let method = METHODS
+        .iter()
+        .find(|method| method.subcommand() == subcommand)
+        .expect("subcommands are built from METHODS");
+    if !method.takes_request {
+        return Ok((method, json!({}), matches));
+    }
+
+    let params = match matches.get_one::<String>("params") {
+        Some(raw) => serde_json::from_str(raw).map_err(|source| CliError::InvalidJson {
+            arg: "params",
+            source,
+        })?,
+        None => json!({}),
+    };
+    // Params that are not an object, such as `"latest"`, are sent as given.
+    let Value::Object(mut params) = params else {
+        validate(method.name, &params)?;
+        return Ok((method, params, matches));
+    };
+    let spec = params_of(method.name);
+    for param in spec {
+        param.apply(matches, &mut params)?;
+    }
+    let params = if params.is_empty() && spec.contains(&Param::Epoch) {
+        json!("latest")
+    } else {
+        Value::Object(params)
+    };
+    validate(method.name, &params)?;
+    Ok((method, params, matches))
+}
+
+/// Replaces the bytes a view function returned with their decoding as JSON,
+/// or else as UTF-8, leaving other bytes as they are.
+fn decode_call_result(response: &mut Value) {
+    let Some(result) = response.get_mut("result") else {
+        return;
+    };
+    let Ok(bytes) = Vec::<u8>::deserialize(&*result) else {
+        return;
+    };
+    if let Ok(value) = serde_json::from_slice(&bytes) {
+        *result = value;
+    } else if let Ok(text) = String::from_utf8(bytes) {
+        *result = Value::String(text);
+    }
+}
+
+/// Renders `value` as aligned columns: a list of objects with a column per
+/// field, and anything else with a row per leaf field, named by its path.
+fn table(value: &Value) -> String {
+    let rows: Vec<Vec<String>> = match value {
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => {
+            let mut columns: Vec<&String> = Vec::new();
+            for key in items
+                .iter()
+                .filter_map(Value::as_object)
+                .flat_map(Map::keys)
+            {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+            let header = columns.iter().map(|column| column.to_uppercase()).collect();
+            std::iter::once(header)
+                .chain(items.iter().map(|item| {
+                    columns
+                        .iter()
+                        .map(|column| item.get(column.as_str()).map_or_else(String::new, cell))
+                        .collect()
+                }))
+                .collect()
+        }
+        Value::Array(_) | Value::Object(_) => {
+            let mut rows = Vec::new();
+            flatten(String::new(), value, &mut rows);
+            rows
+        }
+        scalar => return cell(scalar),
+    };
+
+    let mut widths = vec![0; rows.iter().map(Vec::len).max().unwrap_or(0)];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect();
+            line.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn flatten(path: String, value: &Value, rows: &mut Vec<Vec<String>>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+    match value {
+        Value::Object(fields) if !fields.is_empty() => {
+            for (key, value) in fields {
+                flatten(child(key), value, rows);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, value) in items.iter().enumerate() {
+                flatten(child(&index.to_string()), value, rows);
+            }
+        }
+        _ => rows.push(vec![path, cell(value)]),
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(args: &[&str]) -> Result<Value, CliError> {
+        let matches = command()
+            .try_get_matches_from(std::iter::once("near-rpc").chain(args.iter().copied()))
+            .unwrap();
+        invocation(&matches).map(|(_, params, _)| params)
+    }
+
+    #[test]
+    fn builds_params_from_arguments() {
+        command().debug_assert();
+        assert_eq!(
+            params(&["view-account", "near"]).unwrap(),
+            json!({ "account_id": "near", "finality": "final" })
+        );
+        assert_eq!(
+            params(&["block", "--block-id", "130000000"]).unwrap(),
+            json!({ "block_id": 130000000 })
+        );
+        assert_eq!(
+            params(&[
+                "call-function",
+                "wrap.near",
+                "ft_balance_of",
+                "--args",
+                r#"{"account_id": "near"}"#,
+                "--finality",
+                "optimistic",
+            ])
+            .unwrap(),
+            json!({
+                "account_id": "wrap.near",
+                "method_name": "ft_balance_of",
+                "args_base64": STANDARD.encode(r#"{"account_id":"near"}"#),
+                "finality": "optimistic",
+            })
+        );
+        assert_eq!(
+            params(&[
+                "view-state",
+                "near",
+                "--prefix",
+                "STATE",
+                "--params",
+                r#"{"include_proof": true}"#
+            ])
+            .unwrap(),
+            json!({
+                "account_id": "near",
+                "prefix_base64": "U1RBVEU=",
+                "include_proof": true,
+                "finality": "final",
+            })
+        );
+        assert_eq!(params(&["validators"]).unwrap(), json!("latest"));
+        assert_eq!(params(&["--output", "table", "status"]).unwrap(), json!({}));
+        assert!(matches!(
+            params(&["chunk"]),
+            Err(CliError::InvalidParams {
+                method: "chunk",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn renders_tables() {
+        let block = json!({
+            "author": "node.near",
+            "header": { "height": 10, "hash": "abc" },
+            "chunks": [],
+        });
+        assert_eq!(
+            table(&block),
+            "author         node.near\nchunks         []\nheader.hash    abc\nheader.height  10"
+        );
+        let validators = json!([
+            { "account_id": "a.near", "stake": "10" },
+            { "account_id": "bb.near", "stake": "2", "slashed": true },
+        ]);
+        assert_eq!(
+            table(&validators),
+            "ACCOUNT_ID  STAKE  SLASHED\na.near      10\nbb.near     2      true"
+        );
+
+        let mut response = json!({ "result": b"\"42\"".to_vec(), "logs": [] });
+        decode_call_result(&mut response);
+        assert_eq!(response["result"], "42");
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn returns_results_as_sent() {
+        use crate::testing::{MockRpc, Reply};
+
+        let mock = MockRpc::new();
+        let status = json!({ "chain_id": "mainnet", "unknown_field": [1, 2] });
+        mock.on("status", Reply::result(&status));
+        mock.on(
+            "EXPERIMENTAL_call_function",
+            Reply::legacy_query_error("wasm execution failed with error: MethodNotFound"),
+        );
+        let client = mock.client();
+
+        assert_eq!(call(&client, "status", json!([])).await.unwrap(), status);
+        let params = json!({
+            "account_id": "wrap.near",
+            "method_name": "missing",
+            "args_base64": "",
+            "finality": "final",
+        });
+        let err = call(&client, "call_function", params).await.unwrap_err();
+        assert!(
+            matches!(&err, CliError::Client(err) if matches!(**err, crate::client::Error::LegacyQueryResult(_))),
+            "{err:?}"
+        );
+    }
+}
@@ -1,17 +1,22 @@
 //! Async JSON-RPC client for NEAR Protocol.
 
+use crate::cache::{CacheBackend, ResponseCache};
 use crate::errors::{LegacyQueryError, RpcError};
+use crate::middleware::{Middleware, Next, RpcCall};
+use crate::telemetry;
 use crate::types::*;
 use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 /// JSON-RPC request wrapper
 #[derive(Debug, Serialize)]
-struct RpcRequest<T> {
+struct RpcRequest<'a, T> {
     jsonrpc: &'static str,
     id: u64,
-    method: &'static str,
+    method: &'a str,
     params: T,
 }
 
@@ -39,7 +44,7 @@ pub enum Error {
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
     #[error("RPC error: {0}")]
-    Rpc(#[from] RpcError),
+    Rpc(Box<RpcError>),
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
     /// Legacy error from nearcore's backward-compatible query handling.
@@ -48,7 +53,46 @@ pub enum Error {
     /// `ContractExecutionError` as fake success responses inside the `"result"`
     /// field instead of as proper JSON-RPC errors.
     #[error("Legacy RPC query error: {0}")]
-    LegacyQueryResult(LegacyQueryError),
+    LegacyQueryResult(Box<LegacyQueryError>),
+    /// An error raised by a [`Middleware`].
+    #[error("Middleware error: {0}")]
+    Middleware(Box<dyn std::error::Error + Send + Sync>),
+    #[cfg(feature = "keystore")]
+    #[error("Keystore error: {0}")]
+    Keystore(#[from] crate::keystore::KeystoreError),
+    #[cfg(feature = "keystore")]
+    #[error("Transaction error: {0}")]
+    Transaction(#[from] crate::transaction::TransactionError),
+    #[error("Fee estimation error: {0}")]
+    Fee(#[from] crate::fees::FeeError),
+    #[error("State changes error: {0}")]
+    StateChanges(#[from] crate::state_changes::StateChangesError),
+    #[error("Validator analytics error: {0}")]
+    ValidatorAnalytics(#[from] crate::validator_analytics::ValidatorAnalyticsError),
+    #[cfg(feature = "stream")]
+    #[error("Block stream error: {0}")]
+    BlockStream(#[from] crate::block_stream::BlockStreamError),
+    #[cfg(feature = "stream")]
+    #[error("Account watcher error: {0}")]
+    AccountWatcher(#[from] crate::account_watcher::AccountWatcherError),
+    #[cfg(feature = "stream")]
+    #[error("Contract state error: {0}")]
+    ContractState(#[from] crate::contract_state::ContractStateError),
+    #[cfg(feature = "light-client")]
+    #[error("Light client error: {0}")]
+    LightClient(#[from] crate::light_client::LightClientError),
+    #[cfg(feature = "light-client")]
+    #[error("Proof error: {0}")]
+    Proof(#[from] crate::proof::ProofError),
+    #[cfg(feature = "light-client")]
+    #[error("State proof error: {0}")]
+    StateProof(#[from] crate::state_proof::StateProofError),
+}
+
+impl From<RpcError> for Error {
+    fn from(err: RpcError) -> Self {
+        Self::Rpc(Box::new(err))
+    }
 }
 
 /// Result type alias for client operations.
@@ -72,7 +116,13 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub struct NearRpcClient {
     client: Client,
     url: String,
+    network: Option<String>,
     request_id: AtomicU64,
+    cache: Option<ResponseCache>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    /// Answers calls in place of the HTTP transport.
+    #[cfg(feature = "testing")]
+    mock: Option<crate::testing::MockRpc>,
 }
 
 impl NearRpcClient {
@@ -81,59 +131,160 @@ impl NearRpcClient {
         Self {
             client: Client::new(),
             url: url.into(),
+            network: None,
             request_id: AtomicU64::new(1),
+            cache: None,
+            middleware: Vec::new(),
+            #[cfg(feature = "testing")]
+            mock: None,
         }
     }
 
     /// Create a client for NEAR Mainnet.
     pub fn mainnet() -> Self {
-        Self::new("https://rpc.mainnet.near.org")
+        Self::new("https://rpc.mainnet.near.org").with_network("mainnet")
     }
 
     /// Create a client for NEAR Testnet.
     pub fn testnet() -> Self {
-        Self::new("https://rpc.testnet.near.org")
+        Self::new("https://rpc.testnet.near.org").with_network("testnet")
     }
 
     /// Create a client for NEAR Betanet.
     pub fn betanet() -> Self {
-        Self::new("https://rpc.betanet.near.org")
+        Self::new("https://rpc.betanet.near.org").with_network("betanet")
     }
 
     /// Create a client for local development (localhost:3030).
     pub fn local() -> Self {
-        Self::new("http://localhost:3030")
+        Self::new("http://localhost:3030").with_network("local")
+    }
+
+    /// Set the network name (e.g. `"testnet"`), used to locate credentials
+    /// under `~/.near-credentials/<network>/`.
+    pub fn with_network(mut self, network: impl Into<String>) -> Self {
+        self.network = Some(network.into());
+        self
+    }
+
+    /// Cache immutable responses in `backend`, e.g. a
+    /// [`LruCache`](crate::cache::LruCache). See the [`cache`](crate::cache)
+    /// module for which responses qualify.
+    pub fn with_cache(mut self, backend: impl CacheBackend + 'static) -> Self {
+        self.cache = Some(ResponseCache::new(backend));
+        self
+    }
+
+    /// Add a [`Middleware`] around every request. Middleware added first
+    /// runs outermost.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Answer calls with `mock` instead of sending them, after all middleware.
+    #[cfg(feature = "testing")]
+    pub(crate) fn with_mock(mut self, mock: crate::testing::MockRpc) -> Self {
+        self.mock = Some(mock);
+        self
+    }
+
+    /// Returns the network name, if known.
+    pub fn network(&self) -> Option<&str> {
+        self.network.as_deref()
     }
 
     fn next_id(&self) -> u64 {
         self.request_id.fetch_add(1, Ordering::Relaxed)
     }
 
-    async fn call<P: Serialize, R: for<'de> Deserialize<'de>>(
+    pub(crate) async fn call<P: Serialize, R: for<'de> Deserialize<'de>>(
         &self,
         method: &'static str,
         params: P,
     ) -> Result<R> {
-        let request = RpcRequest {
-            jsonrpc: "2.0",
+        telemetry::CallSpan::new(method, &self.url)
+            .run(self.dispatch(method, params))
+            .await
+    }
+
+    /// Serves a call from the cache or sends it through the middleware.
+    async fn dispatch<P: Serialize, R: for<'de> Deserialize<'de>>(
+        &self,
+        method: &'static str,
+        params: P,
+    ) -> Result<R> {
+        let Some(cache) = &self.cache else {
+            return Self::decode(self.send(method, params).await?);
+        };
+
+        let params = serde_json::to_value(params)?;
+        let key = cache.key(method, &params);
+        if let Some(result) = key.as_deref().and_then(|key| cache.get(key)) {
+            telemetry::record_cache_hit(method);
+            return Ok(serde_json::from_value(result)?);
+        }
+
+        let raw = self.send(method, &params).await?;
+        let result = Self::decode(raw.clone())?;
+        if let Some(result) = raw.get("result") {
+            cache.store(method, &params, key, result);
+        }
+        Ok(result)
+    }
+
+    async fn send<P: Serialize>(
+        &self,
+        method: &'static str,
+        params: P,
+    ) -> Result<serde_json::Value> {
+        let call = RpcCall {
             id: self.next_id(),
-            method,
-            params,
+            method: method.to_string(),
+            params: serde_json::to_value(params)?,
+            headers: Default::default(),
         };
+        telemetry::record_request_id(call.id);
+        let attempts = AtomicU32::new(0);
+        let response = Next::new(self, &self.middleware, &attempts).run(call).await;
+        telemetry::record_attempts(method, attempts.into_inner());
+        response
+    }
+
+    /// Sends `call` over HTTP, after all middleware.
+    pub(crate) async fn transport(&self, call: RpcCall) -> Result<serde_json::Value> {
+        #[cfg(feature = "testing")]
+        if let Some(mock) = &self.mock {
+            return mock.answer(call).await;
+        }
 
-        let raw: serde_json::Value = self
+        let request = serde_json::to_vec(&RpcRequest {
+            jsonrpc: "2.0",
+            id: call.id,
+            method: &call.method,
+            params: call.params,
+        })?;
+        let request_size = request.len();
+
+        let response = self
             .client
             .post(&self.url)
-            .json(&request)
+            .headers(call.headers)
+            .header(CONTENT_TYPE, "application/json")
+            .body(request)
             .send()
-            .await?
-            .json()
             .await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        telemetry::record_response(&call.method, status.as_u16(), request_size, body.len());
+        Ok(serde_json::from_slice(&body)?)
+    }
 
+    fn decode<R: for<'de> Deserialize<'de>>(raw: serde_json::Value) -> Result<R> {
         match serde_json::from_value::<RpcResponse<R>>(raw.clone()) {
             Ok(response) => match response.result {
                 RpcResult::Ok { result } => Ok(result),
-                RpcResult::Err { error } => Err(Error::Rpc(error)),
+                RpcResult::Err { error } => Err(error.into()),
             },
             Err(deser_err) => {
                 // Nearcore returns UnknownAccessKey and ContractExecutionError as
@@ -146,239 +297,140 @@ impl NearRpcClient {
                         serde_json::from_value::<LegacyQueryError>(result.clone()).ok()
                     })
                 }) {
-                    Err(Error::LegacyQueryResult(legacy))
+                    Err(Error::LegacyQueryResult(Box::new(legacy)))
                 } else {
                     Err(Error::Json(deser_err))
                 }
             }
         }
     }
+}
 
-    // ── Core ─────────────────────────────────────────────────────
-
-    /// Returns the current status of the node.
-    pub async fn status(&self) -> Result<RpcStatusResponse> {
-        self.call("status", serde_json::json!({})).await
-    }
-
-    /// Returns health status of the node.
-    pub async fn health(&self) -> Result<RpcHealthResponse> {
-        self.call("health", serde_json::json!({})).await
-    }
-
-    /// Queries the current state of node network connections.
-    pub async fn network_info(&self) -> Result<RpcNetworkInfoResponse> {
-        self.call("network_info", serde_json::json!({})).await
-    }
-
-    // ── Block / Chunk ────────────────────────────────────────────
-
-    /// Returns block details for a given height or hash.
-    pub async fn block(&self, request: RpcBlockRequest) -> Result<RpcBlockResponse> {
-        self.call("block", request).await
-    }
-
-    /// Returns details of a specific chunk.
-    pub async fn chunk(&self, request: RpcChunkRequest) -> Result<RpcChunkResponse> {
-        self.call("chunk", request).await
-    }
-
-    /// Returns gas price for a specific block.
-    pub async fn gas_price(&self, request: RpcGasPriceRequest) -> Result<RpcGasPriceResponse> {
-        self.call("gas_price", request).await
-    }
-
-    // ── Query ────────────────────────────────────────────────────
-
-    /// Returns account information for a given account ID.
-    pub async fn view_account(
-        &self,
-        request: RpcViewAccountRequest,
-    ) -> Result<RpcViewAccountResponse> {
-        self.call("EXPERIMENTAL_view_account", request).await
-    }
-
-    /// Returns the contract code (Wasm binary) deployed to an account.
-    pub async fn view_code(&self, request: RpcViewCodeRequest) -> Result<RpcViewCodeResponse> {
-        self.call("EXPERIMENTAL_view_code", request).await
-    }
-
-    /// Returns contract state (key-value pairs) based on a key prefix.
-    pub async fn view_state(&self, request: RpcViewStateRequest) -> Result<RpcViewStateResponse> {
-        self.call("EXPERIMENTAL_view_state", request).await
-    }
-
-    /// Returns information about a single access key for an account.
-    pub async fn view_access_key(
-        &self,
-        request: RpcViewAccessKeyRequest,
-    ) -> Result<RpcViewAccessKeyResponse> {
-        self.call("EXPERIMENTAL_view_access_key", request).await
-    }
-
-    /// Returns all access keys for an account.
-    pub async fn view_access_key_list(
-        &self,
-        request: RpcViewAccessKeyListRequest,
-    ) -> Result<RpcViewAccessKeyListResponse> {
-        self.call("EXPERIMENTAL_view_access_key_list", request)
-            .await
-    }
-
-    /// Calls a view function on a contract and returns the result.
-    pub async fn call_function(
-        &self,
-        request: RpcCallFunctionRequest,
-    ) -> Result<RpcCallFunctionResponse> {
-        self.call("EXPERIMENTAL_call_function", request).await
-    }
-
-    // ── Transactions ─────────────────────────────────────────────
-
-    /// Sends a signed transaction asynchronously. Returns immediately with the transaction hash.
-    pub async fn broadcast_tx_async(
-        &self,
-        request: RpcSendTransactionRequest,
-    ) -> Result<CryptoHash> {
-        self.call("broadcast_tx_async", request).await
-    }
-
-    /// Sends a signed transaction and waits for it to complete.
-    pub async fn broadcast_tx_commit(
-        &self,
-        request: RpcSendTransactionRequest,
-    ) -> Result<RpcTransactionResponse> {
-        self.call("broadcast_tx_commit", request).await
-    }
-
-    /// Sends a signed transaction (alias for broadcast_tx_commit).
-    pub async fn send_tx(
-        &self,
-        request: RpcSendTransactionRequest,
-    ) -> Result<RpcTransactionResponse> {
-        self.call("send_tx", request).await
-    }
-
-    /// Queries status of a transaction by hash.
-    pub async fn tx(&self, request: RpcTransactionStatusRequest) -> Result<RpcTransactionResponse> {
-        self.call("tx", request).await
-    }
-
-    // ── Validators ───────────────────────────────────────────────
-
-    /// Queries active validators on the network for a given epoch.
-    pub async fn validators(&self, request: RpcValidatorRequest) -> Result<RpcValidatorResponse> {
-        self.call("validators", request).await
-    }
-
-    /// Returns validators ordered by stake for a given epoch.
-    pub async fn validators_ordered(
-        &self,
-        request: RpcValidatorsOrderedRequest,
-    ) -> Result<ValidatorStakeViews> {
-        self.call("EXPERIMENTAL_validators_ordered", request).await
-    }
-
-    // ── Light Client ─────────────────────────────────────────────
-
-    /// Returns execution proof for light clients (transaction or receipt).
-    pub async fn light_client_proof(
-        &self,
-        request: RpcLightClientExecutionProofRequest,
-    ) -> Result<RpcLightClientExecutionProofResponse> {
-        self.call("light_client_proof", request).await
-    }
-
-    /// Returns the next light client block.
-    pub async fn next_light_client_block(
-        &self,
-        request: RpcLightClientNextBlockRequest,
-    ) -> Result<RpcLightClientNextBlockResponse> {
-        self.call("next_light_client_block", request).await
-    }
-
-    /// Returns block proof for light clients.
-    pub async fn light_client_block_proof(
-        &self,
-        request: RpcLightClientBlockProofRequest,
-    ) -> Result<RpcLightClientBlockProofResponse> {
-        self.call("light_client_block_proof", request).await
-    }
-
-    // ── State Changes ────────────────────────────────────────────
-
-    /// Returns changes in block for a given block height or hash.
-    pub async fn changes_in_block(
-        &self,
-        request: RpcStateChangesInBlockRequest,
-    ) -> Result<RpcStateChangesInBlockByTypeResponse> {
-        self.call("EXPERIMENTAL_changes_in_block", request).await
-    }
-
-    /// Returns state changes for specific state change kinds.
-    pub async fn changes(
-        &self,
-        request: RpcStateChangesInBlockByTypeRequest,
-    ) -> Result<RpcStateChangesInBlockResponse> {
-        self.call("EXPERIMENTAL_changes", request).await
-    }
-
-    /// Returns changes in block (alias for changes_in_block).
-    pub async fn block_effects(
-        &self,
-        request: RpcStateChangesInBlockRequest,
-    ) -> Result<RpcStateChangesInBlockByTypeResponse> {
-        self.call("block_effects", request).await
-    }
-
-    // ── Config ───────────────────────────────────────────────────
-
-    /// Returns protocol configuration for a given block.
-    pub async fn protocol_config(
-        &self,
-        request: RpcProtocolConfigRequest,
-    ) -> Result<RpcProtocolConfigResponse> {
-        self.call("EXPERIMENTAL_protocol_config", request).await
-    }
-
-    /// Returns genesis configuration of the network.
-    pub async fn genesis_config(&self) -> Result<GenesisConfig> {
-        self.call("genesis_config", serde_json::json!({})).await
-    }
-
-    /// Returns client configuration.
-    pub async fn client_config(&self) -> Result<RpcClientConfigResponse> {
-        self.call("client_config", serde_json::json!({})).await
-    }
-
-    // ── Other ────────────────────────────────────────────────────
-
-    /// Returns a receipt by receipt_id.
-    pub async fn receipt(&self, request: RpcReceiptRequest) -> Result<RpcReceiptResponse> {
-        self.call("EXPERIMENTAL_receipt", request).await
-    }
-
-    /// Returns maintenance windows.
-    pub async fn maintenance_windows(&self) -> Result<BlockHeightRanges> {
-        self.call("maintenance_windows", serde_json::json!({}))
-            .await
-    }
-
-    /// Returns split storage info.
-    pub async fn split_storage_info(&self) -> Result<RpcSplitStorageInfoResponse> {
-        self.call("EXPERIMENTAL_split_storage_info", serde_json::json!({}))
-            .await
-    }
+/// Expands `$emit!` with every RPC method, written as
+/// `fn name(request: Request) -> Response = "method";` under its docs, so the
+/// async and [blocking](crate::blocking) clients share one list.
+macro_rules! rpc_methods {
+    ($emit:ident) => {
+        $emit! {
+            // ── Core ─────────────────────────────────────────────────────
+
+            /// Returns the current status of the node.
+            fn status() -> RpcStatusResponse = "status";
+            /// Returns health status of the node.
+            fn health() -> RpcHealthResponse = "health";
+            /// Queries the current state of node network connections.
+            fn network_info() -> RpcNetworkInfoResponse = "network_info";
+
+            // ── Block / Chunk ────────────────────────────────────────────
+
+            /// Returns block details for a given height or hash.
+            fn block(request: RpcBlockRequest) -> RpcBlockResponse = "block";
+            /// Returns details of a specific chunk.
+            fn chunk(request: RpcChunkRequest) -> RpcChunkResponse = "chunk";
+            /// Returns gas price for a specific block.
+            fn gas_price(request: RpcGasPriceRequest) -> RpcGasPriceResponse = "gas_price";
+
+            // ── Query ────────────────────────────────────────────────────
+
+            /// Returns account information for a given account ID.
+            fn view_account(request: RpcViewAccountRequest) -> RpcViewAccountResponse = "EXPERIMENTAL_view_account";
+            /// Returns the contract code (Wasm binary) deployed to an account.
+            fn view_code(request: RpcViewCodeRequest) -> RpcViewCodeResponse = "EXPERIMENTAL_view_code";
+            /// Returns contract state (key-value pairs) based on a key prefix.
+            fn view_state(request: RpcViewStateRequest) -> RpcViewStateResponse = "EXPERIMENTAL_view_state";
+            /// Returns information about a single access key for an account.
+            fn view_access_key(request: RpcViewAccessKeyRequest) -> RpcViewAccessKeyResponse = "EXPERIMENTAL_view_access_key";
+            /// Returns all access keys for an account.
+            fn view_access_key_list(request: RpcViewAccessKeyListRequest) -> RpcViewAccessKeyListResponse = "EXPERIMENTAL_view_access_key_list";
+            /// Calls a view function on a contract and returns the result.
+            fn call_function(request: RpcCallFunctionRequest) -> RpcCallFunctionResponse = "EXPERIMENTAL_call_function";
+
+            // ── Transactions ─────────────────────────────────────────────
+
+            /// Sends a signed transaction asynchronously. Returns immediately with the transaction hash.
+            fn broadcast_tx_async(request: RpcSendTransactionRequest) -> CryptoHash = "broadcast_tx_async";
+            /// Sends a signed transaction and waits for it to complete.
+            fn broadcast_tx_commit(request: RpcSendTransactionRequest) -> RpcTransactionResponse = "broadcast_tx_commit";
+            /// Sends a signed transaction (alias for broadcast_tx_commit).
+            fn send_tx(request: RpcSendTransactionRequest) -> RpcTransactionResponse = "send_tx";
+            /// Queries status of a transaction by hash.
+            fn tx(request: RpcTransactionStatusRequest) -> RpcTransactionResponse = "tx";
+
+            // ── Validators ───────────────────────────────────────────────
+
+            /// Queries active validators on the network for a given epoch.
+            fn validators(request: RpcValidatorRequest) -> RpcValidatorResponse = "validators";
+            /// Returns validators ordered by stake for a given epoch.
+            fn validators_ordered(request: RpcValidatorsOrderedRequest) -> ValidatorStakeViews = "EXPERIMENTAL_validators_ordered";
+
+            // ── Light Client ─────────────────────────────────────────────
+
+            /// Returns execution proof for light clients (transaction or receipt).
+            fn light_client_proof(request: RpcLightClientExecutionProofRequest) -> RpcLightClientExecutionProofResponse = "light_client_proof";
+            /// Returns the next light client block.
+            fn next_light_client_block(request: RpcLightClientNextBlockRequest) -> RpcLightClientNextBlockResponse = "next_light_client_block";
+            /// Returns block proof for light clients.
+            fn light_client_block_proof(request: RpcLightClientBlockProofRequest) -> RpcLightClientBlockProofResponse = "light_client_block_proof";
+
+            // ── State Changes ────────────────────────────────────────────
+
+            /// Returns changes in block for a given block height or hash.
+            fn changes_in_block(request: RpcStateChangesInBlockRequest) -> RpcStateChangesInBlockByTypeResponse = "EXPERIMENTAL_changes_in_block";
+            /// Returns state changes for specific state change kinds.
+            fn changes(request: RpcStateChangesInBlockByTypeRequest) -> RpcStateChangesInBlockResponse = "EXPERIMENTAL_changes";
+            /// Returns changes in block (alias for changes_in_block).
+            fn block_effects(request: RpcStateChangesInBlockRequest) -> RpcStateChangesInBlockByTypeResponse = "block_effects";
+
+            // ── Config ───────────────────────────────────────────────────
+
+            /// Returns protocol configuration for a given block.
+            fn protocol_config(request: RpcProtocolConfigRequest) -> RpcProtocolConfigResponse = "EXPERIMENTAL_protocol_config";
+            /// Returns genesis configuration of the network.
+            fn genesis_config() -> GenesisConfig = "genesis_config";
+            /// Returns client configuration.
+            fn client_config() -> RpcClientConfigResponse = "client_config";
+
+            // ── Other ────────────────────────────────────────────────────
+
+            /// Returns a receipt by receipt_id.
+            fn receipt(request: RpcReceiptRequest) -> RpcReceiptResponse = "EXPERIMENTAL_receipt";
+            /// Returns maintenance windows.
+            fn maintenance_windows() -> BlockHeightRanges = "maintenance_windows";
+            /// Returns split storage info.
+            fn split_storage_info() -> RpcSplitStorageInfoResponse = "EXPERIMENTAL_split_storage_info";
+            /// Returns congestion level for a chunk.
+            fn congestion_level(request: RpcCongestionLevelRequest) -> RpcCongestionLevelResponse = "EXPERIMENTAL_congestion_level";
+        }
+    };
+}
+#[cfg(feature = "blocking")]
+pub(crate) use rpc_methods;
+
+/// The params of a method taking `request`, or `{}` for one without.
+macro_rules! rpc_params {
+    () => {
+        serde_json::json!({})
+    };
+    ($request:ident) => {
+        $request
+    };
+}
 
-    /// Returns congestion level for a chunk.
-    pub async fn congestion_level(
-        &self,
-        request: RpcCongestionLevelRequest,
-    ) -> Result<RpcCongestionLevelResponse> {
-        self.call("EXPERIMENTAL_congestion_level", request).await
-    }
+macro_rules! async_methods {
+    ($($(#[$attr:meta])* fn $name:ident($($arg:ident: $request:ty)?) -> $response:ty = $method:literal;)*) => {
+        impl NearRpcClient {
+            $(
+                $(#[$attr])*
+                pub async fn $name(&self $(, $arg: $request)?) -> Result<$response> {
+                    self.call($method, rpc_params!($($arg)?)).await
+                }
+            )*
+        }
+    };
 }
 
+rpc_methods!(async_methods);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -457,11 +509,14 @@ mod tests {
     fn test_client_creation() {
         let client = NearRpcClient::mainnet();
         assert_eq!(client.url, "https://rpc.mainnet.near.org");
+        assert_eq!(client.network(), Some("mainnet"));
 
         let client = NearRpcClient::testnet();
         assert_eq!(client.url, "https://rpc.testnet.near.org");
+        assert_eq!(client.network(), Some("testnet"));
 
         let client = NearRpcClient::new("https://custom.rpc.near.org");
         assert_eq!(client.url, "https://custom.rpc.near.org");
+        assert_eq!(client.network(), None);
     }
 }
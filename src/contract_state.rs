@@ -0,0 +1,342 @@
+//! Full contract state dumps.
+//!
+//! Nodes refuse `view_state` with `TOO_LARGE_CONTRACT_STATE` when the values
+//! under a prefix exceed their size limit. [`NearRpcClient::dump_contract_state`]
+//! handles this by splitting a refused prefix into its 256 one-byte-longer
+//! sub-prefixes and querying each of them, recursively, with every query
+//! pinned to the same block hash.
+//!
+//! `view_state` cannot fetch a single key, so a value stored at a key exactly
+//! equal to a split prefix is not part of any sub-query. Each split is
+//! reported as a [`ContractStateItem::SplitPrefix`], in key order, so callers
+//! know where such a value could be missing. If nothing at all is found below
+//! a refused prefix, that key must be what is too large and the dump fails
+//! with [`ContractStateError::TooLarge`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use near_openrpc_client::contract_state::ContractStateItem;
+//! use near_openrpc_client::{NearRpcClient, types::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     let state = client.dump_contract_state(
+//!         "wrap.near".parse().unwrap(),
+//!         RpcBlockRequest::Finality(Finality::Final),
+//!     );
+//!
+//!     let mut state = std::pin::pin!(state);
+//!     while let Some(item) = state.next().await {
+//!         match item? {
+//!             ContractStateItem::Entry { key, value } => {
+//!                 println!("{} bytes at {:?}", value.len(), String::from_utf8_lossy(&key));
+//!             }
+//!             ContractStateItem::SplitPrefix(prefix) => {
+//!                 eprintln!("a value at {:?} may be missing", String::from_utf8_lossy(&prefix));
+//!             }
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::VecDeque;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use futures::{Stream, stream};
+
+use crate::client::{Error, NearRpcClient, Result};
+use crate::types::*;
+
+/// Errors specific to contract state dumps.
+#[derive(Debug, thiserror::Error)]
+pub enum ContractStateError {
+    #[error("value at key {} is too large for view_state", STANDARD.encode(prefix))]
+    TooLarge { prefix: Vec<u8> },
+    #[error("invalid base64 in view_state {field}: {value}")]
+    InvalidBase64 { field: &'static str, value: String },
+}
+
+/// An item of a contract state dump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractStateItem {
+    /// A key and its value.
+    Entry { key: Vec<u8>, value: Vec<u8> },
+    /// The values under this prefix were too large to fetch at once, so it
+    /// was split. A value stored at exactly this key cannot be fetched with
+    /// `view_state` and, if it exists, is not part of the dump.
+    SplitPrefix(Vec<u8>),
+}
+
+enum Task {
+    Fetch(Vec<u8>),
+    /// All sub-prefixes of a refused prefix have been fetched; `yielded` is
+    /// the [entry](ContractStateItem::Entry) count at the time it was split.
+    Finish {
+        prefix: Vec<u8>,
+        yielded: u64,
+    },
+}
+
+struct State<'a> {
+    client: &'a NearRpcClient,
+    account_id: AccountId,
+    block: RpcBlockRequest,
+    block_hash: Option<CryptoHash>,
+    tasks: Vec<Task>,
+    items: VecDeque<ContractStateItem>,
+    yielded: u64,
+}
+
+impl NearRpcClient {
+    /// Returns a stream of every key/value pair in the storage of
+    /// `account_id` at `block`, in key order, splitting the key space as
+    /// needed when the node refuses a query as too large. Each split is
+    /// reported as a [`ContractStateItem::SplitPrefix`] before the entries
+    /// under it.
+    ///
+    /// `block` is resolved to a block hash once, before the first query. The
+    /// stream ends after the first error.
+    pub fn dump_contract_state(
+        &self,
+        account_id: AccountId,
+        block: RpcBlockRequest,
+    ) -> impl Stream<Item = Result<ContractStateItem>> + '_ {
+        let state = State {
+            client: self,
+            account_id,
+            block,
+            block_hash: None,
+            tasks: vec![Task::Fetch(Vec::new())],
+            items: VecDeque::new(),
+            yielded: 0,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let item = match state.next_item().await {
+                Ok(item) => item.map(Ok),
+                Err(err) => {
+                    state.tasks.clear();
+                    state.items.clear();
+                    Some(Err(err))
+                }
+            }?;
+            Some((item, state))
+        })
+    }
+}
+
+impl State<'_> {
+    async fn next_item(&mut self) -> Result<Option<ContractStateItem>> {
+        loop {
+            if let Some(item) = self.items.pop_front() {
+                if let ContractStateItem::Entry { .. } = item {
+                    self.yielded += 1;
+                }
+                return Ok(Some(item));
+            }
+
+            let block_hash = match &self.block_hash {
+                Some(hash) => hash.clone(),
+                None => {
+                    let block = self.client.block(self.block.clone()).await?;
+                    self.block_hash.insert(block.header.hash).clone()
+                }
+            };
+
+            match self.tasks.pop() {
+                None => return Ok(None),
+                Some(Task::Finish { prefix, yielded }) => {
+                    if self.yielded == yielded {
+                        return Err(ContractStateError::TooLarge { prefix }.into());
+                    }
+                }
+                Some(Task::Fetch(prefix)) => {
+                    let response = self
+                        .client
+                        .view_state(RpcViewStateRequest::BlockIdAccountId {
+                            account_id: self.account_id.clone(),
+                            block_id: BlockId::CryptoHash(block_hash),
+                            include_proof: false,
+                            prefix_base64: StoreKey(STANDARD.encode(&prefix)),
+                        })
+                        .await;
+                    match response {
+                        Ok(response) => {
+                            for item in response.values {
+                                self.items.push_back(ContractStateItem::Entry {
+                                    key: decode("key", &item.key)?,
+                                    value: decode("value", &item.value)?,
+                                });
+                            }
+                        }
+                        Err(err) if is_too_large(&err) => self.split_prefix(prefix),
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reports the split of `prefix` and queues its 256 sub-prefixes,
+    /// smallest on top.
+    fn split_prefix(&mut self, prefix: Vec<u8>) {
+        self.items
+            .push_back(ContractStateItem::SplitPrefix(prefix.clone()));
+        self.tasks.push(Task::Finish {
+            prefix: prefix.clone(),
+            yielded: self.yielded,
+        });
+        for byte in (0..=u8::MAX).rev() {
+            let mut sub_prefix = prefix.clone();
+            sub_prefix.push(byte);
+            self.tasks.push(Task::Fetch(sub_prefix));
+        }
+    }
+}
+
+fn decode(field: &'static str, value: &str) -> std::result::Result<Vec<u8>, ContractStateError> {
+    STANDARD
+        .decode(value)
+        .map_err(|_| ContractStateError::InvalidBase64 {
+            field,
+            value: value.to_string(),
+        })
+}
+
+/// Whether the node refused a `view_state` query for exceeding its size limit.
+fn is_too_large(err: &Error) -> bool {
+    match err {
+        Error::Rpc(err) => err.cause_name() == Some("TOO_LARGE_CONTRACT_STATE"),
+        Error::LegacyQueryResult(legacy) => legacy.error.contains("is too large to be viewed"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "testing")]
+    use crate::test_utils::{MockChain, block};
+    #[cfg(feature = "testing")]
+    use crate::testing::{MockRpc, Reply};
+
+    #[test]
+    fn split_queues_sub_prefixes_in_key_order() {
+        let client = NearRpcClient::new("http://localhost:3030");
+        let mut state = State {
+            client: &client,
+            account_id: "app.near".parse().unwrap(),
+            block: RpcBlockRequest::Finality(Finality::Final),
+            block_hash: None,
+            tasks: Vec::new(),
+            items: VecDeque::new(),
+            yielded: 7,
+        };
+        state.split_prefix(b"ab".to_vec());
+
+        assert_eq!(
+            state.items.pop_front(),
+            Some(ContractStateItem::SplitPrefix(b"ab".to_vec()))
+        );
+        assert_eq!(state.tasks.len(), 257);
+        assert!(matches!(state.tasks.pop(), Some(Task::Fetch(prefix)) if prefix == b"ab\x00"));
+        assert!(matches!(state.tasks.pop(), Some(Task::Fetch(prefix)) if prefix == b"ab\x01"));
+        assert!(matches!(
+            state.tasks.first(),
+            Some(Task::Finish { prefix, yielded: 7 }) if prefix == b"ab"
+        ));
+    }
+
+    #[test]
+    fn recognises_too_large_errors() {
+        let rpc: crate::errors::RpcError = serde_json::from_value(serde_json::json!({
+            "code": -32000,
+            "message": "Server error",
+            "name": "HANDLER_ERROR",
+            "cause": {
+                "name": "TOO_LARGE_CONTRACT_STATE",
+                "info": { "contract_account_id": "app.near" }
+            }
+        }))
+        .unwrap();
+        assert!(is_too_large(&Error::Rpc(Box::new(rpc))));
+
+        let legacy = serde_json::from_value(serde_json::json!({
+            "error": "State of contract app.near is too large to be viewed",
+            "logs": []
+        }))
+        .unwrap();
+        assert!(is_too_large(&Error::LegacyQueryResult(Box::new(legacy))));
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn dumps_state_by_splitting_refused_prefixes() {
+        use futures::TryStreamExt;
+
+        // The value at "a" hides behind the values under it.
+        let store: [(&[u8], &[u8]); 3] = [(b"a", b"hidden"), (b"a\x01", b"1"), (b"b", b"2")];
+        let mock = MockRpc::new();
+        let chain = MockChain::default();
+        chain.serve(&mock);
+        chain.push(block(10, "block10", "block9"));
+        mock.on_call("EXPERIMENTAL_view_state", move |params| {
+            let prefix = STANDARD
+                .decode(params["prefix_base64"].as_str().unwrap())
+                .unwrap();
+            if prefix.is_empty() || prefix == b"a" {
+                return Reply::handler_error(
+                    "TOO_LARGE_CONTRACT_STATE",
+                    serde_json::json!({ "contract_account_id": "app.near" }),
+                );
+            }
+            let values: Vec<_> = store
+                .iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .map(|(key, value)| {
+                    serde_json::json!({
+                        "key": STANDARD.encode(key),
+                        "value": STANDARD.encode(value),
+                    })
+                })
+                .collect();
+            Reply::result(serde_json::json!({
+                "block_hash": "block10",
+                "block_height": 10,
+                "values": values,
+            }))
+        });
+        let client = mock.client();
+
+        let items: Vec<_> = client
+            .dump_contract_state(
+                "app.near".parse().unwrap(),
+                RpcBlockRequest::Finality(Finality::Final),
+            )
+            .try_collect()
+            .await
+            .unwrap();
+        let entry = |key: &[u8], value: &[u8]| ContractStateItem::Entry {
+            key: key.to_vec(),
+            value: value.to_vec(),
+        };
+        assert_eq!(
+            items,
+            [
+                ContractStateItem::SplitPrefix(Vec::new()),
+                ContractStateItem::SplitPrefix(b"a".to_vec()),
+                entry(b"a\x01", b"1"),
+                entry(b"b", b"2"),
+            ]
+        );
+        assert!(
+            mock.params("EXPERIMENTAL_view_state")
+                .iter()
+                .all(|params| params["block_id"] == "block10")
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Epoch navigation.
+//!
+//! [`EpochCursor`] finds the epoch containing a block height and steps to the
+//! previous and next epochs, stitching together `validators` (for the epoch
+//! height and start height) and `block` (for the epoch ids). Every epoch it
+//! loads is cached by [`EpochId`], so walking back and forth or asking about
+//! heights between two loaded neighbours costs no further calls.
+//!
+//! Historical epochs need an archival node; regular nodes garbage-collect old
+//! blocks after a few epochs.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::NearRpcClient;
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::new("https://archival-rpc.mainnet.near.org");
+//!     let mut epochs = client.epoch_cursor();
+//!
+//!     let epoch = epochs.epoch_of(100_000_000).await?;
+//!     println!("epoch {} starts at {}", epoch.height, epoch.start_height);
+//!
+//!     while let Some(next) = epochs.next_epoch().await? {
+//!         println!("epoch {} starts at {}", next.height, next.start_height);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::client::{NearRpcClient, Result};
+use crate::types::*;
+
+/// The previous block hash of the genesis block.
+const GENESIS_PREV_HASH: &str = "11111111111111111111111111111111";
+
+/// Metadata for one epoch.
+#[derive(Debug, Clone)]
+pub struct Epoch {
+    pub id: EpochId,
+    /// The epoch number, counting from genesis.
+    pub height: u64,
+    /// Height of the first block of the epoch.
+    pub start_height: u64,
+    /// Hash of the first block of the epoch.
+    pub start_block_hash: CryptoHash,
+    /// Hash of the last block of the previous epoch.
+    pub prev_block_hash: CryptoHash,
+    pub next_epoch_id: EpochId,
+}
+
+impl Epoch {
+    /// Whether this is the first epoch of the chain.
+    pub fn is_genesis(&self) -> bool {
+        self.prev_block_hash.0 == GENESIS_PREV_HASH
+    }
+}
+
+/// A position in the chain's epochs, with a cache of every epoch visited.
+///
+/// Created by [`NearRpcClient::epoch_cursor`].
+pub struct EpochCursor<'a> {
+    client: &'a NearRpcClient,
+    epoch_length: Option<u64>,
+    epochs: HashMap<CryptoHash, Epoch>,
+    starts: BTreeMap<u64, CryptoHash>,
+    current: Option<CryptoHash>,
+}
+
+impl NearRpcClient {
+    /// Returns an [`EpochCursor`] that is not positioned at any epoch yet.
+    pub fn epoch_cursor(&self) -> EpochCursor<'_> {
+        EpochCursor {
+            client: self,
+            epoch_length: None,
+            epochs: HashMap::new(),
+            starts: BTreeMap::new(),
+            current: None,
+        }
+    }
+}
+
+impl EpochCursor<'_> {
+    /// Sets the epoch length instead of reading it from the genesis config.
+    pub fn with_epoch_length(mut self, epoch_length: u64) -> Self {
+        self.epoch_length = Some(epoch_length);
+        self
+    }
+
+    /// The epoch the cursor is positioned at.
+    pub fn current(&self) -> Option<&Epoch> {
+        self.current.as_ref().and_then(|id| self.epochs.get(id))
+    }
+
+    /// A previously loaded epoch.
+    pub fn cached(&self, id: &EpochId) -> Option<&Epoch> {
+        self.epochs.get(&id.0)
+    }
+
+    /// The configured number of blocks per epoch, from `genesis_config`
+    /// unless set with [`with_epoch_length`](Self::with_epoch_length).
+    pub async fn epoch_length(&mut self) -> Result<u64> {
+        if let Some(length) = self.epoch_length {
+            return Ok(length);
+        }
+        let length = self.client.genesis_config().await?.epoch_length;
+        Ok(*self.epoch_length.insert(length))
+    }
+
+    /// The last height that belongs to `epoch`: exact once the following
+    /// epoch has been loaded, otherwise `start_height + epoch_length - 1`.
+    /// Epochs can run longer than the configured length when the chain
+    /// stalls, so the estimate is a lower bound.
+    pub async fn end_height(&mut self, epoch: &Epoch) -> Result<u64> {
+        match self.epochs.get(&epoch.next_epoch_id.0) {
+            Some(next) => Ok(next.start_height - 1),
+            None => Ok(epoch.start_height + self.epoch_length().await?.max(1) - 1),
+        }
+    }
+
+    /// Moves to the epoch containing the block at `height`.
+    ///
+    /// Fails with `UNKNOWN_BLOCK` if no block was produced at `height`.
+    pub async fn epoch_of(&mut self, height: u64) -> Result<Epoch> {
+        let epoch = match self.cached_epoch_of(height) {
+            Some(epoch) => epoch.clone(),
+            None => {
+                self.load(RpcValidatorRequest::BlockId(BlockId::BlockHeight(height)))
+                    .await?
+            }
+        };
+        Ok(self.move_to(epoch))
+    }
+
+    /// Moves to the epoch of the latest final block.
+    pub async fn latest(&mut self) -> Result<Epoch> {
+        let block = self
+            .client
+            .block(RpcBlockRequest::Finality(Finality::Final))
+            .await?;
+        let epoch = match self.epochs.get(&block.header.epoch_id) {
+            Some(epoch) => epoch.clone(),
+            None => {
+                self.load(RpcValidatorRequest::BlockId(BlockId::CryptoHash(
+                    block.header.hash,
+                )))
+                .await?
+            }
+        };
+        Ok(self.move_to(epoch))
+    }
+
+    /// Moves to the epoch before the current one, or returns `None` at the
+    /// genesis epoch. An unpositioned cursor starts from [`latest`](Self::latest).
+    pub async fn previous_epoch(&mut self) -> Result<Option<Epoch>> {
+        let current = self.position().await?;
+        if current.is_genesis() {
+            return Ok(None);
+        }
+
+        let cached = self
+            .epochs
+            .values()
+            .find(|epoch| epoch.next_epoch_id.0 == current.id.0)
+            .cloned();
+        let epoch = match cached {
+            Some(epoch) => epoch,
+            None => {
+                self.load(RpcValidatorRequest::BlockId(BlockId::CryptoHash(
+                    current.prev_block_hash,
+                )))
+                .await?
+            }
+        };
+        Ok(Some(self.move_to(epoch)))
+    }
+
+    /// Moves to the epoch after the current one, or returns `None` if the
+    /// current epoch is the one of the latest final block. An unpositioned
+    /// cursor starts from [`latest`](Self::latest).
+    pub async fn next_epoch(&mut self) -> Result<Option<Epoch>> {
+        let current = self.position().await?;
+        let next_id = current.next_epoch_id.0;
+
+        let epoch = match self.epochs.get(&next_id) {
+            Some(epoch) => epoch.clone(),
+            None => {
+                let head = self
+                    .client
+                    .block(RpcBlockRequest::Finality(Finality::Final))
+                    .await?
+                    .header;
+                if head.epoch_id == current.id.0 {
+                    return Ok(None);
+                }
+                // `validators` only accepts an epoch id once the epoch has
+                // finished, so the epoch in progress is looked up by block.
+                let request = if head.epoch_id == next_id {
+                    RpcValidatorRequest::BlockId(BlockId::CryptoHash(head.hash))
+                } else {
+                    RpcValidatorRequest::EpochId(EpochId(next_id))
+                };
+                self.load(request).await?
+            }
+        };
+        Ok(Some(self.move_to(epoch)))
+    }
+
+    async fn position(&mut self) -> Result<Epoch> {
+        match self.current() {
+            Some(epoch) => Ok(epoch.clone()),
+            None => self.latest().await,
+        }
+    }
+
+    /// The cached epoch containing `height`, if both it and its successor
+    /// are cached (otherwise the epoch's end is unknown).
+    fn cached_epoch_of(&self, height: u64) -> Option<&Epoch> {
+        let (&start, id) = self.starts.range(..=height).next_back()?;
+        let epoch = &self.epochs[id];
+        if start == height {
+            return Some(epoch);
+        }
+        let (_, next_id) = self.starts.range(height + 1..).next()?;
+        (*next_id == epoch.next_epoch_id.0).then_some(epoch)
+    }
+
+    async fn load(&mut self, request: RpcValidatorRequest) -> Result<Epoch> {
+        let validators = self.client.validators(request).await?;
+        let start = self
+            .client
+            .block(RpcBlockRequest::BlockId(BlockId::BlockHeight(
+                validators.epoch_start_height,
+            )))
+            .await?
+            .header;
+
+        let epoch = Epoch {
+            id: EpochId(start.epoch_id),
+            height: validators.epoch_height,
+            start_height: start.height,
+            start_block_hash: start.hash,
+            prev_block_hash: start.prev_hash,
+            next_epoch_id: EpochId(start.next_epoch_id),
+        };
+        self.insert(epoch.clone());
+        Ok(epoch)
+    }
+
+    fn insert(&mut self, epoch: Epoch) {
+        self.starts.insert(epoch.start_height, epoch.id.0.clone());
+        self.epochs.insert(epoch.id.0.clone(), epoch);
+    }
+
+    fn move_to(&mut self, epoch: Epoch) -> Epoch {
+        self.current = Some(epoch.id.0.clone());
+        epoch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn epoch(id: &str, next: &str, height: u64, start_height: u64) -> Epoch {
+        Epoch {
+            id: EpochId(CryptoHash(id.to_string())),
+            height,
+            start_height,
+            start_block_hash: CryptoHash(format!("start{height}")),
+            prev_block_hash: CryptoHash(format!("prev{height}")),
+            next_epoch_id: EpochId(CryptoHash(next.to_string())),
+        }
+    }
+
+    #[test]
+    fn answers_heights_between_cached_neighbours() {
+        let client = NearRpcClient::new("http://localhost:3030");
+        let mut cursor = client.epoch_cursor();
+        cursor.insert(epoch("a", "b", 10, 1000));
+        cursor.insert(epoch("b", "c", 11, 1100));
+        cursor.insert(epoch("d", "e", 13, 1300));
+
+        let id = |height| cursor.cached_epoch_of(height).map(|e| e.id.0.0.as_str());
+        assert_eq!(id(999), None);
+        assert_eq!(id(1000), Some("a"));
+        assert_eq!(id(1099), Some("a"));
+        assert_eq!(id(1100), Some("b"));
+        // The epoch after "b" has not been loaded, so its end is unknown.
+        assert_eq!(id(1150), None);
+        assert_eq!(id(1300), Some("d"));
+        assert_eq!(id(1301), None);
+    }
+
+    #[tokio::test]
+    async fn end_height_uses_next_epoch_when_cached() {
+        let client = NearRpcClient::new("http://localhost:3030");
+        let mut cursor = client.epoch_cursor().with_epoch_length(100);
+        let a = epoch("a", "b", 10, 1000);
+        let b = epoch("b", "c", 11, 1103);
+        cursor.insert(a.clone());
+        cursor.insert(b.clone());
+
+        assert_eq!(cursor.end_height(&a).await.unwrap(), 1102);
+        assert_eq!(cursor.end_height(&b).await.unwrap(), 1202);
+        assert!(!a.is_genesis());
+        assert!(
+            Epoch {
+                prev_block_hash: CryptoHash(GENESIS_PREV_HASH.to_string()),
+                ..a
+            }
+            .is_genesis()
+        );
+    }
+}
@@ -0,0 +1,797 @@
+//! Transaction gas and fee estimation from the runtime config.
+//!
+//! Combines the fees in [`RuntimeFeesConfigView`] (from `EXPERIMENTAL_protocol_config`)
+//! with a gas price (from `gas_price`) the same way nearcore's `tx_cost` does:
+//!
+//! - **send** gas is burnt when the transaction is converted into a receipt, and
+//!   depends on whether the signer is also the receiver ("sir").
+//! - **exec** gas is reserved for executing the receipt and its actions.
+//! - **prepaid** gas is the gas attached to function calls; it is an upper bound
+//!   on what the contract may burn, and unused gas is refunded.
+//!
+//! The yoctoNEAR cost uses a single gas price for all of it. Receipts may execute
+//! in later blocks at a higher price, so nodes pre-charge using a pessimistic
+//! inflation of the price; treat estimates as approximate for multi-block work.
+
+use crate::types::{
+    AccessKeyPermission, AccountId, ActionCreationConfigView, Fee, LimitConfig, NonDelegateAction,
+    RuntimeConfigView, RuntimeFeesConfigView,
+};
+
+/// Send and exec gas for a single action or receipt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActionGas {
+    pub send: u64,
+    pub exec: u64,
+}
+
+impl ActionGas {
+    fn from_fee(fee: &Fee, sender_is_receiver: bool) -> Self {
+        Self {
+            send: if sender_is_receiver {
+                fee.send_sir.0
+            } else {
+                fee.send_not_sir.0
+            },
+            exec: fee.execution.0,
+        }
+    }
+
+    fn scaled(fee: &Fee, sender_is_receiver: bool, count: u64) -> Self {
+        let base = Self::from_fee(fee, sender_is_receiver);
+        Self {
+            send: base.send.saturating_mul(count),
+            exec: base.exec.saturating_mul(count),
+        }
+    }
+
+    fn plus(self, other: Self) -> Self {
+        Self {
+            send: self.send.saturating_add(other.send),
+            exec: self.exec.saturating_add(other.exec),
+        }
+    }
+}
+
+/// A protocol limit from [`LimitConfig`] that a planned transaction would exceed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LimitViolation {
+    #[error("{count} actions exceed max_actions_per_receipt ({limit})")]
+    TooManyActions { count: u64, limit: u64 },
+    #[error("action #{index}: arguments of {length} bytes exceed max_arguments_length ({limit})")]
+    ArgumentsTooLong {
+        index: usize,
+        length: u64,
+        limit: u64,
+    },
+    #[error(
+        "action #{index}: method name of {length} bytes exceeds max_length_method_name ({limit})"
+    )]
+    MethodNameTooLong {
+        index: usize,
+        length: u64,
+        limit: u64,
+    },
+    #[error("action #{index}: contract of {size} bytes exceeds max_contract_size ({limit})")]
+    ContractTooLarge { index: usize, size: u64, limit: u64 },
+    #[error(
+        "action #{index}: access key method names of {length} bytes exceed max_number_bytes_method_names ({limit})"
+    )]
+    MethodNamesTooLong {
+        index: usize,
+        length: u64,
+        limit: u64,
+    },
+    #[error("action #{index}: function call attaches no gas")]
+    ZeroAttachedGas { index: usize },
+    #[error("total prepaid gas {total} exceeds max_total_prepaid_gas ({limit})")]
+    TotalPrepaidGasExceeded { total: u64, limit: u64 },
+}
+
+/// Errors from estimating a planned transaction.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FeeError {
+    #[error("action #{index}: invalid deposit {amount:?}")]
+    InvalidDeposit { index: usize, amount: String },
+    /// The node reported a gas price that is not a number of yoctoNEAR.
+    #[error("invalid gas price {0:?}")]
+    InvalidGasPrice(String),
+}
+
+/// Gas breakdown for a planned transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasEstimate {
+    /// Gas burnt when converting the transaction into a receipt.
+    pub send_gas: u64,
+    /// Gas reserved for executing the receipt and its actions.
+    pub exec_gas: u64,
+    /// Gas attached to function calls.
+    pub prepaid_gas: u64,
+    /// Sum of the deposits attached to the actions, in yoctoNEAR.
+    pub total_deposit: u128,
+    /// Actions whose fees are not part of [`RuntimeFeesConfigView`]
+    /// (global contracts, deterministic state init, gas keys), by index and name.
+    /// They are counted as zero gas.
+    pub unpriced_actions: Vec<(usize, &'static str)>,
+    /// Protocol limits the transaction would exceed.
+    pub limit_violations: Vec<LimitViolation>,
+}
+
+impl GasEstimate {
+    /// Returns the total gas: send + exec + prepaid.
+    pub fn total_gas(&self) -> u64 {
+        self.send_gas
+            .saturating_add(self.exec_gas)
+            .saturating_add(self.prepaid_gas)
+    }
+
+    /// Returns the cost of all gas at `gas_price` (yoctoNEAR per gas).
+    pub fn gas_cost(&self, gas_price: u128) -> u128 {
+        (self.total_gas() as u128).saturating_mul(gas_price)
+    }
+
+    /// Returns the gas cost plus attached deposits at `gas_price`.
+    pub fn total_cost(&self, gas_price: u128) -> u128 {
+        self.gas_cost(gas_price).saturating_add(self.total_deposit)
+    }
+
+    /// Adds the gas of an extra receipt, e.g. from
+    /// [`FeeEstimator::data_receipt_gas`].
+    pub fn add(&mut self, gas: ActionGas) {
+        self.send_gas = self.send_gas.saturating_add(gas.send);
+        self.exec_gas = self.exec_gas.saturating_add(gas.exec);
+    }
+
+    /// Returns `true` if the transaction stays within all protocol limits.
+    pub fn within_limits(&self) -> bool {
+        self.limit_violations.is_empty()
+    }
+}
+
+/// Computes gas for actions from the fees and limits of a [`RuntimeConfigView`].
+///
+/// # Example
+///
+/// ```no_run
+/// use near_openrpc_client::fees::FeeEstimator;
+/// use near_openrpc_client::{NearRpcClient, types::*};
+///
+/// #[tokio::main]
+/// async fn main() -> near_openrpc_client::client::Result<()> {
+///     let config = NearRpcClient::mainnet()
+///         .protocol_config(RpcProtocolConfigRequest::Finality(Finality::Final))
+///         .await?;
+///     let estimator = FeeEstimator::from_runtime_config(&config.runtime_config);
+///     let estimate = estimator.estimate(
+///         &"alice.near".parse().unwrap(),
+///         &"bob.near".parse().unwrap(),
+///         &[NonDelegateAction::Transfer(TransferAction {
+///             deposit: NearToken("1".to_string()),
+///         })],
+///     )?;
+///     println!("{} gas, {} yoctoNEAR", estimate.total_gas(), estimate.total_cost(100_000_000));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FeeEstimator {
+    fees: RuntimeFeesConfigView,
+    limits: LimitConfig,
+}
+
+impl FeeEstimator {
+    /// Create an estimator from transaction fees and VM limits.
+    pub fn new(fees: RuntimeFeesConfigView, limits: LimitConfig) -> Self {
+        Self { fees, limits }
+    }
+
+    /// Create an estimator from the `runtime_config` of a protocol config.
+    pub fn from_runtime_config(config: &RuntimeConfigView) -> Self {
+        Self::new(
+            config.transaction_costs.clone(),
+            config.wasm_config.limit_config.clone(),
+        )
+    }
+
+    /// Returns the fees used for estimates.
+    pub fn fees(&self) -> &RuntimeFeesConfigView {
+        &self.fees
+    }
+
+    /// Returns the limits checked by [`check_limits`](Self::check_limits).
+    pub fn limits(&self) -> &LimitConfig {
+        &self.limits
+    }
+
+    fn action_fees(&self) -> &ActionCreationConfigView {
+        &self.fees.action_creation_config
+    }
+
+    /// Returns the gas for creating an action receipt, excluding its actions.
+    pub fn receipt_creation_gas(&self, sender_is_receiver: bool) -> ActionGas {
+        ActionGas::from_fee(
+            &self.fees.action_receipt_creation_config,
+            sender_is_receiver,
+        )
+    }
+
+    /// Returns the gas for a data receipt carrying `data_len` bytes, as created
+    /// for each output dependency of a promise (`promise_then`).
+    pub fn data_receipt_gas(&self, sender_is_receiver: bool, data_len: u64) -> ActionGas {
+        let config = &self.fees.data_receipt_creation_config;
+        ActionGas::from_fee(&config.base_cost, sender_is_receiver).plus(ActionGas::scaled(
+            &config.cost_per_byte,
+            sender_is_receiver,
+            data_len,
+        ))
+    }
+
+    /// Returns the send/exec gas of a single action, or `None` if its fee is not
+    /// part of the runtime fees view.
+    ///
+    /// `receiver_id` matters for transfers to implicit accounts, which also pay
+    /// for creating the account (and, for NEAR-implicit accounts, its key).
+    pub fn action_gas(
+        &self,
+        action: &NonDelegateAction,
+        sender_is_receiver: bool,
+        receiver_id: &AccountId,
+    ) -> Option<ActionGas> {
+        let fees = self.action_fees();
+        let sir = sender_is_receiver;
+        let gas = match action {
+            NonDelegateAction::CreateAccount(_) => {
+                ActionGas::from_fee(&fees.create_account_cost, sir)
+            }
+            NonDelegateAction::DeployContract(action) => {
+                ActionGas::from_fee(&fees.deploy_contract_cost, sir).plus(ActionGas::scaled(
+                    &fees.deploy_contract_cost_per_byte,
+                    sir,
+                    base64_decoded_len(&action.code),
+                ))
+            }
+            NonDelegateAction::FunctionCall(action) => {
+                let bytes = action.method_name.len() as u64 + base64_decoded_len(&action.args);
+                ActionGas::from_fee(&fees.function_call_cost, sir).plus(ActionGas::scaled(
+                    &fees.function_call_cost_per_byte,
+                    sir,
+                    bytes,
+                ))
+            }
+            NonDelegateAction::Transfer(_) => {
+                let transfer = ActionGas::from_fee(&fees.transfer_cost, sir);
+                match implicit_account_kind(receiver_id) {
+                    Some(ImplicitAccount::Near) => transfer
+                        .plus(ActionGas::from_fee(&fees.create_account_cost, sir))
+                        .plus(ActionGas::from_fee(
+                            &fees.add_key_cost.full_access_cost,
+                            sir,
+                        )),
+                    Some(ImplicitAccount::Eth) => {
+                        transfer.plus(ActionGas::from_fee(&fees.create_account_cost, sir))
+                    }
+                    None => transfer,
+                }
+            }
+            NonDelegateAction::Stake(_) => ActionGas::from_fee(&fees.stake_cost, sir),
+            NonDelegateAction::AddKey(action) => match &action.access_key.permission {
+                AccessKeyPermission::FunctionCall(permission)
+                | AccessKeyPermission::GasKeyFunctionCall(_, permission) => {
+                    ActionGas::from_fee(&fees.add_key_cost.function_call_cost, sir).plus(
+                        ActionGas::scaled(
+                            &fees.add_key_cost.function_call_cost_per_byte,
+                            sir,
+                            method_names_len(&permission.method_names),
+                        ),
+                    )
+                }
+                AccessKeyPermission::FullAccess | AccessKeyPermission::GasKeyFullAccess(_) => {
+                    ActionGas::from_fee(&fees.add_key_cost.full_access_cost, sir)
+                }
+            },
+            NonDelegateAction::DeleteKey(_) => ActionGas::from_fee(&fees.delete_key_cost, sir),
+            NonDelegateAction::DeleteAccount(_) => {
+                ActionGas::from_fee(&fees.delete_account_cost, sir)
+            }
+            NonDelegateAction::DeployGlobalContract(_)
+            | NonDelegateAction::UseGlobalContract(_)
+            | NonDelegateAction::DeterministicStateInit(_)
+            | NonDelegateAction::TransferToGasKey(_)
+            | NonDelegateAction::WithdrawFromGasKey(_) => return None,
+        };
+        Some(gas)
+    }
+
+    /// Estimates gas for a transaction from `signer_id` to `receiver_id`, and
+    /// checks the actions against [`LimitConfig`].
+    ///
+    /// Fails if an attached deposit is not a valid yoctoNEAR amount.
+    pub fn estimate(
+        &self,
+        signer_id: &AccountId,
+        receiver_id: &AccountId,
+        actions: &[NonDelegateAction],
+    ) -> Result<GasEstimate, FeeError> {
+        let sender_is_receiver = signer_id == receiver_id;
+        let mut estimate = GasEstimate::default();
+        estimate.add(self.receipt_creation_gas(sender_is_receiver));
+
+        for (index, action) in actions.iter().enumerate() {
+            match self.action_gas(action, sender_is_receiver, receiver_id) {
+                Some(gas) => estimate.add(gas),
+                None => estimate
+                    .unpriced_actions
+                    .push((index, crate::access_key::action_name(action))),
+            }
+            estimate.prepaid_gas = estimate.prepaid_gas.saturating_add(prepaid_gas(action));
+            estimate.total_deposit = estimate
+                .total_deposit
+                .saturating_add(deposit(index, action)?);
+        }
+
+        estimate.limit_violations = self.check_limits(actions);
+        Ok(estimate)
+    }
+
+    /// Returns the [`LimitConfig`] limits that `actions` would exceed.
+    pub fn check_limits(&self, actions: &[NonDelegateAction]) -> Vec<LimitViolation> {
+        let limits = self.limits();
+        let mut violations = Vec::new();
+
+        let count = actions.len() as u64;
+        if count > limits.max_actions_per_receipt {
+            violations.push(LimitViolation::TooManyActions {
+                count,
+                limit: limits.max_actions_per_receipt,
+            });
+        }
+
+        let mut total_prepaid = 0u64;
+        for (index, action) in actions.iter().enumerate() {
+            match action {
+                NonDelegateAction::FunctionCall(call) => {
+                    let length = call.method_name.len() as u64;
+                    if length > limits.max_length_method_name {
+                        violations.push(LimitViolation::MethodNameTooLong {
+                            index,
+                            length,
+                            limit: limits.max_length_method_name,
+                        });
+                    }
+                    let length = base64_decoded_len(&call.args);
+                    if length > limits.max_arguments_length {
+                        violations.push(LimitViolation::ArgumentsTooLong {
+                            index,
+                            length,
+                            limit: limits.max_arguments_length,
+                        });
+                    }
+                    if call.gas.0 == 0 {
+                        violations.push(LimitViolation::ZeroAttachedGas { index });
+                    }
+                    total_prepaid = total_prepaid.saturating_add(call.gas.0);
+                }
+                NonDelegateAction::DeployContract(deploy) => {
+                    let size = base64_decoded_len(&deploy.code);
+                    if size > limits.max_contract_size {
+                        violations.push(LimitViolation::ContractTooLarge {
+                            index,
+                            size,
+                            limit: limits.max_contract_size,
+                        });
+                    }
+                }
+                NonDelegateAction::DeployGlobalContract(deploy) => {
+                    let size = base64_decoded_len(&deploy.code);
+                    if size > limits.max_contract_size {
+                        violations.push(LimitViolation::ContractTooLarge {
+                            index,
+                            size,
+                            limit: limits.max_contract_size,
+                        });
+                    }
+                }
+                NonDelegateAction::AddKey(add_key) => {
+                    if let AccessKeyPermission::FunctionCall(permission)
+                    | AccessKeyPermission::GasKeyFunctionCall(_, permission) =
+                        &add_key.access_key.permission
+                    {
+                        let length = method_names_len(&permission.method_names);
+                        if length > limits.max_number_bytes_method_names {
+                            violations.push(LimitViolation::MethodNamesTooLong {
+                                index,
+                                length,
+                                limit: limits.max_number_bytes_method_names,
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if total_prepaid > limits.max_total_prepaid_gas.0 {
+            violations.push(LimitViolation::TotalPrepaidGasExceeded {
+                total: total_prepaid,
+                limit: limits.max_total_prepaid_gas.0,
+            });
+        }
+
+        violations
+    }
+}
+
+fn prepaid_gas(action: &NonDelegateAction) -> u64 {
+    match action {
+        NonDelegateAction::FunctionCall(call) => call.gas.0,
+        _ => 0,
+    }
+}
+
+fn deposit(index: usize, action: &NonDelegateAction) -> Result<u128, FeeError> {
+    let amount = match action {
+        NonDelegateAction::FunctionCall(call) => &call.deposit,
+        NonDelegateAction::Transfer(transfer) => &transfer.deposit,
+        NonDelegateAction::DeterministicStateInit(init) => &init.deposit,
+        NonDelegateAction::TransferToGasKey(transfer) => &transfer.deposit,
+        _ => return Ok(0),
+    };
+    amount.parse().map_err(|_| FeeError::InvalidDeposit {
+        index,
+        amount: amount.0.clone(),
+    })
+}
+
+/// Bytes charged for access key method names: each name plus a terminator.
+fn method_names_len(method_names: &[String]) -> u64 {
+    method_names.iter().map(|name| name.len() as u64 + 1).sum()
+}
+
+/// Length of the data encoded by a padded base64 string, without decoding it.
+fn base64_decoded_len(encoded: &str) -> u64 {
+    let encoded = encoded.trim_end();
+    let padding = encoded.bytes().rev().take_while(|&b| b == b'=').count();
+    ((encoded.len() / 4 * 3) + ((encoded.len() % 4) * 3 / 4)).saturating_sub(padding) as u64
+}
+
+enum ImplicitAccount {
+    Near,
+    Eth,
+}
+
+fn implicit_account_kind(account_id: &AccountId) -> Option<ImplicitAccount> {
+    let id = account_id.as_str();
+    if id.len() == 64 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        Some(ImplicitAccount::Near)
+    } else if id.len() == 42
+        && id.starts_with("0x")
+        && id[2..]
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    {
+        Some(ImplicitAccount::Eth)
+    } else {
+        None
+    }
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+fn parse_gas_price(gas_price: &crate::types::NearToken) -> Result<u128, FeeError> {
+    gas_price
+        .parse()
+        .map_err(|_| FeeError::InvalidGasPrice(gas_price.0.clone()))
+}
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use super::{FeeEstimator, GasEstimate, parse_gas_price};
+    use crate::client::{NearRpcClient, Result};
+    use crate::types::*;
+
+    /// A [`GasEstimate`] priced at a specific gas price.
+    #[derive(Debug, Clone)]
+    pub struct FeeEstimate {
+        pub gas: GasEstimate,
+        /// Gas price in yoctoNEAR per gas.
+        pub gas_price: u128,
+    }
+
+    impl FeeEstimate {
+        /// Returns the cost of the estimated gas in yoctoNEAR.
+        pub fn gas_cost(&self) -> u128 {
+            self.gas.gas_cost(self.gas_price)
+        }
+
+        /// Returns the gas cost plus attached deposits in yoctoNEAR.
+        pub fn total_cost(&self) -> u128 {
+            self.gas.total_cost(self.gas_price)
+        }
+    }
+
+    impl NearRpcClient {
+        /// Returns a [`FeeEstimator`] for the runtime config at final finality.
+        pub async fn fee_estimator(&self) -> Result<FeeEstimator> {
+            let config = self
+                .protocol_config(RpcProtocolConfigRequest::Finality(Finality::Final))
+                .await?;
+            Ok(FeeEstimator::from_runtime_config(&config.runtime_config))
+        }
+
+        /// Estimates gas and cost for a transaction using the current runtime
+        /// config, priced at `gas_price` or, if `None`, the latest gas price.
+        pub async fn estimate_transaction_fee(
+            &self,
+            signer_id: &AccountId,
+            receiver_id: &AccountId,
+            actions: &[NonDelegateAction],
+            gas_price: Option<u128>,
+        ) -> Result<FeeEstimate> {
+            let estimator = self.fee_estimator().await?;
+            let gas_price = match gas_price {
+                Some(price) => price,
+                None => {
+                    let response = self
+                        .gas_price(RpcGasPriceRequest { block_id: None })
+                        .await?;
+                    parse_gas_price(&response.gas_price)?
+                }
+            };
+            Ok(FeeEstimate {
+                gas: estimator.estimate(signer_id, receiver_id, actions)?,
+                gas_price,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+pub use client_ext::FeeEstimate;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+
+    fn fee(send_sir: u64, send_not_sir: u64, execution: u64) -> serde_json::Value {
+        serde_json::json!({
+            "send_sir": send_sir,
+            "send_not_sir": send_not_sir,
+            "execution": execution,
+        })
+    }
+
+    fn estimator() -> FeeEstimator {
+        let fees: RuntimeFeesConfigView = serde_json::from_value(serde_json::json!({
+            "action_receipt_creation_config": fee(100, 110, 120),
+            "action_creation_config": {
+                "add_key_cost": {
+                    "full_access_cost": fee(10, 11, 12),
+                    "function_call_cost": fee(20, 21, 22),
+                    "function_call_cost_per_byte": fee(1, 2, 3),
+                },
+                "create_account_cost": fee(30, 31, 32),
+                "delegate_cost": fee(0, 0, 0),
+                "delete_account_cost": fee(40, 41, 42),
+                "delete_key_cost": fee(50, 51, 52),
+                "deploy_contract_cost": fee(60, 61, 62),
+                "deploy_contract_cost_per_byte": fee(1, 2, 3),
+                "function_call_cost": fee(70, 71, 72),
+                "function_call_cost_per_byte": fee(1, 2, 3),
+                "stake_cost": fee(80, 81, 82),
+                "transfer_cost": fee(90, 91, 92),
+            },
+            "data_receipt_creation_config": {
+                "base_cost": fee(5, 6, 7),
+                "cost_per_byte": fee(1, 2, 3),
+            },
+            "burnt_gas_reward": [3, 10],
+            "pessimistic_gas_price_inflation_ratio": [103, 100],
+            "storage_usage_config": { "num_bytes_account": 100, "num_extra_bytes_record": 40 },
+        }))
+        .unwrap();
+
+        let limits: LimitConfig = serde_json::from_value(serde_json::json!({
+            "initial_memory_pages": 1024,
+            "max_actions_per_receipt": 2,
+            "max_arguments_length": 4,
+            "max_contract_size": 4,
+            "max_gas_burnt": 300,
+            "max_length_method_name": 8,
+            "max_length_returned_data": 0,
+            "max_length_storage_key": 0,
+            "max_length_storage_value": 0,
+            "max_memory_pages": 2048,
+            "max_number_bytes_method_names": 6,
+            "max_number_input_data_dependencies": 0,
+            "max_number_logs": 0,
+            "max_number_registers": 0,
+            "max_promises_per_function_call_action": 0,
+            "max_receipt_size": 0,
+            "max_register_size": 0,
+            "max_stack_height": 0,
+            "max_total_log_length": 0,
+            "max_total_prepaid_gas": 300,
+            "max_transaction_size": 0,
+            "max_yield_payload_size": 0,
+            "per_receipt_storage_proof_size_limit": 0,
+            "registers_memory_limit": 0,
+            "yield_timeout_length_in_blocks": 0,
+        }))
+        .unwrap();
+
+        FeeEstimator::new(fees, limits)
+    }
+
+    fn account(id: &str) -> AccountId {
+        id.parse().unwrap()
+    }
+
+    fn function_call(method_name: &str, args: &str, gas: u64) -> NonDelegateAction {
+        NonDelegateAction::FunctionCall(FunctionCallAction {
+            args: args.to_string(),
+            deposit: NearToken("0".to_string()),
+            gas: NearGas(gas),
+            method_name: method_name.to_string(),
+        })
+    }
+
+    #[test]
+    fn transfer_to_named_account() {
+        let estimate = estimator()
+            .estimate(
+                &account("alice.near"),
+                &account("bob.near"),
+                &[NonDelegateAction::Transfer(TransferAction {
+                    deposit: NearToken("1000".to_string()),
+                })],
+            )
+            .unwrap();
+        assert_eq!(estimate.send_gas, 110 + 91);
+        assert_eq!(estimate.exec_gas, 120 + 92);
+        assert_eq!(estimate.prepaid_gas, 0);
+        assert_eq!(estimate.total_deposit, 1000);
+        assert_eq!(estimate.total_cost(2), (110 + 91 + 120 + 92) * 2 + 1000);
+        assert!(estimate.within_limits());
+    }
+
+    #[test]
+    fn rejects_malformed_deposits() {
+        let err = estimator()
+            .estimate(
+                &account("alice.near"),
+                &account("bob.near"),
+                &[
+                    function_call("set", "", 100),
+                    NonDelegateAction::Transfer(TransferAction {
+                        deposit: NearToken("1 NEAR".to_string()),
+                    }),
+                ],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FeeError::InvalidDeposit {
+                index: 1,
+                amount: "1 NEAR".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn transfer_to_implicit_account_includes_account_creation() {
+        let implicit = account(&"a".repeat(64));
+        let gas = estimator()
+            .action_gas(
+                &NonDelegateAction::Transfer(TransferAction {
+                    deposit: NearToken("1".to_string()),
+                }),
+                false,
+                &implicit,
+            )
+            .unwrap();
+        assert_eq!(gas.send, 91 + 31 + 11);
+        assert_eq!(gas.exec, 92 + 32 + 12);
+    }
+
+    #[test]
+    fn function_call_to_self_uses_sir_fees() {
+        // "c2V0" is base64 for "set": 3 bytes of args plus 3 bytes of method name.
+        let estimate = estimator()
+            .estimate(
+                &account("alice.near"),
+                &account("alice.near"),
+                &[function_call("set", "c2V0", 100)],
+            )
+            .unwrap();
+        assert_eq!(estimate.send_gas, 100 + 70 + 6);
+        assert_eq!(estimate.exec_gas, 120 + 72 + 6 * 3);
+        assert_eq!(estimate.prepaid_gas, 100);
+        assert_eq!(estimate.total_gas(), 100 + 70 + 6 + 120 + 72 + 18 + 100);
+    }
+
+    #[test]
+    fn data_receipt_gas_scales_with_bytes() {
+        let gas = estimator().data_receipt_gas(false, 10);
+        assert_eq!(
+            gas,
+            ActionGas {
+                send: 6 + 20,
+                exec: 7 + 30
+            }
+        );
+    }
+
+    #[test]
+    fn flags_limit_violations_and_unpriced_actions() {
+        let estimate = estimator()
+            .estimate(
+                &account("alice.near"),
+                &account("bob.near"),
+                &[
+                    function_call("a_very_long_name", "AAAAAAAA", 200),
+                    function_call("set", "", 0),
+                    NonDelegateAction::WithdrawFromGasKey(WithdrawFromGasKeyAction {
+                        amount: NearToken("1".to_string()),
+                        public_key: PublicKey(
+                            "ed25519:11111111111111111111111111111111".to_string(),
+                        ),
+                    }),
+                ],
+            )
+            .unwrap();
+        assert_eq!(estimate.unpriced_actions, vec![(2, "WithdrawFromGasKey")]);
+        assert_eq!(
+            estimate.limit_violations,
+            vec![
+                LimitViolation::TooManyActions { count: 3, limit: 2 },
+                LimitViolation::MethodNameTooLong {
+                    index: 0,
+                    length: 16,
+                    limit: 8,
+                },
+                LimitViolation::ArgumentsTooLong {
+                    index: 0,
+                    length: 6,
+                    limit: 4,
+                },
+                LimitViolation::ZeroAttachedGas { index: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_total_prepaid_gas() {
+        let violations = estimator().check_limits(&[function_call("set", "", 301)]);
+        assert_eq!(
+            violations,
+            vec![LimitViolation::TotalPrepaidGasExceeded {
+                total: 301,
+                limit: 300,
+            }]
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn rejects_malformed_gas_prices() {
+        assert_eq!(
+            parse_gas_price(&NearToken("100000000".to_string())).unwrap(),
+            100_000_000
+        );
+        assert!(matches!(
+            parse_gas_price(&NearToken("0.1".to_string())),
+            Err(FeeError::InvalidGasPrice(price)) if price == "0.1"
+        ));
+    }
+
+    #[test]
+    fn base64_lengths() {
+        assert_eq!(base64_decoded_len(""), 0);
+        assert_eq!(base64_decoded_len("YQ=="), 1);
+        assert_eq!(base64_decoded_len("YWI="), 2);
+        assert_eq!(base64_decoded_len("YWJj"), 3);
+        assert_eq!(base64_decoded_len("YWJjZA"), 4);
+    }
+}
@@ -0,0 +1,269 @@
+//! Record-and-replay fixtures of RPC traffic.
+//!
+//! [`Fixtures`] is a [`Middleware`] with two modes. When recording, it sends
+//! each call on to the node and writes the request and response to a JSON
+//! file in a fixture directory. When replaying, it answers each call from
+//! those files without touching the network, and fails calls that were never
+//! recorded with [`FixtureError::Missing`].
+//!
+//! A fixture is keyed by the JSON-RPC method and its params with object keys
+//! sorted, and stored as `<method>-<hash of the key>.json`:
+//!
+//! ```json
+//! { "method": "block", "params": { "finality": "final" }, "response": { "result": { ... } } }
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::fixtures::Fixtures;
+//! use near_openrpc_client::{NearRpcClient, types::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     // Records with NEAR_RPC_RECORD=1, replays otherwise.
+//!     let client = NearRpcClient::mainnet().with_middleware(Fixtures::from_env("tests/fixtures"));
+//!     let block = client.block(RpcBlockRequest::Finality(Finality::Final)).await?;
+//!     println!("block {}", block.header.height);
+//!     Ok(())
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::client::Error;
+use crate::middleware::{HandleFuture, Middleware, Next, RpcCall};
+
+/// Set to record fixtures in [`Fixtures::from_env`].
+pub const RECORD_ENV: &str = "NEAR_RPC_RECORD";
+
+/// Errors from reading or writing fixtures, returned as
+/// [`Error::Middleware`].
+#[derive(Debug, thiserror::Error)]
+pub enum FixtureError {
+    #[error("no fixture recorded for {method} with params {params} (expected at {path})")]
+    Missing {
+        method: String,
+        params: Value,
+        path: PathBuf,
+    },
+    #[error("invalid fixture {path}: {source}")]
+    Invalid {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("fixture I/O error at {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Whether [`Fixtures`] writes or serves responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Record,
+    Replay,
+}
+
+/// One recorded exchange, as stored on disk.
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    method: String,
+    params: Value,
+    /// The response's `result` or `error` member.
+    response: Map<String, Value>,
+}
+
+/// A middleware recording RPC traffic to, or replaying it from, a directory.
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    dir: PathBuf,
+    mode: Mode,
+}
+
+impl Fixtures {
+    pub fn new(dir: impl Into<PathBuf>, mode: Mode) -> Self {
+        Self {
+            dir: dir.into(),
+            mode,
+        }
+    }
+
+    /// Records every exchange into `dir`, overwriting earlier recordings of
+    /// the same call.
+    pub fn record(dir: impl Into<PathBuf>) -> Self {
+        Self::new(dir, Mode::Record)
+    }
+
+    /// Serves every call from the recordings in `dir`.
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self::new(dir, Mode::Replay)
+    }
+
+    /// Records if the [`RECORD_ENV`] environment variable is set to anything
+    /// but `0`, and replays otherwise.
+    pub fn from_env(dir: impl Into<PathBuf>) -> Self {
+        let record = std::env::var(RECORD_ENV).is_ok_and(|value| value != "0");
+        Self::new(dir, if record { Mode::Record } else { Mode::Replay })
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// The file a call's fixture is stored in.
+    pub fn path(&self, method: &str, params: &Value) -> PathBuf {
+        let key = format!("{method}:{}", canonicalize(params));
+        let hash = Sha256::digest(key.as_bytes());
+        let hex: String = hash[..8].iter().map(|byte| format!("{byte:02x}")).collect();
+        self.dir.join(format!("{method}-{hex}.json"))
+    }
+
+    fn load(&self, call: &RpcCall) -> std::result::Result<Value, FixtureError> {
+        let path = self.path(&call.method, &call.params);
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(FixtureError::Missing {
+                    method: call.method.clone(),
+                    params: call.params.clone(),
+                    path,
+                });
+            }
+            Err(source) => return Err(FixtureError::Io { path, source }),
+        };
+        let fixture: Fixture = serde_json::from_slice(&contents)
+            .map_err(|source| FixtureError::Invalid { path, source })?;
+        let mut response = json!({ "jsonrpc": "2.0", "id": call.id });
+        response
+            .as_object_mut()
+            .expect("response is an object")
+            .extend(fixture.response);
+        Ok(response)
+    }
+
+    fn save(&self, call: &RpcCall, response: &Value) -> std::result::Result<(), FixtureError> {
+        let path = self.path(&call.method, &call.params);
+        let fixture = Fixture {
+            method: call.method.clone(),
+            params: canonicalize(&call.params),
+            response: ["result", "error"]
+                .into_iter()
+                .filter_map(|field| Some((field.to_string(), response.get(field)?.clone())))
+                .collect(),
+        };
+        let contents = serde_json::to_vec_pretty(&fixture).expect("fixtures serialize");
+        write(&path, &contents).map_err(|source| FixtureError::Io { path, source })
+    }
+}
+
+impl Middleware for Fixtures {
+    fn handle<'a>(&'a self, call: RpcCall, next: Next<'a>) -> HandleFuture<'a> {
+        Box::pin(async move {
+            match self.mode {
+                Mode::Replay => self.load(&call).map_err(middleware_error),
+                Mode::Record => {
+                    let response = next.run(call.clone()).await?;
+                    self.save(&call, &response).map_err(middleware_error)?;
+                    Ok(response)
+                }
+            }
+        })
+    }
+}
+
+fn middleware_error(err: FixtureError) -> Error {
+    Error::Middleware(Box::new(err))
+}
+
+fn write(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, contents)
+}
+
+/// `value` with the keys of every object sorted, so equal params produce
+/// the same key whatever order they were built in.
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonicalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NearRpcClient;
+    use crate::testing::{MockRpc, Reply};
+    use crate::types::*;
+
+    #[tokio::test]
+    async fn replays_recorded_exchanges() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockRpc::new();
+        mock.on(
+            "gas_price",
+            Reply::result(json!({ "gas_price": "100000000" })),
+        )
+        .on("status", Reply::handler_error("NODE_SYNCING", json!({})));
+
+        let request = || RpcGasPriceRequest {
+            block_id: Some(BlockId::BlockHeight(10)),
+        };
+        let recorder = mock.client().with_middleware(Fixtures::record(dir.path()));
+        recorder.gas_price(request()).await.unwrap();
+        recorder.status().await.unwrap_err();
+
+        // Replay never reaches the (unreachable) node.
+        let replayer =
+            NearRpcClient::new("http://127.0.0.1:9").with_middleware(Fixtures::replay(dir.path()));
+        let price = replayer.gas_price(request()).await.unwrap();
+        assert_eq!(price.gas_price.parse::<u128>().unwrap(), 100_000_000);
+        let Err(Error::Rpc(err)) = replayer.status().await else {
+            panic!("expected the recorded error");
+        };
+        assert_eq!(err.cause_name(), Some("NODE_SYNCING"));
+
+        let Err(Error::Middleware(err)) = replayer
+            .gas_price(RpcGasPriceRequest { block_id: None })
+            .await
+        else {
+            panic!("expected a missing fixture");
+        };
+        assert!(matches!(
+            err.downcast_ref::<FixtureError>(),
+            Some(FixtureError::Missing { .. })
+        ));
+        assert_eq!(mock.calls().len(), 2);
+    }
+
+    #[test]
+    fn keys_ignore_object_key_order() {
+        let fixtures = Fixtures::replay("fixtures");
+        assert_eq!(
+            fixtures.path("block", &json!({ "a": 1, "b": { "c": 2, "d": 3 } })),
+            fixtures.path("block", &json!({ "b": { "d": 3, "c": 2 }, "a": 1 }))
+        );
+        assert_ne!(
+            fixtures.path("block", &json!({ "block_id": 1 })),
+            fixtures.path("chunk", &json!({ "block_id": 1 }))
+        );
+    }
+}
@@ -56,6 +56,13 @@ pub struct AccessKeyCreationConfigView {
     ///Cost per byte of method_names of creating a restricted access-key.
     pub function_call_cost_per_byte: Fee,
 }
+///`AccessKeyDeletionChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct AccessKeyDeletionChange {
+    pub account_id: AccountId,
+    pub public_key: PublicKey,
+}
 ///Describes information about an access key including the public key.
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -114,6 +121,14 @@ pub enum AccessKeyPermissionView {
     },
     GasKeyFullAccess { balance: NearToken, num_nonces: u16 },
 }
+///`AccessKeyUpdateChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct AccessKeyUpdateChange {
+    pub access_key: AccessKeyView,
+    pub account_id: AccountId,
+    pub public_key: PublicKey,
+}
 ///Describes access key permission scope and nonce.
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -157,6 +172,12 @@ messages to the validator.*/
     ///UTC timestamp of when the AccountData has been signed.
     pub timestamp: ::std::string::String,
 }
+///`AccountDeletionChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct AccountDeletionChange {
+    pub account_id: AccountId,
+}
 /**NEAR Account Identifier.
 
 This is a unique, syntactically valid, human-readable account identifier on the NEAR network.
@@ -267,6 +288,23 @@ pub struct AccountInfo {
     pub amount: NearToken,
     pub public_key: PublicKey,
 }
+///A view of the account
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct AccountUpdateChange {
+    pub account_id: AccountId,
+    pub amount: NearToken,
+    pub code_hash: CryptoHash,
+    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
+    pub global_contract_account_id: ::std::option::Option<AccountId>,
+    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
+    pub global_contract_hash: ::std::option::Option<CryptoHash>,
+    pub locked: NearToken,
+    ///TODO(2271): deprecated.
+    #[serde(default)]
+    pub storage_paid_at: u64,
+    pub storage_usage: u64,
+}
 ///Account ID with its public key.
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -929,6 +967,8 @@ It limits the total sum of outgoing receipts, not individual receipts.*/
 This limit is pretty small to keep the size of source_receipt_proofs under control.
 It limits the total sum of outgoing receipts, not individual receipts.*/
     pub outgoing_receipts_usual_size_limit: u64,
+    /**How much congestion a shard can tolerate before it stops all shards from
+accepting new transactions with the receiver set to the congested shard.*/
     pub reject_tx_congestion_threshold: f64,
 }
 ///Stores the congestion level of a shard. More info about congestion [here](https://near.github.io/nearcore/architecture/how/receipt-congestion.html?highlight=congestion#receipt-congestion)
@@ -940,6 +980,19 @@ pub struct CongestionInfoView {
     pub delayed_receipts_gas: ::std::string::String,
     pub receipt_bytes: u64,
 }
+///`ContractCodeDeletionChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct ContractCodeDeletionChange {
+    pub account_id: AccountId,
+}
+///`ContractCodeUpdateChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct ContractCodeUpdateChange {
+    pub account_id: AccountId,
+    pub code_base64: ::std::string::String,
+}
 ///Shows gas profile. More info [here](https://near.github.io/nearcore/architecture/gas/gas_profile.html?highlight=WASM_HOST_COST#example-transaction-gas-profile).
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -1054,6 +1107,13 @@ Each entry in the array corresponds to the shard in the `shards_endorsed` array.
     pub shards_endorsed: ::std::vec::Vec<ShardId>,
     pub stake: NearToken,
 }
+///`DataDeletionChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct DataDeletionChange {
+    pub account_id: AccountId,
+    pub key_base64: StoreKey,
+}
 ///The fees settings for a data receipt creation
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -1079,6 +1139,14 @@ pub struct DataReceiverView {
     pub data_id: CryptoHash,
     pub receiver_id: AccountId,
 }
+///`DataUpdateChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct DataUpdateChange {
+    pub account_id: AccountId,
+    pub key_base64: StoreKey,
+    pub value_base64: StoreValue,
+}
 ///This action allows to execute the inner actions behalf of the defined sender.
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -1905,6 +1973,15 @@ pub struct GasKeyInfo {
     pub balance: NearToken,
     pub num_nonces: u16,
 }
+///`GasKeyNonceUpdateChange`
+///
+#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
+pub struct GasKeyNonceUpdateChange {
+    pub account_id: AccountId,
+    pub index: u16,
+    pub nonce: u64,
+    pub public_key: PublicKey,
+}
 ///Configuration for garbage collection.
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -2318,6 +2395,7 @@ pub enum InvalidTxError {
     /**The receiver shard of the transaction is too congested to accept new
 transactions at the moment.*/
     ShardCongested {
+        ///A value between 0 (no congestion) and 1 (max congestion).
         congestion_level: f64,
         ///The congested shard.
         shard_id: u32,
@@ -4902,70 +4980,35 @@ pub enum StateChangeKindView {
 pub enum StateChangeWithCauseView {
     ///AccountUpdate
     #[serde(rename = "account_update")]
-    AccountUpdate {
-        cause: StateChangeCauseView,
-        change: StateChangeWithCauseViewChange,
-    },
+    AccountUpdate { cause: StateChangeCauseView, change: AccountUpdateChange },
     ///AccountDeletion
     #[serde(rename = "account_deletion")]
-    AccountDeletion {
-        cause: StateChangeCauseView,
-        change: StateChangeWithCauseViewChange,
-    },
+    AccountDeletion { cause: StateChangeCauseView, change: AccountDeletionChange },
     ///AccessKeyUpdate
     #[serde(rename = "access_key_update")]
-    AccessKeyUpdate {
-        cause: StateChangeCauseView,
-        change: StateChangeWithCauseViewChange,
-    },
+    AccessKeyUpdate { cause: StateChangeCauseView, change: AccessKeyUpdateChange },
     ///AccessKeyDeletion
     #[serde(rename = "access_key_deletion")]
-    AccessKeyDeletion {
-        cause: StateChangeCauseView,
-        change: StateChangeWithCauseViewChange,
-    },
+    AccessKeyDeletion { cause: StateChangeCauseView, change: AccessKeyDeletionChange },
     ///GasKeyNonceUpdate
     #[serde(rename = "gas_key_nonce_update")]
-    GasKeyNonceUpdate {
-        cause: StateChangeCauseView,
-        change: StateChangeWithCauseViewChange,
-    },
+    GasKeyNonceUpdate { cause: StateChangeCauseView, change: GasKeyNonceUpdateChange },
     ///DataUpdate
     #[serde(rename = "data_update")]
-    DataUpdate { cause: StateChangeCauseView, change: StateChangeWithCauseViewChange },
+    DataUpdate { cause: StateChangeCauseView, change: DataUpdateChange },
     ///DataDeletion
     #[serde(rename = "data_deletion")]
-    DataDeletion { cause: StateChangeCauseView, change: StateChangeWithCauseViewChange },
+    DataDeletion { cause: StateChangeCauseView, change: DataDeletionChange },
     ///ContractCodeUpdate
     #[serde(rename = "contract_code_update")]
-    ContractCodeUpdate {
-        cause: StateChangeCauseView,
-        change: StateChangeWithCauseViewChange,
-    },
+    ContractCodeUpdate { cause: StateChangeCauseView, change: ContractCodeUpdateChange },
     ///ContractCodeDeletion
     #[serde(rename = "contract_code_deletion")]
     ContractCodeDeletion {
         cause: StateChangeCauseView,
-        change: StateChangeWithCauseViewChange,
+        change: ContractCodeDeletionChange,
     },
 }
-///A view of the account
-///
-#[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
-pub struct StateChangeWithCauseViewChange {
-    pub account_id: AccountId,
-    pub amount: NearToken,
-    pub code_hash: CryptoHash,
-    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
-    pub global_contract_account_id: ::std::option::Option<AccountId>,
-    #[serde(default, skip_serializing_if = "::std::option::Option::is_none")]
-    pub global_contract_hash: ::std::option::Option<CryptoHash>,
-    pub locked: NearToken,
-    ///TODO(2271): deprecated.
-    #[serde(default)]
-    pub storage_paid_at: u64,
-    pub storage_usage: u64,
-}
 ///`StateChangesRequestView`
 ///
 #[derive(::serde::Deserialize, ::serde::Serialize, Clone, Debug)]
@@ -0,0 +1,536 @@
+//! Local keystore for NEAR credential files.
+//!
+//! Reads and writes the JSON key files used by `near-cli` and `near-cli-rs`:
+//!
+//! - `~/.near-credentials/<network>/<account_id>.json` (legacy `near-cli` layout)
+//! - `~/.near-credentials/<network>/<account_id>/<public_key>.json` (`near-cli-rs` layout)
+//!
+//! Each file holds an `account_id`, a `public_key` and a `private_key` (older files
+//! use `secret_key`). Keys are loaded into an [`InMemorySigner`] that can sign
+//! transactions built with [`crate::transaction::Transaction`].
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use ed25519_dalek::{Signer as _, SigningKey};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AccountId, PublicKey, Signature};
+
+/// Errors returned while reading, writing or parsing key files.
+#[derive(Debug, thiserror::Error)]
+pub enum KeystoreError {
+    #[error("I/O error on {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid key file {}: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("no key found for {account_id} on {network}")]
+    NotFound {
+        network: String,
+        account_id: AccountId,
+    },
+    #[error("key file {} has no account_id", path.display())]
+    MissingAccountId { path: PathBuf },
+    #[error("public key {public_key} does not match the private key")]
+    KeyMismatch { public_key: PublicKey },
+    #[error("invalid key: {0}")]
+    InvalidKey(String),
+    #[error("client has no network name; use `with_network` to set one")]
+    UnknownNetwork,
+    #[error("HOME is not set; cannot locate ~/.near-credentials")]
+    NoHomeDir,
+}
+
+// ── Keys ─────────────────────────────────────────────────────────────────
+
+/// An ed25519 secret key in NEAR's `ed25519:<base58>` string format.
+///
+/// Accepts both the 64-byte expanded form written by `near-cli` (secret seed
+/// followed by the public key) and a bare 32-byte seed.
+#[derive(Clone)]
+pub struct SecretKey(SigningKey);
+
+impl SecretKey {
+    /// Create a secret key from a 32-byte ed25519 seed.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self(SigningKey::from_bytes(&seed))
+    }
+
+    /// Returns the public key matching this secret key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(format!(
+            "ed25519:{}",
+            bs58::encode(self.0.verifying_key().as_bytes()).into_string()
+        ))
+    }
+
+    /// Signs `message` and returns the signature in `ed25519:<base58>` format.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        let signature = self.0.sign(message);
+        Signature(format!(
+            "ed25519:{}",
+            bs58::encode(signature.to_bytes()).into_string()
+        ))
+    }
+}
+
+impl FromStr for SecretKey {
+    type Err = KeystoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = s.strip_prefix("ed25519:").ok_or_else(|| {
+            KeystoreError::InvalidKey("only ed25519 secret keys are supported".to_string())
+        })?;
+        let bytes = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| KeystoreError::InvalidKey(e.to_string()))?;
+        let seed: [u8; 32] = match bytes.len() {
+            32 | 64 => bytes[..32].try_into().expect("length checked above"),
+            len => {
+                return Err(KeystoreError::InvalidKey(format!(
+                    "expected 32 or 64 bytes, got {len}"
+                )));
+            }
+        };
+        let key = Self::from_seed(seed);
+        if bytes.len() == 64 && bytes[32..] != key.0.verifying_key().as_bytes()[..] {
+            return Err(KeystoreError::KeyMismatch {
+                public_key: key.public_key(),
+            });
+        }
+        Ok(key)
+    }
+}
+
+impl fmt::Display for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = self.0.to_bytes().to_vec();
+        bytes.extend_from_slice(self.0.verifying_key().as_bytes());
+        write!(f, "ed25519:{}", bs58::encode(bytes).into_string())
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SecretKey")
+            .field(&self.public_key().0)
+            .finish()
+    }
+}
+
+impl Serialize for SecretKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// ── Key files ────────────────────────────────────────────────────────────
+
+/// Contents of a NEAR credentials JSON file.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyFile {
+    pub account_id: AccountId,
+    pub public_key: PublicKey,
+    pub private_key: SecretKey,
+}
+
+/// On-disk shape: `near-cli-rs` may omit `account_id` (it is implied by the
+/// directory) and older `near-cli` versions wrote `secret_key`.
+#[derive(Deserialize)]
+struct RawKeyFile {
+    #[serde(default)]
+    account_id: Option<AccountId>,
+    public_key: PublicKey,
+    #[serde(alias = "secret_key")]
+    private_key: SecretKey,
+}
+
+impl KeyFile {
+    /// Create a key file for `account_id` from a secret key.
+    pub fn new(account_id: AccountId, private_key: SecretKey) -> Self {
+        Self {
+            account_id,
+            public_key: private_key.public_key(),
+            private_key,
+        }
+    }
+
+    /// Reads a key file from `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, KeystoreError> {
+        Self::read(path.as_ref(), None)
+    }
+
+    fn read(path: &Path, account_id: Option<&AccountId>) -> Result<Self, KeystoreError> {
+        let content = fs::read_to_string(path).map_err(|source| KeystoreError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let raw: RawKeyFile =
+            serde_json::from_str(&content).map_err(|source| KeystoreError::Json {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        let account_id = raw
+            .account_id
+            .or_else(|| account_id.cloned())
+            .ok_or_else(|| KeystoreError::MissingAccountId {
+                path: path.to_path_buf(),
+            })?;
+        if raw.private_key.public_key() != raw.public_key {
+            return Err(KeystoreError::KeyMismatch {
+                public_key: raw.public_key,
+            });
+        }
+        Ok(Self {
+            account_id,
+            public_key: raw.public_key,
+            private_key: raw.private_key,
+        })
+    }
+
+    /// Returns a signer for this key.
+    pub fn signer(&self) -> InMemorySigner {
+        InMemorySigner::new(self.account_id.clone(), self.private_key.clone())
+    }
+}
+
+// ── Keystore ─────────────────────────────────────────────────────────────
+
+/// A directory of credential files, organised by network.
+///
+/// # Example
+///
+/// ```no_run
+/// use near_openrpc_client::keystore::Keystore;
+///
+/// # fn main() -> Result<(), near_openrpc_client::keystore::KeystoreError> {
+/// let keystore = Keystore::from_home()?;
+/// let signer = keystore.signer("testnet", &"alice.testnet".parse().unwrap())?;
+/// println!("Signing as {} with {}", signer.account_id(), signer.public_key());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keystore {
+    root: PathBuf,
+}
+
+impl Keystore {
+    /// Create a keystore rooted at `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Create a keystore rooted at `~/.near-credentials`.
+    pub fn from_home() -> Result<Self, KeystoreError> {
+        let home = std::env::var_os("HOME").ok_or(KeystoreError::NoHomeDir)?;
+        Ok(Self::new(Path::new(&home).join(".near-credentials")))
+    }
+
+    /// Returns the root directory of this keystore.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Loads the key for `account_id` on `network`.
+    ///
+    /// Looks for `<network>/<account_id>.json` first, then for the first
+    /// `*.json` file (sorted by name) in `<network>/<account_id>/`.
+    pub fn load(&self, network: &str, account_id: &AccountId) -> Result<KeyFile, KeystoreError> {
+        let legacy = self.root.join(network).join(format!("{account_id}.json"));
+        if legacy.is_file() {
+            return KeyFile::read(&legacy, Some(account_id));
+        }
+
+        self.load_all(network, account_id)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| KeystoreError::NotFound {
+                network: network.to_string(),
+                account_id: account_id.clone(),
+            })
+    }
+
+    /// Loads every key stored in the `near-cli-rs` directory for `account_id`.
+    pub fn load_all(
+        &self,
+        network: &str,
+        account_id: &AccountId,
+    ) -> Result<Vec<KeyFile>, KeystoreError> {
+        let dir = self.root.join(network).join(account_id.as_str());
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        json_files(&dir)?
+            .iter()
+            .map(|path| KeyFile::read(path, Some(account_id)))
+            .collect()
+    }
+
+    /// Writes `key` to `<network>/<account_id>.json` and returns the path.
+    ///
+    /// On Unix the file is created with `0600` permissions.
+    pub fn save(&self, network: &str, key: &KeyFile) -> Result<PathBuf, KeystoreError> {
+        let dir = self.root.join(network);
+        fs::create_dir_all(&dir).map_err(|source| KeystoreError::Io {
+            path: dir.clone(),
+            source,
+        })?;
+        let path = dir.join(format!("{}.json", key.account_id));
+        let content = serde_json::to_string(key).map_err(|source| KeystoreError::Json {
+            path: path.clone(),
+            source,
+        })?;
+        write_private(&path, content.as_bytes()).map_err(|source| KeystoreError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(path)
+    }
+
+    /// Lists the accounts that have keys stored for `network`.
+    pub fn accounts(&self, network: &str) -> Result<Vec<AccountId>, KeystoreError> {
+        let dir = self.root.join(network);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let entries = fs::read_dir(&dir).map_err(|source| KeystoreError::Io {
+            path: dir.clone(),
+            source,
+        })?;
+
+        let mut accounts = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|source| KeystoreError::Io {
+                    path: dir.clone(),
+                    source,
+                })?
+                .path();
+            let name = if path.is_dir() {
+                path.file_name()
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                path.file_stem()
+            } else {
+                None
+            };
+            if let Some(account_id) = name
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.parse().ok())
+            {
+                accounts.push(account_id);
+            }
+        }
+        accounts.sort();
+        accounts.dedup();
+        Ok(accounts)
+    }
+
+    /// Loads the key for `account_id` on `network` as a signer.
+    pub fn signer(
+        &self,
+        network: &str,
+        account_id: &AccountId,
+    ) -> Result<InMemorySigner, KeystoreError> {
+        Ok(self.load(network, account_id)?.signer())
+    }
+}
+
+fn json_files(dir: &Path) -> Result<Vec<PathBuf>, KeystoreError> {
+    let entries = fs::read_dir(dir).map_err(|source| KeystoreError::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|source| KeystoreError::Io {
+                path: dir.to_path_buf(),
+                source,
+            })?
+            .path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(content)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    fs::write(path, content)
+}
+
+// ── Signer ───────────────────────────────────────────────────────────────
+
+/// Signs on behalf of a single account with a key held in memory.
+#[derive(Debug, Clone)]
+pub struct InMemorySigner {
+    account_id: AccountId,
+    secret_key: SecretKey,
+}
+
+impl InMemorySigner {
+    /// Create a signer for `account_id` using `secret_key`.
+    pub fn new(account_id: AccountId, secret_key: SecretKey) -> Self {
+        Self {
+            account_id,
+            secret_key,
+        }
+    }
+
+    /// Returns the account this signer acts as.
+    pub fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    /// Returns the public key of the signing key.
+    pub fn public_key(&self) -> PublicKey {
+        self.secret_key.public_key()
+    }
+
+    /// Signs `message` with the signing key.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.secret_key.sign(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "alice.testnet";
+    // Seed of all 7s; the expanded form is seed || public key.
+    const SECRET: &str = "ed25519:99eUso3aSbE9tqGSTXzo3TLfKb9RkMTURrHKQ1K7Zh3StnzFNUx8FKCPPPPpR479qsw5zv2WNBKmgiz7WqgAJfM";
+
+    fn secret_key() -> SecretKey {
+        SecretKey::from_seed([7; 32])
+    }
+
+    #[test]
+    fn secret_key_string_round_trip() {
+        let key = secret_key();
+        let parsed: SecretKey = key.to_string().parse().unwrap();
+        assert_eq!(parsed.public_key(), key.public_key());
+        assert!(key.public_key().starts_with("ed25519:"));
+    }
+
+    #[test]
+    fn secret_key_accepts_bare_seed() {
+        let seed = format!("ed25519:{}", bs58::encode([7u8; 32]).into_string());
+        let parsed: SecretKey = seed.parse().unwrap();
+        assert_eq!(parsed.public_key(), secret_key().public_key());
+    }
+
+    #[test]
+    fn secret_key_rejects_other_curves() {
+        assert!(matches!(
+            "secp256k1:abc".parse::<SecretKey>(),
+            Err(KeystoreError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn save_and_load_legacy_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = Keystore::new(dir.path());
+        let account_id: AccountId = ACCOUNT.parse().unwrap();
+
+        let path = keystore
+            .save("testnet", &KeyFile::new(account_id.clone(), secret_key()))
+            .unwrap();
+        assert_eq!(path, dir.path().join("testnet").join("alice.testnet.json"));
+
+        let loaded = keystore.load("testnet", &account_id).unwrap();
+        assert_eq!(loaded.account_id, account_id);
+        assert_eq!(loaded.public_key, secret_key().public_key());
+        assert_eq!(keystore.accounts("testnet").unwrap(), vec![account_id]);
+        assert!(keystore.accounts("mainnet").unwrap().is_empty());
+    }
+
+    #[test]
+    fn load_near_cli_rs_layout_without_account_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let account_dir = dir.path().join("testnet").join(ACCOUNT);
+        fs::create_dir_all(&account_dir).unwrap();
+        let public_key = secret_key().public_key();
+        fs::write(
+            account_dir.join(format!("{}.json", public_key.as_str())),
+            serde_json::json!({
+                "public_key": public_key,
+                "secret_key": SECRET,
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let keystore = Keystore::new(dir.path());
+        let account_id: AccountId = ACCOUNT.parse().unwrap();
+        let signer = keystore.signer("testnet", &account_id).unwrap();
+        assert_eq!(signer.account_id(), &account_id);
+        assert_eq!(signer.public_key(), public_key);
+    }
+
+    #[test]
+    fn load_rejects_mismatched_public_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key.json");
+        fs::write(
+            &path,
+            serde_json::json!({
+                "account_id": ACCOUNT,
+                "public_key": SecretKey::from_seed([8; 32]).public_key(),
+                "private_key": SECRET,
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            KeyFile::from_path(&path),
+            Err(KeystoreError::KeyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn load_missing_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = Keystore::new(dir.path());
+        assert!(matches!(
+            keystore.load("testnet", &ACCOUNT.parse().unwrap()),
+            Err(KeystoreError::NotFound { .. })
+        ));
+    }
+}
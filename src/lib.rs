@@ -9,6 +9,58 @@
 //! - **`types` module** — All RPC request/response types, generated at build time via
 //!   [`typify`](https://docs.rs/typify). Available with no additional features.
 //! - **`client` module** (enabled by default) — An async RPC client built on `reqwest`.
+//! - **`blocking` module** (`blocking` feature) — A synchronous client with the same
+//!   RPC methods and error types, for scripts and CLI tools.
+//! - **`cli` module** (`cli` feature) — The `near-rpc` binary, with a subcommand per
+//!   RPC method and JSON or table output.
+//! - **`account_stream` module** (`stream` feature) — Transactions and receipts
+//!   touching a set of accounts, joined with their execution outcomes.
+//! - **`account_watcher` module** (`stream` feature) — Balance, key, code and storage
+//!   change events for watched accounts, as a stream or on a channel.
+//! - **`access_key` module** — Pre-flight checks of access key permissions against a
+//!   planned transaction.
+//! - **`block_stream` module** (`stream` feature) — A `Stream` of blocks following the
+//!   chain, with reorg detection and optional chunk fetching.
+//! - **`cache` module** (`client` feature) — Opt-in caching of immutable responses
+//!   in an LRU or custom backend, via `NearRpcClient::with_cache`.
+//! - **`chunks` module** (`client` feature) — `block_with_chunks`, a block with the
+//!   chunk of every shard and missing chunks marked.
+//! - **`contract_state` module** (`stream` feature) — Dumps a contract's full
+//!   storage, splitting prefixes the node refuses as too large.
+//! - **`epoch` module** (`client` feature) — An epoch cursor that finds the epoch of
+//!   a height and steps between epochs, caching what it loads.
+//! - **`fees` module** — Gas and fee estimation for planned transactions from the
+//!   protocol's runtime config.
+//! - **`middleware` module** (`client` feature) — A `Middleware` trait wrapping each
+//!   JSON-RPC exchange, for headers, logging, retries or canned responses.
+//! - **`shard_layout` module** — `ShardLayout::account_id_to_shard_id`, shard
+//!   index/id conversions and parent/child shards across reshardings.
+//! - **`state_changes` module** (`client` feature) — `changes_for`, returning state
+//!   changes decoded and grouped per account.
+//! - **`validator_analytics` module** — Per-validator production ratios, stake
+//!   shares and seat status, and diffs between current and next validators.
+//! - **`fixtures` module** (`testing` feature) — Records RPC exchanges to fixture
+//!   files and replays them offline, keyed by method and canonicalized params.
+//! - **`testing` module** (`testing` feature) — `MockRpc`, an in-process mock node
+//!   with canned or computed replies per method, injected errors and latency.
+//! - **`tracing` feature** — A span per RPC call with OpenTelemetry JSON-RPC
+//!   attributes: method, request id, endpoint, HTTP status, duration and error class.
+//! - **`metrics` feature** — Per-method request, failure, retry and cache-hit
+//!   counters, latency histograms and byte counts through the `metrics` facade.
+//! - **`keystore` and `transaction` modules** (`keystore` feature) — Load keys from
+//!   `~/.near-credentials` and sign transactions, e.g. via
+//!   `NearRpcClient::send_transaction_as`.
+//! - **`light_client` module** (`light-client` feature) — Verifies headers from
+//!   `next_light_client_block` against block producer signatures, epoch by epoch.
+//! - **`proof` module** (`light-client` feature) — Verifies the merkle proofs of
+//!   `light_client_proof` and `light_client_block_proof` against a trusted head.
+//! - **`state_proof` module** (`light-client` feature) — Verifies `view_state` trie
+//!   proofs against a state root, including that no stored keys were omitted.
+//!
+//! The client also compiles for `wasm32-unknown-unknown`, where reqwest sends
+//! requests with the browser's `fetch` and [`middleware::HandleFuture`] is not
+//! `Send`. The `blocking`, `cli`, `stream` and `testing` features need
+//! tokio and are native-only.
 //!
 //! # Quick start
 //!
@@ -30,12 +82,76 @@
 //! }
 //! ```
 
+pub mod access_key;
 pub mod errors;
+pub mod fees;
+pub mod shard_layout;
 pub mod types;
+pub mod validator_analytics;
 
 #[cfg(feature = "client")]
 pub mod client;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+#[cfg(feature = "cli")]
+pub mod cli;
+
+#[cfg(feature = "stream")]
+pub mod account_stream;
+
+#[cfg(feature = "stream")]
+pub mod account_watcher;
+
+#[cfg(feature = "stream")]
+pub mod block_stream;
+
+#[cfg(feature = "stream")]
+pub mod contract_state;
+
+#[cfg(feature = "client")]
+pub mod cache;
+
+#[cfg(feature = "client")]
+pub mod chunks;
+
+#[cfg(feature = "client")]
+pub mod epoch;
+
+#[cfg(feature = "client")]
+pub mod middleware;
+
+#[cfg(feature = "client")]
+pub mod state_changes;
+
+#[cfg(feature = "client")]
+mod telemetry;
+
+#[cfg(feature = "keystore")]
+pub mod keystore;
+
+#[cfg(feature = "light-client")]
+pub mod light_client;
+
+#[cfg(feature = "light-client")]
+pub mod proof;
+
+#[cfg(feature = "light-client")]
+pub mod state_proof;
+
+#[cfg(feature = "testing")]
+pub mod fixtures;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "keystore")]
+pub mod transaction;
+
+#[cfg(all(test, any(feature = "client", feature = "light-client")))]
+mod test_utils;
+
 #[cfg(feature = "client")]
 pub use client::NearRpcClient;
 
@@ -0,0 +1,570 @@
+//! Light client header verification.
+//!
+//! A [`LightClient`] starts from a trusted block header and follows the chain
+//! one epoch at a time through `next_light_client_block`, checking each block
+//! the way the [nomicon light client spec](https://nomicon.io/ChainSpec/LightClient)
+//! describes:
+//!
+//! - the block hash is recomputed from `inner_lite`, `inner_rest_hash` and
+//!   `prev_block_hash`;
+//! - the block belongs to the head's epoch or the next one, and carries
+//!   `next_bps` when it is the last block the client will see of an epoch;
+//! - `approvals_after_next` are valid ed25519 signatures of the epoch's block
+//!   producers over the endorsement of the next block, from more than 2/3 of
+//!   their stake;
+//! - `next_bps` hashes to `inner_lite.next_bp_hash`.
+//!
+//! The client state serializes with serde so it can be persisted between runs.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::{NearRpcClient, light_client::LightClient};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     // A block obtained from a trusted source, with the producers of the next epoch.
+//!     let trusted: near_openrpc_client::types::RpcLightClientNextBlockResponse =
+//!         serde_json::from_str(&std::fs::read_to_string("trusted_block.json").unwrap()).unwrap();
+//!
+//!     let mut light_client = LightClient::from_trusted_block(&trusted)?;
+//!     let verified = client.sync_light_client(&mut light_client).await?;
+//!     println!("verified {verified} blocks, head at {}", light_client.head().inner_lite.height);
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use ed25519_dalek::{Signature as Ed25519Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::types::*;
+
+/// Reasons a light client block fails verification.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LightClientError {
+    #[error("invalid crypto hash: {0}")]
+    InvalidHash(String),
+    #[error("invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("invalid signature encoding: {0}")]
+    InvalidSignature(String),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("invalid timestamp: {0}")]
+    InvalidTimestamp(String),
+    #[error("unsupported validator stake version {0}")]
+    UnsupportedStakeVersion(String),
+    #[error("light client block is missing {0}")]
+    IncompleteBlock(&'static str),
+    #[error("block at height {height} is not newer than the head at height {head_height}")]
+    NotNewer { head_height: u64, height: u64 },
+    #[error("block epoch {epoch_id} is neither the head's epoch nor the next one")]
+    UnexpectedEpoch { epoch_id: CryptoHash },
+    #[error("block producers of epoch {epoch_id} are unknown")]
+    UnknownEpoch { epoch_id: CryptoHash },
+    #[error("block at height {height} starts a new epoch but has no next_bps")]
+    MissingNextBlockProducers { height: u64 },
+    #[error("next_bps of block at height {height} do not hash to next_bp_hash")]
+    NextBlockProducersMismatch { height: u64 },
+    #[error("approval signature of {account_id} is invalid")]
+    BadApproval { account_id: AccountId },
+    #[error("approved stake {approved} is not more than 2/3 of {total}")]
+    NotEnoughStake { approved: u128, total: u128 },
+}
+
+/// A light client following the chain from a trusted header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightClient {
+    head: LightClientBlockLiteView,
+    /// Block producers by epoch id, for the head's epoch and the next one.
+    epoch_block_producers: BTreeMap<CryptoHash, Vec<ValidatorStakeView>>,
+}
+
+impl LightClient {
+    /// Create a light client trusting `head`, whose next epoch is produced by
+    /// `next_block_producers`.
+    pub fn new(
+        head: LightClientBlockLiteView,
+        next_block_producers: Vec<ValidatorStakeView>,
+    ) -> Self {
+        let next_epoch_id = head.inner_lite.next_epoch_id.clone();
+        Self {
+            head,
+            epoch_block_producers: BTreeMap::from([(next_epoch_id, next_block_producers)]),
+        }
+    }
+
+    /// Create a light client trusting a `next_light_client_block` response
+    /// without verifying it. The response must include `next_bps`.
+    pub fn from_trusted_block(
+        block: &RpcLightClientNextBlockResponse,
+    ) -> Result<Self, LightClientError> {
+        let head = lite_view(block)?;
+        let next_bps = block
+            .next_bps
+            .clone()
+            .ok_or(LightClientError::IncompleteBlock("next_bps"))?;
+        Ok(Self::new(head, next_bps))
+    }
+
+    /// Registers the block producers of `epoch_id`, e.g. to verify blocks in
+    /// the trusted head's own epoch.
+    pub fn with_epoch_block_producers(
+        mut self,
+        epoch_id: CryptoHash,
+        block_producers: Vec<ValidatorStakeView>,
+    ) -> Self {
+        self.epoch_block_producers.insert(epoch_id, block_producers);
+        self
+    }
+
+    /// Returns the latest verified header.
+    pub fn head(&self) -> &LightClientBlockLiteView {
+        &self.head
+    }
+
+    /// Returns the hash of the latest verified header.
+    pub fn head_hash(&self) -> Result<CryptoHash, LightClientError> {
+        block_hash(&self.head)
+    }
+
+    /// Returns the known block producers of `epoch_id`.
+    pub fn block_producers(&self, epoch_id: &CryptoHash) -> Option<&[ValidatorStakeView]> {
+        self.epoch_block_producers.get(epoch_id).map(Vec::as_slice)
+    }
+
+    /// Verifies `block` against the current head without changing the state,
+    /// returning its header.
+    pub fn verify(
+        &self,
+        block: &RpcLightClientNextBlockResponse,
+    ) -> Result<LightClientBlockLiteView, LightClientError> {
+        let new_head = lite_view(block)?;
+        let inner = &new_head.inner_lite;
+        let head = &self.head.inner_lite;
+
+        if inner.height <= head.height {
+            return Err(LightClientError::NotNewer {
+                head_height: head.height,
+                height: inner.height,
+            });
+        }
+        if inner.epoch_id != head.epoch_id && inner.epoch_id != head.next_epoch_id {
+            return Err(LightClientError::UnexpectedEpoch {
+                epoch_id: inner.epoch_id.clone(),
+            });
+        }
+        if inner.epoch_id == head.next_epoch_id && block.next_bps.is_none() {
+            return Err(LightClientError::MissingNextBlockProducers {
+                height: inner.height,
+            });
+        }
+
+        let next_block_inner_hash = block
+            .next_block_inner_hash
+            .as_ref()
+            .ok_or(LightClientError::IncompleteBlock("next_block_inner_hash"))?;
+        let current_hash = hash_bytes(&new_head)?;
+        let next_hash = sha256(&[&decode_hash(next_block_inner_hash)?, &current_hash]);
+
+        let mut message = vec![0u8]; // ApprovalInner::Endorsement
+        message.extend_from_slice(&next_hash);
+        message.extend_from_slice(&(inner.height + 2).to_le_bytes());
+
+        let block_producers = self.block_producers(&inner.epoch_id).ok_or_else(|| {
+            LightClientError::UnknownEpoch {
+                epoch_id: inner.epoch_id.clone(),
+            }
+        })?;
+
+        let mut total_stake = 0u128;
+        let mut approved_stake = 0u128;
+        for (approval, producer) in block.approvals_after_next.iter().zip(block_producers) {
+            let stake = parse_amount(&producer.stake)?;
+            total_stake += stake;
+            let Some(signature) = approval else {
+                continue;
+            };
+            approved_stake += stake;
+            if !verify_signature(&producer.public_key, signature, &message)? {
+                return Err(LightClientError::BadApproval {
+                    account_id: producer.account_id.clone(),
+                });
+            }
+        }
+        if approved_stake <= total_stake * 2 / 3 {
+            return Err(LightClientError::NotEnoughStake {
+                approved: approved_stake,
+                total: total_stake,
+            });
+        }
+
+        if let Some(next_bps) = &block.next_bps
+            && block_producers_hash(next_bps)? != decode_hash(&inner.next_bp_hash)?
+        {
+            return Err(LightClientError::NextBlockProducersMismatch {
+                height: inner.height,
+            });
+        }
+
+        Ok(new_head)
+    }
+
+    /// Verifies `block` and makes it the new head.
+    pub fn advance(
+        &mut self,
+        block: &RpcLightClientNextBlockResponse,
+    ) -> Result<(), LightClientError> {
+        let new_head = self.verify(block)?;
+        if let Some(next_bps) = &block.next_bps {
+            self.epoch_block_producers
+                .insert(new_head.inner_lite.next_epoch_id.clone(), next_bps.clone());
+        }
+        self.head = new_head;
+
+        let inner = &self.head.inner_lite;
+        self.epoch_block_producers
+            .retain(|epoch_id, _| *epoch_id == inner.epoch_id || *epoch_id == inner.next_epoch_id);
+        Ok(())
+    }
+}
+
+/// Computes the hash of a block from its light client view:
+/// `sha256(sha256(sha256(borsh(inner_lite)) ++ inner_rest_hash) ++ prev_block_hash)`.
+pub fn block_hash(block: &LightClientBlockLiteView) -> Result<CryptoHash, LightClientError> {
+    Ok(CryptoHash(bs58::encode(hash_bytes(block)?).into_string()))
+}
+
+pub(crate) fn hash_bytes(block: &LightClientBlockLiteView) -> Result<[u8; 32], LightClientError> {
+    let inner_lite_hash = sha256(&[&inner_lite_borsh(&block.inner_lite)?]);
+    let inner_hash = sha256(&[&inner_lite_hash, &decode_hash(&block.inner_rest_hash)?]);
+    Ok(sha256(&[
+        &inner_hash,
+        &decode_hash(&block.prev_block_hash)?,
+    ]))
+}
+
+/// Returns the header part of a `next_light_client_block` response.
+fn lite_view(
+    block: &RpcLightClientNextBlockResponse,
+) -> Result<LightClientBlockLiteView, LightClientError> {
+    Ok(LightClientBlockLiteView {
+        inner_lite: block
+            .inner_lite
+            .clone()
+            .ok_or(LightClientError::IncompleteBlock("inner_lite"))?,
+        inner_rest_hash: block
+            .inner_rest_hash
+            .clone()
+            .ok_or(LightClientError::IncompleteBlock("inner_rest_hash"))?,
+        prev_block_hash: block
+            .prev_block_hash
+            .clone()
+            .ok_or(LightClientError::IncompleteBlock("prev_block_hash"))?,
+    })
+}
+
+// ── Hashing and Borsh encoding ───────────────────────────────────────────
+
+pub(crate) fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+pub(crate) fn decode_hash(hash: &CryptoHash) -> Result<[u8; 32], LightClientError> {
+    bs58::decode(hash.as_str())
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| LightClientError::InvalidHash(hash.0.clone()))
+}
+
+fn parse_amount(amount: &NearToken) -> Result<u128, LightClientError> {
+    amount
+        .parse()
+        .map_err(|_| LightClientError::InvalidAmount(amount.0.clone()))
+}
+
+/// Borsh layout of nearcore's `BlockHeaderInnerLite`.
+fn inner_lite_borsh(inner: &BlockHeaderInnerLiteView) -> Result<Vec<u8>, LightClientError> {
+    let timestamp: u64 = inner
+        .timestamp_nanosec
+        .parse()
+        .map_err(|_| LightClientError::InvalidTimestamp(inner.timestamp_nanosec.clone()))?;
+    let mut buf = Vec::with_capacity(208);
+    buf.extend_from_slice(&inner.height.to_le_bytes());
+    buf.extend_from_slice(&decode_hash(&inner.epoch_id)?);
+    buf.extend_from_slice(&decode_hash(&inner.next_epoch_id)?);
+    buf.extend_from_slice(&decode_hash(&inner.prev_state_root)?);
+    buf.extend_from_slice(&decode_hash(&inner.outcome_root)?);
+    buf.extend_from_slice(&timestamp.to_le_bytes());
+    buf.extend_from_slice(&decode_hash(&inner.next_bp_hash)?);
+    buf.extend_from_slice(&decode_hash(&inner.block_merkle_root)?);
+    Ok(buf)
+}
+
+/// Hash of the Borsh encoding of a `Vec<ValidatorStake>`, as committed to by
+/// `next_bp_hash`.
+fn block_producers_hash(
+    block_producers: &[ValidatorStakeView],
+) -> Result<[u8; 32], LightClientError> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(block_producers.len() as u32).to_le_bytes());
+    for producer in block_producers {
+        if producer.validator_stake_struct_version != "V1" {
+            return Err(LightClientError::UnsupportedStakeVersion(
+                producer.validator_stake_struct_version.clone(),
+            ));
+        }
+        buf.push(0); // ValidatorStake::V1
+        buf.extend_from_slice(&(producer.account_id.len() as u32).to_le_bytes());
+        buf.extend_from_slice(producer.account_id.as_bytes());
+        buf.extend_from_slice(&public_key_borsh(&producer.public_key)?);
+        buf.extend_from_slice(&parse_amount(&producer.stake)?.to_le_bytes());
+    }
+    Ok(sha256(&[&buf]))
+}
+
+/// Borsh layout of a `PublicKey`: a curve tag byte followed by the key bytes.
+fn public_key_borsh(key: &PublicKey) -> Result<Vec<u8>, LightClientError> {
+    let invalid = || LightClientError::InvalidPublicKey(key.0.clone());
+    let (tag, len, encoded) = if let Some(rest) = key.strip_prefix("ed25519:") {
+        (0u8, 32, rest)
+    } else if let Some(rest) = key.strip_prefix("secp256k1:") {
+        (1u8, 64, rest)
+    } else {
+        return Err(invalid());
+    };
+    let bytes = bs58::decode(encoded).into_vec().map_err(|_| invalid())?;
+    if bytes.len() != len {
+        return Err(invalid());
+    }
+    let mut out = vec![tag];
+    out.extend_from_slice(&bytes);
+    Ok(out)
+}
+
+/// Verifies an ed25519 `signature` of `message` by `public_key`.
+fn verify_signature(
+    public_key: &PublicKey,
+    signature: &Signature,
+    message: &[u8],
+) -> Result<bool, LightClientError> {
+    let key: [u8; 32] = public_key
+        .strip_prefix("ed25519:")
+        .and_then(|encoded| bs58::decode(encoded).into_vec().ok())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| LightClientError::InvalidPublicKey(public_key.0.clone()))?;
+    let key = VerifyingKey::from_bytes(&key)
+        .map_err(|_| LightClientError::InvalidPublicKey(public_key.0.clone()))?;
+    let signature: [u8; 64] = signature
+        .strip_prefix("ed25519:")
+        .and_then(|encoded| bs58::decode(encoded).into_vec().ok())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| LightClientError::InvalidSignature(signature.0.clone()))?;
+    Ok(key
+        .verify(message, &Ed25519Signature::from_bytes(&signature))
+        .is_ok())
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use super::LightClient;
+    use crate::client::{NearRpcClient, Result};
+    use crate::types::*;
+
+    impl NearRpcClient {
+        /// Advances `light_client` as far as the node allows, verifying every
+        /// block returned by `next_light_client_block`.
+        ///
+        /// Each step moves at most one epoch forward. Returns the number of
+        /// blocks verified; the light client is left at the last good block
+        /// if verification fails.
+        pub async fn sync_light_client(&self, light_client: &mut LightClient) -> Result<usize> {
+            let mut verified = 0;
+            loop {
+                let block = self
+                    .next_light_client_block(RpcLightClientNextBlockRequest {
+                        last_block_hash: light_client.head_hash()?,
+                    })
+                    .await?;
+                let is_newer = block
+                    .inner_lite
+                    .as_ref()
+                    .is_some_and(|inner| inner.height > light_client.head().inner_lite.height);
+                if !is_newer {
+                    return Ok(verified);
+                }
+                light_client.advance(&block)?;
+                verified += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::sample_block;
+    use ed25519_dalek::{Signer as _, SigningKey};
+
+    fn encode_hash(bytes: [u8; 32]) -> CryptoHash {
+        CryptoHash(bs58::encode(bytes).into_string())
+    }
+
+    fn keys() -> Vec<SigningKey> {
+        (1..=4u8)
+            .map(|i| SigningKey::from_bytes(&[i; 32]))
+            .collect()
+    }
+
+    fn block_producers(keys: &[SigningKey]) -> Vec<ValidatorStakeView> {
+        keys.iter()
+            .enumerate()
+            .map(|(i, key)| ValidatorStakeView {
+                account_id: format!("validator{i}.near").parse().unwrap(),
+                public_key: PublicKey(format!(
+                    "ed25519:{}",
+                    bs58::encode(key.verifying_key().as_bytes()).into_string()
+                )),
+                stake: NearToken("100".to_string()),
+                validator_stake_struct_version: "V1".to_string(),
+            })
+            .collect()
+    }
+
+    fn header(
+        height: u64,
+        epoch: u8,
+        next_epoch: u8,
+        next_bps: &[ValidatorStakeView],
+    ) -> LightClientBlockLiteView {
+        LightClientBlockLiteView {
+            inner_lite: BlockHeaderInnerLiteView {
+                block_merkle_root: encode_hash([9; 32]),
+                epoch_id: encode_hash([epoch; 32]),
+                height,
+                next_bp_hash: encode_hash(block_producers_hash(next_bps).unwrap()),
+                next_epoch_id: encode_hash([next_epoch; 32]),
+                outcome_root: encode_hash([8; 32]),
+                prev_state_root: encode_hash([7; 32]),
+                timestamp: 1_700_000_000_000_000_000,
+                timestamp_nanosec: "1700000000000000000".to_string(),
+            },
+            inner_rest_hash: encode_hash([6; 32]),
+            prev_block_hash: encode_hash([height as u8; 32]),
+        }
+    }
+
+    /// A next block in epoch 2 signed by the first `signers` of `keys`.
+    fn next_block(keys: &[SigningKey], signers: usize) -> RpcLightClientNextBlockResponse {
+        let next_bps = block_producers(keys);
+        let head = header(200, 2, 3, &next_bps);
+        let next_block_inner_hash = [5u8; 32];
+        let next_hash = sha256(&[&next_block_inner_hash, &hash_bytes(&head).unwrap()]);
+        let mut message = vec![0u8];
+        message.extend_from_slice(&next_hash);
+        message.extend_from_slice(&202u64.to_le_bytes());
+
+        RpcLightClientNextBlockResponse {
+            approvals_after_next: keys
+                .iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    (i < signers).then(|| {
+                        Signature(format!(
+                            "ed25519:{}",
+                            bs58::encode(key.sign(&message).to_bytes()).into_string()
+                        ))
+                    })
+                })
+                .collect(),
+            inner_lite: Some(head.inner_lite),
+            inner_rest_hash: Some(head.inner_rest_hash),
+            next_block_inner_hash: Some(encode_hash(next_block_inner_hash)),
+            next_bps: Some(next_bps),
+            prev_block_hash: Some(head.prev_block_hash),
+        }
+    }
+
+    fn light_client(keys: &[SigningKey]) -> LightClient {
+        let bps = block_producers(keys);
+        LightClient::new(header(100, 1, 2, &bps), bps)
+    }
+
+    #[test]
+    fn block_hash_matches_nearcore() {
+        assert_eq!(
+            block_hash(&sample_block()).unwrap(),
+            CryptoHash("95K8Je1iAVqieVU8ZuGgSdbvYs8T9rL6ER1XnRekMGbj".to_string())
+        );
+    }
+
+    #[test]
+    fn advances_into_next_epoch() {
+        let keys = keys();
+        let mut client = light_client(&keys);
+        client.advance(&next_block(&keys, 3)).unwrap();
+
+        assert_eq!(client.head().inner_lite.height, 200);
+        assert!(client.block_producers(&encode_hash([1; 32])).is_none());
+        assert!(client.block_producers(&encode_hash([2; 32])).is_some());
+        assert!(client.block_producers(&encode_hash([3; 32])).is_some());
+
+        let json = serde_json::to_string(&client).unwrap();
+        let restored: LightClient = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.head_hash(), client.head_hash());
+    }
+
+    #[test]
+    fn rejects_insufficient_stake() {
+        let keys = keys();
+        assert_eq!(
+            light_client(&keys)
+                .verify(&next_block(&keys, 2))
+                .unwrap_err(),
+            LightClientError::NotEnoughStake {
+                approved: 200,
+                total: 400,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_blocks() {
+        let keys = keys();
+        let client = light_client(&keys);
+
+        let mut block = next_block(&keys, 4);
+        block.inner_lite.as_mut().unwrap().outcome_root = encode_hash([0; 32]);
+        assert_eq!(
+            client.verify(&block).unwrap_err(),
+            LightClientError::BadApproval {
+                account_id: "validator0.near".parse().unwrap(),
+            }
+        );
+
+        let mut block = next_block(&keys, 4);
+        block.next_bps.as_mut().unwrap().pop();
+        assert_eq!(
+            client.verify(&block).unwrap_err(),
+            LightClientError::NextBlockProducersMismatch { height: 200 }
+        );
+
+        let mut block = next_block(&keys, 4);
+        block.next_bps = None;
+        assert_eq!(
+            client.verify(&block).unwrap_err(),
+            LightClientError::MissingNextBlockProducers { height: 200 }
+        );
+    }
+}
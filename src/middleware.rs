@@ -0,0 +1,221 @@
+//! Request middleware for [`NearRpcClient`].
+//!
+//! A [`Middleware`] wraps every JSON-RPC exchange the client makes. It sees
+//! the outgoing [`RpcCall`] (id, method, params and HTTP headers), may change
+//! it, and either passes it on with [`Next::run`] or answers it itself. The
+//! value it returns is the raw JSON-RPC response object, before the client
+//! decodes it, so errors in the `error` field pass through middleware as
+//! ordinary values.
+//!
+//! Middleware added first with [`NearRpcClient::with_middleware`] runs
+//! outermost. Responses served by the [response cache](crate::cache) do not
+//! reach middleware.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Instant;
+//!
+//! use near_openrpc_client::NearRpcClient;
+//! use near_openrpc_client::middleware::{HandleFuture, Middleware, Next, RpcCall};
+//!
+//! struct LogLatency;
+//!
+//! impl Middleware for LogLatency {
+//!     fn handle<'a>(&'a self, call: RpcCall, next: Next<'a>) -> HandleFuture<'a> {
+//!         Box::pin(async move {
+//!             let method = call.method.clone();
+//!             let started = Instant::now();
+//!             let response = next.run(call).await;
+//!             println!("{method} took {:?}", started.elapsed());
+//!             response
+//!         })
+//!     }
+//! }
+//!
+//! let client = NearRpcClient::mainnet().with_middleware(LogLatency);
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+use crate::client::{NearRpcClient, Result};
+
+/// One outgoing JSON-RPC request.
+#[derive(Debug, Clone)]
+pub struct RpcCall {
+    pub id: u64,
+    pub method: String,
+    pub params: Value,
+    /// Extra HTTP headers to send with the request.
+    pub headers: HeaderMap,
+}
+
+/// The future returned by [`Middleware::handle`].
+///
+/// It is `Send` except on wasm32, where the fetch-based transport is not.
+#[cfg(not(target_arch = "wasm32"))]
+pub type HandleFuture<'a> = futures::future::BoxFuture<'a, Result<Value>>;
+#[cfg(target_arch = "wasm32")]
+pub type HandleFuture<'a> = futures::future::LocalBoxFuture<'a, Result<Value>>;
+
+/// A layer around the client's JSON-RPC exchanges.
+pub trait Middleware: Send + Sync {
+    /// Handles `call`, usually by passing it (possibly modified) to `next`
+    /// and returning the raw JSON-RPC response.
+    fn handle<'a>(&'a self, call: RpcCall, next: Next<'a>) -> HandleFuture<'a>;
+}
+
+/// The rest of the middleware chain, ending in the HTTP transport.
+///
+/// `Next` is `Copy`, so a middleware can run it more than once, e.g. to
+/// retry a failed request.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    client: &'a NearRpcClient,
+    rest: &'a [Arc<dyn Middleware>],
+    attempts: &'a AtomicU32,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        client: &'a NearRpcClient,
+        chain: &'a [Arc<dyn Middleware>],
+        attempts: &'a AtomicU32,
+    ) -> Self {
+        Self {
+            client,
+            rest: chain,
+            attempts,
+        }
+    }
+
+    /// Passes `call` to the next middleware, or sends it if there is none.
+    pub async fn run(self, call: RpcCall) -> Result<Value> {
+        match self.rest.split_first() {
+            Some((middleware, rest)) => middleware.handle(call, Next { rest, ..self }).await,
+            None => {
+                self.attempts.fetch_add(1, Ordering::Relaxed);
+                self.client.transport(call).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::RpcHealthResponse;
+
+    struct Record {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for Record {
+        fn handle<'a>(&'a self, mut call: RpcCall, next: Next<'a>) -> HandleFuture<'a> {
+            Box::pin(async move {
+                self.log
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {}", self.name, call.method));
+                call.headers.insert("x-layer", self.name.parse().unwrap());
+                next.run(call).await
+            })
+        }
+    }
+
+    /// Answers every call without touching the network.
+    struct Respond {
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for Respond {
+        fn handle<'a>(&'a self, call: RpcCall, _next: Next<'a>) -> HandleFuture<'a> {
+            Box::pin(async move {
+                let layer = call.headers["x-layer"].to_str().unwrap().to_string();
+                self.log
+                    .lock()
+                    .unwrap()
+                    .push(format!("respond after {layer}"));
+                Ok(json!({ "jsonrpc": "2.0", "id": call.id, "result": null }))
+            })
+        }
+    }
+
+    /// Retries a call once if the response is an error.
+    struct RetryOnce;
+
+    impl Middleware for RetryOnce {
+        fn handle<'a>(&'a self, call: RpcCall, next: Next<'a>) -> HandleFuture<'a> {
+            Box::pin(async move {
+                let response = next.run(call.clone()).await?;
+                if response.get("error").is_some() {
+                    return next.run(call).await;
+                }
+                Ok(response)
+            })
+        }
+    }
+
+    /// Fails the first call and answers the rest.
+    struct FailFirst {
+        calls: Arc<AtomicU32>,
+    }
+
+    impl Middleware for FailFirst {
+        fn handle<'a>(&'a self, call: RpcCall, _next: Next<'a>) -> HandleFuture<'a> {
+            Box::pin(async move {
+                if self.calls.fetch_add(1, Ordering::Relaxed) == 0 {
+                    return Ok(json!({
+                        "jsonrpc": "2.0",
+                        "id": call.id,
+                        "error": { "code": -32000, "message": "Server error" }
+                    }));
+                }
+                Ok(json!({ "jsonrpc": "2.0", "id": call.id, "result": null }))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_middleware_in_order_and_short_circuits() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let client = NearRpcClient::new("http://127.0.0.1:9")
+            .with_middleware(Record {
+                name: "outer",
+                log: log.clone(),
+            })
+            .with_middleware(Record {
+                name: "inner",
+                log: log.clone(),
+            })
+            .with_middleware(Respond { log: log.clone() });
+
+        let _: RpcHealthResponse = client.health().await.unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["outer health", "inner health", "respond after inner"]
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_by_running_next_again() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let client = NearRpcClient::new("http://127.0.0.1:9")
+            .with_middleware(RetryOnce)
+            .with_middleware(FailFirst {
+                calls: calls.clone(),
+            });
+
+        let _: RpcHealthResponse = client.health().await.unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
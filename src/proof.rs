@@ -0,0 +1,334 @@
+//! Merkle proof verification for `light_client_proof` and
+//! `light_client_block_proof` responses.
+//!
+//! An execution proof ties an [`ExecutionOutcomeWithIdView`] to a block that a
+//! light client trusts in two steps:
+//!
+//! 1. the outcome hash is walked up `outcome_proof.proof` to its shard's
+//!    outcome root, which is walked up `outcome_root_proof` to the
+//!    `outcome_root` of `block_header_lite`;
+//! 2. the hash of `block_header_lite` is walked up `block_proof` to the
+//!    `block_merkle_root` of the light client head.
+//!
+//! Use [`LightClient::head`](crate::light_client::LightClient::head) (or any
+//! other trusted header) as the head.
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::light_client::{LightClientError, decode_hash, hash_bytes, sha256};
+use crate::types::*;
+
+/// Reasons a proof fails verification.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProofError {
+    #[error("invalid proof encoding: {0}")]
+    Encoding(#[from] LightClientError),
+    #[error("invalid base64 in SuccessValue: {0}")]
+    InvalidSuccessValue(String),
+    #[error("outcome proof leads to outcome root {computed}, block header has {expected}")]
+    OutcomeRootMismatch {
+        expected: CryptoHash,
+        computed: CryptoHash,
+    },
+    #[error("block proof leads to block merkle root {computed}, head has {expected}")]
+    BlockMerkleRootMismatch {
+        expected: CryptoHash,
+        computed: CryptoHash,
+    },
+}
+
+/// Computes the root of a merkle tree from a leaf hash and its path.
+pub fn compute_root_from_path(
+    path: &[MerklePathItem],
+    item_hash: &CryptoHash,
+) -> Result<CryptoHash, ProofError> {
+    Ok(encode(walk(path, decode_hash(item_hash)?)?))
+}
+
+/// Returns the hash nearcore commits to for an execution outcome: the hash of
+/// the Borsh-encoded list of the outcome id, the hash of the outcome without
+/// logs and metadata, and the hash of each log line.
+pub fn outcome_hash(outcome: &ExecutionOutcomeWithIdView) -> Result<CryptoHash, ProofError> {
+    Ok(encode(outcome_hash_bytes(outcome)?))
+}
+
+/// Verifies that the outcome in `proof` was included in a block that is an
+/// ancestor of the trusted `head`.
+pub fn verify_execution_proof(
+    proof: &RpcLightClientExecutionProofResponse,
+    head: &LightClientBlockLiteView,
+) -> Result<(), ProofError> {
+    let shard_outcome_root = walk(
+        &proof.outcome_proof.proof,
+        outcome_hash_bytes(&proof.outcome_proof)?,
+    )?;
+    let outcome_root = walk(&proof.outcome_root_proof, sha256(&[&shard_outcome_root]))?;
+    let expected = &proof.block_header_lite.inner_lite.outcome_root;
+    if outcome_root != decode_hash(expected)? {
+        return Err(ProofError::OutcomeRootMismatch {
+            expected: expected.clone(),
+            computed: encode(outcome_root),
+        });
+    }
+
+    verify_block_in_head(&proof.block_header_lite, &proof.block_proof, head)
+}
+
+/// Verifies that the block in `proof` is an ancestor of the trusted `head`.
+pub fn verify_block_proof(
+    proof: &RpcLightClientBlockProofResponse,
+    head: &LightClientBlockLiteView,
+) -> Result<(), ProofError> {
+    verify_block_in_head(&proof.block_header_lite, &proof.block_proof, head)
+}
+
+fn verify_block_in_head(
+    block: &LightClientBlockLiteView,
+    block_proof: &[MerklePathItem],
+    head: &LightClientBlockLiteView,
+) -> Result<(), ProofError> {
+    // The leaves of the block merkle tree are the block hashes themselves.
+    let block_merkle_root = walk(block_proof, hash_bytes(block)?)?;
+    let expected = &head.inner_lite.block_merkle_root;
+    if block_merkle_root != decode_hash(expected)? {
+        return Err(ProofError::BlockMerkleRootMismatch {
+            expected: expected.clone(),
+            computed: encode(block_merkle_root),
+        });
+    }
+    Ok(())
+}
+
+fn walk(path: &[MerklePathItem], item_hash: [u8; 32]) -> Result<[u8; 32], ProofError> {
+    path.iter().try_fold(item_hash, |hash, step| {
+        let sibling = decode_hash(&step.hash)?;
+        Ok(match step.direction {
+            Direction::Left => sha256(&[&sibling, &hash]),
+            Direction::Right => sha256(&[&hash, &sibling]),
+        })
+    })
+}
+
+fn encode(hash: [u8; 32]) -> CryptoHash {
+    CryptoHash(bs58::encode(hash).into_string())
+}
+
+fn outcome_hash_bytes(outcome: &ExecutionOutcomeWithIdView) -> Result<[u8; 32], ProofError> {
+    let view = &outcome.outcome;
+    let mut hashes = vec![
+        decode_hash(&outcome.id)?,
+        sha256(&[&partial_outcome_borsh(view)?]),
+    ];
+    hashes.extend(view.logs.iter().map(|log| sha256(&[log.as_bytes()])));
+
+    let mut buf = Vec::with_capacity(4 + hashes.len() * 32);
+    buf.extend_from_slice(&(hashes.len() as u32).to_le_bytes());
+    for hash in &hashes {
+        buf.extend_from_slice(hash);
+    }
+    Ok(sha256(&[&buf]))
+}
+
+/// Borsh layout of nearcore's `PartialExecutionOutcome`.
+fn partial_outcome_borsh(view: &ExecutionOutcomeView) -> Result<Vec<u8>, ProofError> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(view.receipt_ids.len() as u32).to_le_bytes());
+    for receipt_id in &view.receipt_ids {
+        buf.extend_from_slice(&decode_hash(receipt_id)?);
+    }
+    buf.extend_from_slice(&view.gas_burnt.0.to_le_bytes());
+    let tokens_burnt: u128 = view
+        .tokens_burnt
+        .parse()
+        .map_err(|_| LightClientError::InvalidAmount(view.tokens_burnt.0.clone()))?;
+    buf.extend_from_slice(&tokens_burnt.to_le_bytes());
+    buf.extend_from_slice(&(view.executor_id.len() as u32).to_le_bytes());
+    buf.extend_from_slice(view.executor_id.as_bytes());
+    match &view.status {
+        ExecutionStatusView::Unknown => buf.push(0),
+        ExecutionStatusView::Failure(_) => buf.push(1),
+        ExecutionStatusView::SuccessValue(value) => {
+            let bytes = STANDARD
+                .decode(value)
+                .map_err(|_| ProofError::InvalidSuccessValue(value.clone()))?;
+            buf.push(2);
+            buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&bytes);
+        }
+        ExecutionStatusView::SuccessReceiptId(receipt_id) => {
+            buf.push(3);
+            buf.extend_from_slice(&decode_hash(receipt_id)?);
+        }
+    }
+    Ok(buf)
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use super::{verify_block_proof, verify_execution_proof};
+    use crate::client::{NearRpcClient, Result};
+    use crate::light_client::LightClient;
+    use crate::types::*;
+
+    impl NearRpcClient {
+        /// Calls `light_client_proof` and verifies the response against the
+        /// head of `light_client`, which should be the `light_client_head` of
+        /// `request`.
+        pub async fn verified_light_client_proof(
+            &self,
+            request: RpcLightClientExecutionProofRequest,
+            light_client: &LightClient,
+        ) -> Result<RpcLightClientExecutionProofResponse> {
+            let proof = self.light_client_proof(request).await?;
+            verify_execution_proof(&proof, light_client.head())?;
+            Ok(proof)
+        }
+
+        /// Calls `light_client_block_proof` and verifies the response against
+        /// the head of `light_client`, which should be the `light_client_head`
+        /// of `request`.
+        pub async fn verified_light_client_block_proof(
+            &self,
+            request: RpcLightClientBlockProofRequest,
+            light_client: &LightClient,
+        ) -> Result<RpcLightClientBlockProofResponse> {
+            let proof = self.light_client_block_proof(request).await?;
+            verify_block_proof(&proof, light_client.head())?;
+            Ok(proof)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sample_block, sample_outcomes};
+
+    fn hash(byte: u8) -> CryptoHash {
+        encode([byte; 32])
+    }
+
+    fn hash_of(hash: &str) -> CryptoHash {
+        CryptoHash(hash.to_string())
+    }
+
+    /// A head whose block merkle tree has the two blocks before
+    /// [`sample_block`] and then `leaf`, with the root nearcore's
+    /// `PartialMerkleTree` computes for them. Returns the proof for `leaf`.
+    fn head(block_merkle_root: &str) -> (Vec<MerklePathItem>, LightClientBlockLiteView) {
+        let mut head = sample_block();
+        head.inner_lite.block_merkle_root = hash_of(block_merkle_root);
+        let block_proof = vec![MerklePathItem {
+            // combine_hash(last_final_block, prev_hash) of the sample block.
+            direction: Direction::Left,
+            hash: hash_of("GoqWZVBe2Fkdfbou2T1WoDDDAwzCxQKU9vhxGkFgJdvV"),
+        }];
+        (block_proof, head)
+    }
+
+    /// An execution proof for the sample transaction outcome. The sample
+    /// block's `outcome_root` is replaced with the root nearcore's `merklize`
+    /// gives for the chunk's outcomes, which changes its hash to
+    /// `S8pKd3YrXX5kT5LB6o37U52uiZa9WjwpVKhkYtaWL1A`.
+    fn proof() -> (
+        RpcLightClientExecutionProofResponse,
+        LightClientBlockLiteView,
+    ) {
+        let [tx_outcome, _] = sample_outcomes();
+        let mut block_header_lite = sample_block();
+        block_header_lite.inner_lite.outcome_root =
+            hash_of("Gx1udXipW2ThN6sA6TfPmaLoDdvy3myhDxDapS5Z8fww");
+        let (block_proof, head) = head("4hme3xx7XjBhE91AGuHB2QGStQUinctm1JkJc9czZ96n");
+
+        let proof = RpcLightClientExecutionProofResponse {
+            block_header_lite,
+            block_proof,
+            outcome_proof: tx_outcome,
+            outcome_root_proof: Vec::new(),
+        };
+        (proof, head)
+    }
+
+    #[test]
+    fn computes_root_from_path() {
+        let path = vec![
+            MerklePathItem {
+                direction: Direction::Left,
+                hash: hash(1),
+            },
+            MerklePathItem {
+                direction: Direction::Right,
+                hash: hash(2),
+            },
+        ];
+        let expected = sha256(&[&sha256(&[&[1; 32], &[3; 32]]), &[2; 32]]);
+        assert_eq!(
+            compute_root_from_path(&path, &hash(3)).unwrap(),
+            encode(expected)
+        );
+        assert_eq!(compute_root_from_path(&[], &hash(3)).unwrap(), hash(3));
+    }
+
+    #[test]
+    fn outcome_hashes_match_nearcore() {
+        // The chunk has two outcomes, so each is the other's proof sibling.
+        let [tx_outcome, receipt_outcome] = sample_outcomes();
+        assert_eq!(
+            outcome_hash(&tx_outcome).unwrap(),
+            receipt_outcome.proof[0].hash
+        );
+        assert_eq!(
+            outcome_hash(&receipt_outcome).unwrap(),
+            tx_outcome.proof[0].hash
+        );
+
+        // The chunk's outcome root, from nearcore's `merklize`.
+        let shard_outcome_root = hash_of("Dw34LC3qy95dgDWcX5H3tdtcUXrc2dD6FnoFpWWQbKu6");
+        for outcome in [tx_outcome, receipt_outcome] {
+            let root =
+                compute_root_from_path(&outcome.proof, &outcome_hash(&outcome).unwrap()).unwrap();
+            assert_eq!(root, shard_outcome_root);
+        }
+    }
+
+    #[test]
+    fn verifies_block_proof() {
+        let (block_proof, head) = head("2HxDzDTPDwSYvbi1i26uyDED9FhqcbRK7aQ55tNrrk8v");
+        verify_block_proof(
+            &RpcLightClientBlockProofResponse {
+                block_header_lite: sample_block(),
+                block_proof,
+            },
+            &head,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn verifies_execution_proof() {
+        let (proof, head) = proof();
+        verify_execution_proof(&proof, &head).unwrap();
+    }
+
+    #[test]
+    fn rejects_tampered_outcome() {
+        let (mut proof, head) = proof();
+        proof.outcome_proof.outcome.logs.push("hello".to_string());
+        assert!(matches!(
+            verify_execution_proof(&proof, &head),
+            Err(ProofError::OutcomeRootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_block_outside_head() {
+        let (mut proof, head) = proof();
+        proof.block_proof[0].direction = Direction::Right;
+        assert!(matches!(
+            verify_execution_proof(&proof, &head),
+            Err(ProofError::BlockMerkleRootMismatch { .. })
+        ));
+    }
+}
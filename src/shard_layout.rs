@@ -0,0 +1,422 @@
+//! Account-to-shard mapping for [`ShardLayout`].
+//!
+//! Mirrors nearcore's `ShardLayout` methods on the generated types:
+//!
+//! - **V0** layouts hash the account id and take it modulo the shard count.
+//! - **V1–V3** layouts split the account space at `boundary_accounts`: shard
+//!   index `i` holds the accounts `>= boundary_accounts[i - 1]` and
+//!   `< boundary_accounts[i]`.
+//!
+//! From V2 on, shard ids are no longer the shard indices — after a split the
+//! children get fresh ids — so most lookups go through the index maps.
+//!
+//! The layout of any block comes from `EXPERIMENTAL_protocol_config`, see
+//! [`NearRpcClient::shard_layout`](crate::client::NearRpcClient::shard_layout).
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::types::*;
+
+/// Errors from shard id and index lookups.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ShardLayoutError {
+    #[error("shard id {0} is not part of the shard layout")]
+    UnknownShardId(u64),
+    #[error("shard index {0} is out of range for the shard layout")]
+    UnknownShardIndex(usize),
+}
+
+impl ShardLayout {
+    /// The layout version used in [`ShardUId`]s. V3 layouts have no version
+    /// field; nearcore keeps using 3, the version of the V2 layouts before it.
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::V0(layout) => layout.version,
+            Self::V1(layout) => layout.version,
+            Self::V2(layout) => layout.version,
+            Self::V3(_) => 3,
+        }
+    }
+
+    pub fn num_shards(&self) -> usize {
+        match self {
+            Self::V0(layout) => layout.num_shards as usize,
+            Self::V1(layout) => layout.boundary_accounts.len() + 1,
+            Self::V2(layout) => layout.shard_ids.len(),
+            Self::V3(layout) => layout.shard_ids.len(),
+        }
+    }
+
+    /// All shard ids, in shard index order.
+    pub fn shard_ids(&self) -> Vec<ShardId> {
+        match self {
+            Self::V0(_) | Self::V1(_) => (0..self.num_shards() as u64).map(ShardId).collect(),
+            Self::V2(layout) => layout.shard_ids.clone(),
+            Self::V3(layout) => layout.shard_ids.clone(),
+        }
+    }
+
+    /// All shards as [`ShardUId`]s, in shard index order.
+    pub fn shard_uids(&self) -> Vec<ShardUId> {
+        self.shard_ids()
+            .iter()
+            .map(|shard_id| self.shard_uid(shard_id))
+            .collect()
+    }
+
+    pub fn shard_uid(&self, shard_id: &ShardId) -> ShardUId {
+        ShardUId {
+            shard_id: shard_id.0 as u32,
+            version: self.version(),
+        }
+    }
+
+    /// The position of `shard_id` in [`shard_ids`](Self::shard_ids).
+    pub fn get_shard_index(&self, shard_id: &ShardId) -> Result<usize, ShardLayoutError> {
+        let index = match self {
+            Self::V0(_) | Self::V1(_) => Some(shard_id.0 as usize),
+            Self::V2(layout) => id_to_index(&layout.id_to_index_map, shard_id),
+            Self::V3(layout) => id_to_index(&layout.id_to_index_map, shard_id),
+        };
+        index
+            .filter(|&index| index < self.num_shards())
+            .ok_or(ShardLayoutError::UnknownShardId(shard_id.0))
+    }
+
+    /// The shard id at position `index` of [`shard_ids`](Self::shard_ids).
+    pub fn get_shard_id(&self, index: usize) -> Result<ShardId, ShardLayoutError> {
+        let shard_id = match self {
+            Self::V0(_) | Self::V1(_) => {
+                (index < self.num_shards()).then_some(ShardId(index as u64))
+            }
+            Self::V2(layout) => layout.index_to_id_map.get(&index.to_string()).cloned(),
+            Self::V3(layout) => layout.shard_ids.get(index).cloned(),
+        };
+        shard_id.ok_or(ShardLayoutError::UnknownShardIndex(index))
+    }
+
+    /// The index of the shard holding `account_id`.
+    pub fn account_id_to_shard_index(&self, account_id: &AccountId) -> usize {
+        match self {
+            Self::V0(layout) => {
+                let hash = Sha256::digest(account_id.as_bytes());
+                let prefix: [u8; 8] = hash[..8].try_into().expect("sha256 is 32 bytes");
+                (u64::from_le_bytes(prefix) % layout.num_shards.max(1)) as usize
+            }
+            Self::V1(layout) => boundary_index(&layout.boundary_accounts, account_id),
+            Self::V2(layout) => boundary_index(&layout.boundary_accounts, account_id),
+            Self::V3(layout) => boundary_index(&layout.boundary_accounts, account_id),
+        }
+    }
+
+    /// The id of the shard holding `account_id`.
+    pub fn account_id_to_shard_id(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<ShardId, ShardLayoutError> {
+        self.get_shard_id(self.account_id_to_shard_index(account_id))
+    }
+
+    /// The [`ShardUId`] of the shard holding `account_id`.
+    pub fn account_id_to_shard_uid(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<ShardUId, ShardLayoutError> {
+        Ok(self.shard_uid(&self.account_id_to_shard_id(account_id)?))
+    }
+
+    /// The shard of the previous layout that `shard_id` was split from (or
+    /// carried over from), or `None` for layouts without parent information.
+    /// V3 layouts keep every past split, so for them this is the parent of
+    /// the split that created `shard_id`, whichever layout that was in.
+    pub fn get_parent_shard_id(&self, shard_id: &ShardId) -> Option<ShardId> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(layout) => layout
+                .to_parent_shard_map
+                .as_ref()?
+                .get(shard_id.0 as usize)
+                .cloned(),
+            Self::V2(layout) => layout
+                .shards_parent_map
+                .as_ref()?
+                .get(&shard_id.to_string())
+                .cloned(),
+            Self::V3(layout) => parent_from_split_map(&layout.shards_split_map, shard_id),
+        }
+    }
+
+    /// The shards of this layout that `parent` from the previous layout was
+    /// split into, or `None` if the layout has no split information for it.
+    pub fn get_children_shards_ids(&self, parent: &ShardId) -> Option<Vec<ShardId>> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(layout) => layout
+                .shards_split_map
+                .as_ref()?
+                .get(parent.0 as usize)
+                .cloned(),
+            Self::V2(layout) => layout
+                .shards_split_map
+                .as_ref()?
+                .get(&parent.to_string())
+                .cloned(),
+            Self::V3(layout) => layout.shards_split_map.get(&parent.to_string()).cloned(),
+        }
+    }
+}
+
+fn boundary_index(boundary_accounts: &[AccountId], account_id: &AccountId) -> usize {
+    boundary_accounts.partition_point(|boundary| boundary.as_str() <= account_id.as_str())
+}
+
+fn id_to_index(id_to_index_map: &HashMap<String, u32>, shard_id: &ShardId) -> Option<usize> {
+    id_to_index_map
+        .get(&shard_id.to_string())
+        .map(|&index| index as usize)
+}
+
+fn parent_from_split_map(
+    split_map: &HashMap<String, Vec<ShardId>>,
+    shard_id: &ShardId,
+) -> Option<ShardId> {
+    split_map.iter().find_map(|(parent, children)| {
+        children
+            .iter()
+            .any(|child| child.0 == shard_id.0)
+            .then(|| parent.parse().ok().map(ShardId))
+            .flatten()
+    })
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use crate::client::{NearRpcClient, Result};
+    use crate::types::*;
+
+    impl NearRpcClient {
+        /// Returns the shard layout in effect at the block of `request`, from
+        /// `EXPERIMENTAL_protocol_config`.
+        pub async fn shard_layout(&self, request: RpcProtocolConfigRequest) -> Result<ShardLayout> {
+            Ok(self.protocol_config(request).await?.shard_layout)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mainnet's layout from protocol version 64, as recorded in nearcore's
+    /// `epoch_configs/mainnet/64.json`.
+    fn simple_nightshade_v2() -> ShardLayout {
+        serde_json::from_value(serde_json::json!({
+            "V1": {
+                "boundary_accounts": [
+                    "aurora",
+                    "aurora-0",
+                    "kkuuue2akv_1630967379.near",
+                    "tge-lockup.sweat"
+                ],
+                "shards_split_map": [[0], [1], [2], [3, 4]],
+                "to_parent_shard_map": [0, 1, 2, 3, 3],
+                "version": 2
+            }
+        }))
+        .unwrap()
+    }
+
+    /// Mainnet's current layout, from nearcore's
+    /// `epoch_configs/mainnet/81.json`.
+    fn mainnet_v2() -> ShardLayout {
+        serde_json::from_value(serde_json::json!({
+            "V2": {
+                "boundary_accounts": [
+                    "650",
+                    "aurora",
+                    "aurora-0",
+                    "earn.kaiching",
+                    "game.hot.tg",
+                    "game.hot.tg-0",
+                    "kkuuue2akv_1630967379.near",
+                    "tge-lockup.sweat"
+                ],
+                "shard_ids": [10, 11, 1, 8, 9, 6, 7, 4, 5],
+                "id_to_index_map": {
+                    "1": 2, "10": 0, "11": 1, "4": 7, "5": 8, "6": 5, "7": 6, "8": 3, "9": 4
+                },
+                "index_to_id_map": {
+                    "0": 10, "1": 11, "2": 1, "3": 8, "4": 9, "5": 6, "6": 7, "7": 4, "8": 5
+                },
+                "shards_split_map": {
+                    "0": [10, 11], "1": [1], "4": [4], "5": [5],
+                    "6": [6], "7": [7], "8": [8], "9": [9]
+                },
+                "shards_parent_map": {
+                    "1": 1, "10": 0, "11": 0, "4": 4, "5": 5, "6": 6, "7": 7, "8": 8, "9": 9
+                },
+                "version": 3
+            }
+        }))
+        .unwrap()
+    }
+
+    /// The V3 layout nearcore derives from [`mainnet_v2`] and mainnet's
+    /// layout history when shard 4 is split at `sweat`.
+    fn mainnet_v3() -> ShardLayout {
+        serde_json::from_value(serde_json::json!({
+            "V3": {
+                "boundary_accounts": [
+                    "650",
+                    "aurora",
+                    "aurora-0",
+                    "earn.kaiching",
+                    "game.hot.tg",
+                    "game.hot.tg-0",
+                    "kkuuue2akv_1630967379.near",
+                    "sweat",
+                    "tge-lockup.sweat"
+                ],
+                "shard_ids": [10, 11, 1, 8, 9, 6, 7, 12, 13, 5],
+                "id_to_index_map": {
+                    "1": 2, "10": 0, "11": 1, "12": 7, "13": 8,
+                    "5": 9, "6": 5, "7": 6, "8": 3, "9": 4
+                },
+                "shards_split_map": {
+                    "0": [10, 11], "2": [8, 9], "3": [6, 7], "4": [12, 13]
+                },
+                "last_split": 4
+            }
+        }))
+        .unwrap()
+    }
+
+    fn shard_of(layout: &ShardLayout, account_id: &str) -> u64 {
+        layout
+            .account_id_to_shard_id(&account_id.parse().unwrap())
+            .unwrap()
+            .0
+    }
+
+    // The expected shards below were computed by near-primitives from the
+    // same layouts.
+
+    #[test]
+    fn maps_accounts_by_boundary() {
+        let layout = simple_nightshade_v2();
+        assert_eq!(layout.num_shards(), 5);
+        assert_eq!(shard_of(&layout, "0.near"), 0);
+        assert_eq!(shard_of(&layout, "650"), 0);
+        assert_eq!(shard_of(&layout, "a.near"), 0);
+        assert_eq!(shard_of(&layout, "aurora"), 1);
+        assert_eq!(shard_of(&layout, "aurora-0"), 2);
+        assert_eq!(shard_of(&layout, "hello.near"), 2);
+        assert_eq!(shard_of(&layout, "game.hot.tg-0"), 2);
+        assert_eq!(shard_of(&layout, "kkuuue2akv_1630967379.near"), 3);
+        assert_eq!(shard_of(&layout, "sweat_welcome.near"), 3);
+        assert_eq!(shard_of(&layout, "tge-lockup.sweat"), 4);
+        assert_eq!(shard_of(&layout, "zzz.near"), 4);
+
+        assert_eq!(
+            layout.get_parent_shard_id(&ShardId(4)).map(|id| id.0),
+            Some(3)
+        );
+        let children: Vec<u64> = layout
+            .get_children_shards_ids(&ShardId(3))
+            .unwrap()
+            .iter()
+            .map(|id| id.0)
+            .collect();
+        assert_eq!(children, [3, 4]);
+    }
+
+    #[test]
+    fn maps_accounts_through_index_maps() {
+        let layout = mainnet_v2();
+        assert_eq!(layout.num_shards(), 9);
+        for (account, shard) in [
+            ("0.near", 10),
+            ("650", 11),
+            ("6500.near", 11),
+            ("a.near", 11),
+            ("aurora", 1),
+            ("aurora-0", 8),
+            ("earn.kaiching", 9),
+            ("game.hot.tg", 6),
+            ("game.hot.tg-0", 7),
+            ("hello.near", 7),
+            ("kkuuue2akv_1630967379.near", 4),
+            ("sweat_welcome.near", 4),
+            ("tge-lockup.sweat", 5),
+            ("zzz.near", 5),
+        ] {
+            assert_eq!(shard_of(&layout, account), shard, "{account}");
+        }
+
+        assert_eq!(layout.get_shard_index(&ShardId(7)), Ok(6));
+        assert_eq!(
+            layout.get_shard_index(&ShardId(3)),
+            Err(ShardLayoutError::UnknownShardId(3))
+        );
+        assert_eq!(layout.get_shard_id(3).unwrap().0, 8);
+
+        let uid = layout
+            .account_id_to_shard_uid(&"game.hot.tg-0".parse().unwrap())
+            .unwrap();
+        assert_eq!((uid.shard_id, uid.version), (7, 3));
+        assert_eq!(
+            layout.get_parent_shard_id(&ShardId(11)).map(|id| id.0),
+            Some(0)
+        );
+        assert_eq!(
+            layout.get_parent_shard_id(&ShardId(6)).map(|id| id.0),
+            Some(6)
+        );
+        let children: Vec<u64> = layout
+            .get_children_shards_ids(&ShardId(0))
+            .unwrap()
+            .iter()
+            .map(|id| id.0)
+            .collect();
+        assert_eq!(children, [10, 11]);
+        assert!(layout.get_children_shards_ids(&ShardId(2)).is_none());
+    }
+
+    #[test]
+    fn v0_hashes_into_range_and_v3_inverts_split_map() {
+        let v0 = ShardLayout::V0(ShardLayoutV0 {
+            num_shards: 4,
+            version: 0,
+        });
+        for account in ["near", "alice.near", "aurora"] {
+            assert!(shard_of(&v0, account) < 4);
+        }
+
+        let v3 = mainnet_v3();
+        assert_eq!(v3.version(), 3);
+        assert_eq!(v3.num_shards(), 10);
+        for (account, shard) in [
+            ("0.near", 10),
+            ("a.near", 11),
+            ("aurora", 1),
+            ("aurora-0", 8),
+            ("hello.near", 7),
+            ("kkuuue2akv_1630967379.near", 12),
+            ("sweat", 13),
+            ("sweat_welcome.near", 13),
+            ("tge-lockup.sweat", 5),
+            ("zzz.near", 5),
+        ] {
+            assert_eq!(shard_of(&v3, account), shard, "{account}");
+        }
+        assert_eq!(v3.get_shard_index(&ShardId(13)), Ok(8));
+        assert_eq!(v3.get_parent_shard_id(&ShardId(12)).map(|id| id.0), Some(4));
+        assert_eq!(v3.get_parent_shard_id(&ShardId(13)).map(|id| id.0), Some(4));
+        // Shards from earlier splits keep their parent in the history.
+        assert_eq!(v3.get_parent_shard_id(&ShardId(7)).map(|id| id.0), Some(3));
+    }
+}
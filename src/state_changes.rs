@@ -0,0 +1,433 @@
+//! Typed state-change queries.
+//!
+//! [`NearRpcClient::changes`] takes typify's untagged
+//! `RpcStateChangesInBlockByTypeRequest` and returns the raw
+//! `StateChangeWithCauseView`s, with keys, values and code still in base64.
+//! [`NearRpcClient::changes_for`] takes a block reference and a
+//! [`StateChangesRequestView`] instead, and returns every kind of change
+//! decoded and grouped per account.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::{NearRpcClient, state_changes::DataChange, types::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     let changes = client
+//!         .changes_for(
+//!             RpcBlockRequest::Finality(Finality::Final),
+//!             StateChangesRequestView::DataChanges {
+//!                 account_ids: vec!["wrap.near".parse().unwrap()],
+//!                 key_prefix_base64: StoreKey(String::new()),
+//!             },
+//!         )
+//!         .await?;
+//!
+//!     for (account_id, account) in &changes.accounts {
+//!         for data in &account.data {
+//!             if let DataChange::Update { key, value } = &data.change {
+//!                 println!("{account_id}: {} bytes at {key:?}", value.len());
+//!             }
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+
+use crate::client::{NearRpcClient, Result};
+use crate::types::*;
+
+/// Errors specific to decoding state changes.
+#[derive(Debug, thiserror::Error)]
+pub enum StateChangesError {
+    #[error("invalid base64 in {field} of a change to {account_id}")]
+    InvalidBase64 {
+        field: &'static str,
+        account_id: AccountId,
+    },
+}
+
+/// A change together with what caused it.
+#[derive(Debug, Clone)]
+pub struct Caused<T> {
+    pub cause: StateChangeCauseView,
+    pub change: T,
+}
+
+/// A change to the account record itself.
+#[derive(Debug, Clone)]
+pub enum AccountChange {
+    /// The account's new state.
+    Update(Box<AccountUpdateChange>),
+    Deletion,
+}
+
+/// A change to one of the account's access keys.
+#[derive(Debug, Clone)]
+pub enum AccessKeyChange {
+    Update {
+        public_key: PublicKey,
+        access_key: AccessKeyView,
+    },
+    Deletion {
+        public_key: PublicKey,
+    },
+    GasKeyNonceUpdate {
+        public_key: PublicKey,
+        index: u16,
+        nonce: u64,
+    },
+}
+
+/// A change to a contract storage entry.
+#[derive(Debug, Clone)]
+pub enum DataChange {
+    Update { key: Vec<u8>, value: Vec<u8> },
+    Deletion { key: Vec<u8> },
+}
+
+/// A contract deployment or removal.
+#[derive(Debug, Clone)]
+pub enum CodeChange {
+    /// The new contract code.
+    Update(Vec<u8>),
+    Deletion,
+}
+
+/// The changes to one account, each group in the order the node returned it.
+#[derive(Debug, Clone, Default)]
+pub struct AccountStateChanges {
+    pub account: Vec<Caused<AccountChange>>,
+    pub access_keys: Vec<Caused<AccessKeyChange>>,
+    pub data: Vec<Caused<DataChange>>,
+    pub code: Vec<Caused<CodeChange>>,
+}
+
+/// Decoded result of [`NearRpcClient::changes_for`].
+#[derive(Debug, Clone)]
+pub struct StateChanges {
+    pub block_hash: CryptoHash,
+    pub accounts: BTreeMap<AccountId, AccountStateChanges>,
+}
+
+impl StateChanges {
+    /// Returns the changes to `account_id`, if there were any.
+    pub fn account(&self, account_id: &AccountId) -> Option<&AccountStateChanges> {
+        self.accounts.get(account_id)
+    }
+}
+
+impl NearRpcClient {
+    /// Returns the state changes selected by `request` in the block
+    /// referenced by `block`, decoded and grouped per account.
+    pub async fn changes_for(
+        &self,
+        block: RpcBlockRequest,
+        request: StateChangesRequestView,
+    ) -> Result<StateChanges> {
+        let response: RawResponse = self
+            .call(
+                "EXPERIMENTAL_changes",
+                ChangesRequest {
+                    block: &block,
+                    request: &request,
+                },
+            )
+            .await?;
+        Ok(response.decode()?)
+    }
+}
+
+// ── Wire format ──────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct ChangesRequest<'a> {
+    #[serde(flatten)]
+    block: &'a RpcBlockRequest,
+    #[serde(flatten)]
+    request: &'a StateChangesRequestView,
+}
+
+#[derive(Deserialize)]
+struct RawResponse {
+    block_hash: CryptoHash,
+    changes: Vec<RawChange>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum RawChange {
+    AccountUpdate {
+        cause: StateChangeCauseView,
+        change: Box<AccountUpdateChange>,
+    },
+    AccountDeletion {
+        cause: StateChangeCauseView,
+        change: AccountRef,
+    },
+    AccessKeyUpdate {
+        cause: StateChangeCauseView,
+        change: AccessKeyUpdate,
+    },
+    AccessKeyDeletion {
+        cause: StateChangeCauseView,
+        change: AccountWithPublicKey,
+    },
+    GasKeyNonceUpdate {
+        cause: StateChangeCauseView,
+        change: GasKeyNonceUpdate,
+    },
+    DataUpdate {
+        cause: StateChangeCauseView,
+        change: DataUpdate,
+    },
+    DataDeletion {
+        cause: StateChangeCauseView,
+        change: DataDeletion,
+    },
+    ContractCodeUpdate {
+        cause: StateChangeCauseView,
+        change: CodeUpdate,
+    },
+    ContractCodeDeletion {
+        cause: StateChangeCauseView,
+        change: AccountRef,
+    },
+}
+
+#[derive(Deserialize)]
+struct AccountRef {
+    account_id: AccountId,
+}
+
+#[derive(Deserialize)]
+struct AccessKeyUpdate {
+    account_id: AccountId,
+    public_key: PublicKey,
+    access_key: AccessKeyView,
+}
+
+#[derive(Deserialize)]
+struct GasKeyNonceUpdate {
+    account_id: AccountId,
+    public_key: PublicKey,
+    index: u16,
+    nonce: u64,
+}
+
+#[derive(Deserialize)]
+struct DataUpdate {
+    account_id: AccountId,
+    key_base64: StoreKey,
+    value_base64: StoreValue,
+}
+
+#[derive(Deserialize)]
+struct DataDeletion {
+    account_id: AccountId,
+    key_base64: StoreKey,
+}
+
+#[derive(Deserialize)]
+struct CodeUpdate {
+    account_id: AccountId,
+    code_base64: String,
+}
+
+impl RawResponse {
+    fn decode(self) -> std::result::Result<StateChanges, StateChangesError> {
+        let mut accounts: BTreeMap<AccountId, AccountStateChanges> = BTreeMap::new();
+        for change in self.changes {
+            match change {
+                RawChange::AccountUpdate { cause, change } => {
+                    let entry = accounts.entry(change.account_id.clone()).or_default();
+                    entry.account.push(Caused {
+                        cause,
+                        change: AccountChange::Update(change),
+                    });
+                }
+                RawChange::AccountDeletion { cause, change } => {
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.account.push(Caused {
+                        cause,
+                        change: AccountChange::Deletion,
+                    });
+                }
+                RawChange::AccessKeyUpdate { cause, change } => {
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.access_keys.push(Caused {
+                        cause,
+                        change: AccessKeyChange::Update {
+                            public_key: change.public_key,
+                            access_key: change.access_key,
+                        },
+                    });
+                }
+                RawChange::AccessKeyDeletion { cause, change } => {
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.access_keys.push(Caused {
+                        cause,
+                        change: AccessKeyChange::Deletion {
+                            public_key: change.public_key,
+                        },
+                    });
+                }
+                RawChange::GasKeyNonceUpdate { cause, change } => {
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.access_keys.push(Caused {
+                        cause,
+                        change: AccessKeyChange::GasKeyNonceUpdate {
+                            public_key: change.public_key,
+                            index: change.index,
+                            nonce: change.nonce,
+                        },
+                    });
+                }
+                RawChange::DataUpdate { cause, change } => {
+                    let key = decode(&change.key_base64, "key_base64", &change.account_id)?;
+                    let value = decode(&change.value_base64, "value_base64", &change.account_id)?;
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.data.push(Caused {
+                        cause,
+                        change: DataChange::Update { key, value },
+                    });
+                }
+                RawChange::DataDeletion { cause, change } => {
+                    let key = decode(&change.key_base64, "key_base64", &change.account_id)?;
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.data.push(Caused {
+                        cause,
+                        change: DataChange::Deletion { key },
+                    });
+                }
+                RawChange::ContractCodeUpdate { cause, change } => {
+                    let code = decode(&change.code_base64, "code_base64", &change.account_id)?;
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.code.push(Caused {
+                        cause,
+                        change: CodeChange::Update(code),
+                    });
+                }
+                RawChange::ContractCodeDeletion { cause, change } => {
+                    let entry = accounts.entry(change.account_id).or_default();
+                    entry.code.push(Caused {
+                        cause,
+                        change: CodeChange::Deletion,
+                    });
+                }
+            }
+        }
+        Ok(StateChanges {
+            block_hash: self.block_hash,
+            accounts,
+        })
+    }
+}
+
+fn decode(
+    encoded: &str,
+    field: &'static str,
+    account_id: &AccountId,
+) -> std::result::Result<Vec<u8>, StateChangesError> {
+    STANDARD
+        .decode(encoded)
+        .map_err(|_| StateChangesError::InvalidBase64 {
+            field,
+            account_id: account_id.clone(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_flattens_block_reference_and_kind() {
+        let block = RpcBlockRequest::BlockId(BlockId::BlockHeight(42));
+        let request = StateChangesRequestView::DataChanges {
+            account_ids: vec!["app.near".parse().unwrap()],
+            key_prefix_base64: StoreKey("U1RBVEU=".to_string()),
+        };
+        let json = serde_json::to_value(ChangesRequest {
+            block: &block,
+            request: &request,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "block_id": 42,
+                "changes_type": "data_changes",
+                "account_ids": ["app.near"],
+                "key_prefix_base64": "U1RBVEU=",
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_and_groups_changes() {
+        let response: RawResponse = serde_json::from_value(serde_json::json!({
+            "block_hash": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1",
+            "changes": [
+                {
+                    "type": "data_update",
+                    "cause": { "type": "receipt_processing", "receipt_hash": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1" },
+                    "change": { "account_id": "app.near", "key_base64": "U1RBVEU=", "value_base64": "AQI=" }
+                },
+                {
+                    "type": "access_key_deletion",
+                    "cause": { "type": "transaction_processing", "tx_hash": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1" },
+                    "change": { "account_id": "alice.near", "public_key": "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp" }
+                },
+                {
+                    "type": "account_update",
+                    "cause": { "type": "action_receipt_gas_reward", "receipt_hash": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1" },
+                    "change": {
+                        "account_id": "app.near",
+                        "amount": "100",
+                        "code_hash": "11111111111111111111111111111111",
+                        "locked": "0",
+                        "storage_usage": 182
+                    }
+                },
+                {
+                    "type": "contract_code_update",
+                    "cause": { "type": "receipt_processing", "receipt_hash": "9FMnGHBEfJ3PoKzSaq7EwCotanD3RLGA9UFqEjB3hrN1" },
+                    "change": { "account_id": "app.near", "code_base64": "AGFzbQ==" }
+                }
+            ]
+        }))
+        .unwrap();
+        let changes = response.decode().unwrap();
+
+        let app = changes.account(&"app.near".parse().unwrap()).unwrap();
+        assert!(matches!(
+            &app.data[..],
+            [Caused { change: DataChange::Update { key, value }, cause: StateChangeCauseView::ReceiptProcessing { .. } }]
+                if key == b"STATE" && value == &[1, 2]
+        ));
+        assert!(
+            matches!(&app.account[..], [Caused { change: AccountChange::Update(account), .. }] if account.storage_usage == 182)
+        );
+        assert!(
+            matches!(&app.code[..], [Caused { change: CodeChange::Update(code), .. }] if code == b"\0asm")
+        );
+
+        let alice = changes.account(&"alice.near".parse().unwrap()).unwrap();
+        assert!(matches!(
+            &alice.access_keys[..],
+            [Caused {
+                change: AccessKeyChange::Deletion { .. },
+                ..
+            }]
+        ));
+        assert!(alice.data.is_empty());
+    }
+}
@@ -0,0 +1,499 @@
+//! Trie proof verification for `view_state` responses.
+//!
+//! With `include_proof: true`, `view_state` returns the serialized trie nodes
+//! (and values) the node visited while collecting the contract's state. Each is
+//! addressed by its SHA-256 hash, so starting from a trusted state root the
+//! proof can be walked down to the requested prefix and through everything
+//! below it. [`verify_state_proof`] does that walk and checks that the
+//! returned items are exactly the values stored under the prefix: every item
+//! is proven, and no stored key was left out.
+//!
+//! The state root to check against is the post-state root of the account's
+//! shard at the response's block — the `prev_state_root` of that shard's
+//! chunk header in the following block.
+
+use std::collections::{BTreeMap, HashMap};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::light_client::{decode_hash, sha256};
+use crate::types::*;
+
+/// Trie key column of contract storage (`col::CONTRACT_DATA` in nearcore).
+const CONTRACT_DATA: u8 = 9;
+/// Separator between the account id and the storage key in trie keys.
+const ACCOUNT_DATA_SEPARATOR: u8 = b',';
+
+/// Reasons a state proof fails verification.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StateProofError {
+    #[error("invalid state root: {0}")]
+    InvalidStateRoot(String),
+    #[error("invalid base64 in {field}: {value}")]
+    InvalidBase64 { field: &'static str, value: String },
+    #[error("proof is missing trie node {hash}")]
+    MissingNode { hash: CryptoHash },
+    #[error("trie node {hash} is malformed")]
+    InvalidNode { hash: CryptoHash },
+    #[error("value for key {key} does not match the trie")]
+    ValueMismatch { key: StoreKey },
+    #[error("key {key} is not in the trie")]
+    UnprovenItem { key: StoreKey },
+    #[error("response omits key {key} stored in the trie")]
+    MissingItem { key: StoreKey },
+}
+
+/// Verifies that `response` lists exactly the contract storage of
+/// `account_id` under `prefix` in the trie with root `state_root`, returning
+/// the verified key/value pairs.
+///
+/// `prefix` is the raw (not base64) storage key prefix of the request.
+pub fn verify_state_proof(
+    response: &RpcViewStateResponse,
+    account_id: &AccountId,
+    prefix: &[u8],
+    state_root: &CryptoHash,
+) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, StateProofError> {
+    let root = decode_hash(state_root)
+        .map_err(|_| StateProofError::InvalidStateRoot(state_root.0.clone()))?;
+
+    let mut nodes = HashMap::new();
+    for encoded in &response.proof {
+        let bytes = decode_base64("proof", encoded)?;
+        nodes.insert(sha256(&[&bytes]), bytes);
+    }
+
+    let key_prefix = contract_data_key(account_id, &[]);
+    let mut proven = BTreeMap::new();
+    if root != [0; 32] {
+        let walk = Walk {
+            nodes: &nodes,
+            prefix: to_nibbles(&contract_data_key(account_id, prefix)),
+        };
+        walk.visit(root, &mut Vec::new(), &mut proven)?;
+    }
+
+    let mut verified = BTreeMap::new();
+    for item in &response.values {
+        let key = decode_base64("key", &item.key)?;
+        let value = decode_base64("value", &item.value)?;
+        let trie_key = contract_data_key(account_id, &key);
+        let Some(value_ref) = proven.remove(&trie_key) else {
+            return Err(StateProofError::UnprovenItem {
+                key: item.key.clone(),
+            });
+        };
+        if value_ref.length as usize != value.len() || value_ref.hash != sha256(&[&value]) {
+            return Err(StateProofError::ValueMismatch {
+                key: item.key.clone(),
+            });
+        }
+        verified.insert(key, value);
+    }
+
+    if let Some(trie_key) = proven.into_keys().next() {
+        let key = trie_key.get(key_prefix.len()..).unwrap_or_default();
+        return Err(StateProofError::MissingItem {
+            key: StoreKey(STANDARD.encode(key)),
+        });
+    }
+    Ok(verified)
+}
+
+fn decode_base64(field: &'static str, value: &str) -> Result<Vec<u8>, StateProofError> {
+    STANDARD
+        .decode(value)
+        .map_err(|_| StateProofError::InvalidBase64 {
+            field,
+            value: value.to_string(),
+        })
+}
+
+/// The trie key of `key` in the contract storage of `account_id`.
+fn contract_data_key(account_id: &AccountId, key: &[u8]) -> Vec<u8> {
+    let mut trie_key = Vec::with_capacity(2 + account_id.len() + key.len());
+    trie_key.push(CONTRACT_DATA);
+    trie_key.extend_from_slice(account_id.as_bytes());
+    trie_key.push(ACCOUNT_DATA_SEPARATOR);
+    trie_key.extend_from_slice(key);
+    trie_key
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+fn from_nibbles(nibbles: &[u8]) -> Vec<u8> {
+    nibbles
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect()
+}
+
+/// Whether one of `a` and `b` is a prefix of the other.
+fn compatible(a: &[u8], b: &[u8]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x == y)
+}
+
+// ── Trie nodes ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ValueRef {
+    length: u32,
+    hash: [u8; 32],
+}
+
+/// nearcore's `RawTrieNode`, with keys decoded to nibbles.
+#[derive(Debug)]
+enum TrieNode {
+    Leaf(Vec<u8>, ValueRef),
+    Branch(Option<ValueRef>, Vec<(u8, [u8; 32])>),
+    Extension(Vec<u8>, [u8; 32]),
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Option<&[u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn hash(&mut self) -> Option<[u8; 32]> {
+        self.take(32)?.try_into().ok()
+    }
+
+    fn bytes(&mut self) -> Option<&[u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn value_ref(&mut self) -> Option<ValueRef> {
+        Some(ValueRef {
+            length: self.u32()?,
+            hash: self.hash()?,
+        })
+    }
+
+    fn children(&mut self) -> Option<Vec<(u8, [u8; 32])>> {
+        let bitmap = self.u16()?;
+        (0..16u8)
+            .filter(|i| bitmap & (1 << i) != 0)
+            .map(|i| Some((i, self.hash()?)))
+            .collect()
+    }
+
+    /// Hex-prefix encoded nibbles, as written by nearcore's `NibbleSlice`.
+    fn nibbles(&mut self) -> Option<Vec<u8>> {
+        let (first, rest) = self.bytes()?.split_first()?;
+        let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+        if first & 0x10 != 0 {
+            nibbles.push(first & 0x0f);
+        }
+        nibbles.extend(to_nibbles(rest));
+        Some(nibbles)
+    }
+}
+
+impl TrieNode {
+    /// Decodes a Borsh-encoded `RawTrieNodeWithSize`.
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        let node = match reader.u8()? {
+            0 => {
+                let key = reader.nibbles()?;
+                TrieNode::Leaf(key, reader.value_ref()?)
+            }
+            1 => TrieNode::Branch(None, reader.children()?),
+            2 => {
+                let value = reader.value_ref()?;
+                TrieNode::Branch(Some(value), reader.children()?)
+            }
+            3 => {
+                let key = reader.nibbles()?;
+                TrieNode::Extension(key, reader.hash()?)
+            }
+            _ => return None,
+        };
+        // memory_usage
+        reader.take(8)?;
+        reader.0.is_empty().then_some(node)
+    }
+}
+
+struct Walk<'a> {
+    nodes: &'a HashMap<[u8; 32], Vec<u8>>,
+    /// Nibbles of the trie key prefix being collected.
+    prefix: Vec<u8>,
+}
+
+impl Walk<'_> {
+    /// Collects the values under `self.prefix` in the subtree at `hash`,
+    /// whose path from the root is `path`.
+    fn visit(
+        &self,
+        hash: [u8; 32],
+        path: &mut Vec<u8>,
+        out: &mut BTreeMap<Vec<u8>, ValueRef>,
+    ) -> Result<(), StateProofError> {
+        let bytes = self
+            .nodes
+            .get(&hash)
+            .ok_or_else(|| StateProofError::MissingNode {
+                hash: CryptoHash(bs58::encode(hash).into_string()),
+            })?;
+        let node = TrieNode::decode(bytes).ok_or_else(|| StateProofError::InvalidNode {
+            hash: CryptoHash(bs58::encode(hash).into_string()),
+        })?;
+
+        let depth = path.len();
+        match node {
+            TrieNode::Leaf(key, value) => {
+                path.extend_from_slice(&key);
+                self.record(path, value, out);
+            }
+            TrieNode::Extension(key, child) => {
+                path.extend_from_slice(&key);
+                if compatible(path, &self.prefix) {
+                    self.visit(child, path, out)?;
+                }
+            }
+            TrieNode::Branch(value, children) => {
+                if let Some(value) = value {
+                    self.record(path, value, out);
+                }
+                for (index, child) in children {
+                    path.push(index);
+                    if compatible(path, &self.prefix) {
+                        self.visit(child, path, out)?;
+                    }
+                    path.pop();
+                }
+            }
+        }
+        path.truncate(depth);
+        Ok(())
+    }
+
+    fn record(&self, path: &[u8], value: ValueRef, out: &mut BTreeMap<Vec<u8>, ValueRef>) {
+        if path.starts_with(&self.prefix) && path.len().is_multiple_of(2) {
+            out.insert(from_nibbles(path), value);
+        }
+    }
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use std::collections::BTreeMap;
+
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    use super::verify_state_proof;
+    use crate::client::{NearRpcClient, Result};
+    use crate::types::*;
+
+    impl NearRpcClient {
+        /// Calls `view_state` with a proof at `block_id` and verifies it
+        /// against `state_root`, returning the verified key/value pairs
+        /// under `prefix`.
+        ///
+        /// See the [module docs](super) for which state root to use.
+        pub async fn view_state_verified(
+            &self,
+            account_id: &AccountId,
+            prefix: &[u8],
+            block_id: BlockId,
+            state_root: &CryptoHash,
+        ) -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+            let response = self
+                .view_state(RpcViewStateRequest::BlockIdAccountId {
+                    account_id: account_id.clone(),
+                    block_id,
+                    include_proof: true,
+                    prefix_base64: StoreKey(STANDARD.encode(prefix)),
+                })
+                .await?;
+            Ok(verify_state_proof(
+                &response, account_id, prefix, state_root,
+            )?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hex-prefix encoding of `nibbles`, as in nearcore's `NibbleSlice::encode_nibbles`.
+    fn encode_nibbles(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+        let flag = if is_leaf { 0x20 } else { 0 };
+        let (first, rest) = if nibbles.len() % 2 == 1 {
+            (flag | 0x10 | nibbles[0], &nibbles[1..])
+        } else {
+            (flag, nibbles)
+        };
+        let mut out = vec![first];
+        out.extend(from_nibbles(rest));
+        out
+    }
+
+    fn with_len(bytes: &[u8]) -> Vec<u8> {
+        let mut out = (bytes.len() as u32).to_le_bytes().to_vec();
+        out.extend_from_slice(bytes);
+        out
+    }
+
+    fn leaf(nibbles: &[u8], value: &[u8]) -> Vec<u8> {
+        let mut node = vec![0];
+        node.extend(with_len(&encode_nibbles(nibbles, true)));
+        node.extend((value.len() as u32).to_le_bytes());
+        node.extend(sha256(&[value]));
+        node.extend(100u64.to_le_bytes());
+        node
+    }
+
+    fn branch(children: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut node = vec![1];
+        let bitmap = children.iter().fold(0u16, |acc, (i, _)| acc | (1 << i));
+        node.extend(bitmap.to_le_bytes());
+        for (_, child) in children {
+            node.extend(sha256(&[child]));
+        }
+        node.extend(300u64.to_le_bytes());
+        node
+    }
+
+    fn extension(nibbles: &[u8], child: &[u8]) -> Vec<u8> {
+        let mut node = vec![3];
+        node.extend(with_len(&encode_nibbles(nibbles, false)));
+        node.extend(sha256(&[child]));
+        node.extend(400u64.to_le_bytes());
+        node
+    }
+
+    /// A trie holding `a = 1` and `b = 2` in app.near's storage:
+    /// extension(key prefix + nibble 6) → branch { 1: leaf("1"), 2: leaf("2") }.
+    struct Fixture {
+        root: CryptoHash,
+        nodes: Vec<Vec<u8>>,
+    }
+
+    fn fixture() -> Fixture {
+        let account_id: AccountId = "app.near".parse().unwrap();
+        let mut path = to_nibbles(&contract_data_key(&account_id, &[]));
+        path.push(6);
+
+        let leaf_a = leaf(&[], b"1");
+        let leaf_b = leaf(&[], b"2");
+        let branch = branch(&[(1, &leaf_a), (2, &leaf_b)]);
+        let root = extension(&path, &branch);
+        Fixture {
+            root: CryptoHash(bs58::encode(sha256(&[&root])).into_string()),
+            nodes: vec![root, branch, leaf_a, leaf_b],
+        }
+    }
+
+    fn response(nodes: &[Vec<u8>], values: &[(&[u8], &[u8])]) -> RpcViewStateResponse {
+        RpcViewStateResponse {
+            block_hash: CryptoHash(bs58::encode([0u8; 32]).into_string()),
+            block_height: 1,
+            proof: nodes.iter().map(|node| STANDARD.encode(node)).collect(),
+            values: values
+                .iter()
+                .map(|(key, value)| StateItem {
+                    key: StoreKey(STANDARD.encode(key)),
+                    value: StoreValue(STANDARD.encode(value)),
+                })
+                .collect(),
+        }
+    }
+
+    fn app() -> AccountId {
+        "app.near".parse().unwrap()
+    }
+
+    #[test]
+    fn verifies_full_state() {
+        let fixture = fixture();
+        let response = response(&fixture.nodes, &[(b"a", b"1"), (b"b", b"2")]);
+        let verified = verify_state_proof(&response, &app(), b"", &fixture.root).unwrap();
+        assert_eq!(
+            verified,
+            BTreeMap::from([
+                (b"a".to_vec(), b"1".to_vec()),
+                (b"b".to_vec(), b"2".to_vec())
+            ])
+        );
+    }
+
+    #[test]
+    fn verifies_prefix_without_unrelated_nodes() {
+        let fixture = fixture();
+        let response = response(&fixture.nodes[..3], &[(b"a", b"1")]);
+        let verified = verify_state_proof(&response, &app(), b"a", &fixture.root).unwrap();
+        assert_eq!(verified.len(), 1);
+    }
+
+    #[test]
+    fn rejects_tampered_and_incomplete_responses() {
+        let fixture = fixture();
+
+        let tampered = response(&fixture.nodes, &[(b"a", b"1"), (b"b", b"3")]);
+        assert_eq!(
+            verify_state_proof(&tampered, &app(), b"", &fixture.root).unwrap_err(),
+            StateProofError::ValueMismatch {
+                key: StoreKey(STANDARD.encode(b"b")),
+            }
+        );
+
+        let omitted = response(&fixture.nodes, &[(b"a", b"1")]);
+        assert_eq!(
+            verify_state_proof(&omitted, &app(), b"", &fixture.root).unwrap_err(),
+            StateProofError::MissingItem {
+                key: StoreKey(STANDARD.encode(b"b")),
+            }
+        );
+
+        let injected = response(&fixture.nodes, &[(b"a", b"1"), (b"b", b"2"), (b"c", b"3")]);
+        assert!(matches!(
+            verify_state_proof(&injected, &app(), b"", &fixture.root),
+            Err(StateProofError::UnprovenItem { .. })
+        ));
+
+        let other_account = "evil.near".parse().unwrap();
+        let response = response(&fixture.nodes, &[]);
+        assert!(
+            verify_state_proof(&response, &other_account, b"", &fixture.root)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rejects_missing_nodes() {
+        let fixture = fixture();
+        let response = response(&fixture.nodes[..2], &[(b"a", b"1"), (b"b", b"2")]);
+        assert!(matches!(
+            verify_state_proof(&response, &app(), b"", &fixture.root),
+            Err(StateProofError::MissingNode { .. })
+        ));
+    }
+}
@@ -0,0 +1,389 @@
+//! Instrumentation of RPC calls, enabled by the `tracing` and `metrics`
+//! features.
+//!
+//! With `tracing`, each call runs in a `jsonrpc` span whose fields follow the
+//! OpenTelemetry semantic conventions for JSON-RPC client spans
+//! (`rpc.system`, `rpc.method`, `rpc.jsonrpc.*`, `server.address`,
+//! `server.port`, `error.type`), plus the HTTP status, body size, duration and
+//! NEAR's error cause name. The full URL is not recorded, not even in HTTP
+//! error messages, since RPC providers often put API keys in its path or
+//! query. `tracing-opentelemetry` picks up `otel.name`, `otel.kind` and
+//! `otel.status_code` from the span.
+//!
+//! With `metrics`, each call is recorded through the `metrics` facade, labelled
+//! by `method`:
+//!
+//! | Metric | Kind | Extra labels |
+//! |---|---|---|
+//! | `near_rpc_requests_total` | counter | |
+//! | `near_rpc_failures_total` | counter | `error_type`, `cause` |
+//! | `near_rpc_retries_total` | counter | |
+//! | `near_rpc_cache_hits_total` | counter | |
+//! | `near_rpc_request_duration_seconds` | histogram | |
+//! | `near_rpc_request_bytes_total` | counter | |
+//! | `near_rpc_response_bytes_total` | counter | |
+//!
+//! Retries are HTTP requests beyond the first made for one call, i.e. by
+//! middleware that runs [`Next`](crate::middleware::Next) more than once.
+//! Without either feature every function here is a no-op.
+
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use crate::client::{Error, Result};
+
+/// The instrumentation of one call.
+#[cfg_attr(not(any(feature = "tracing", feature = "metrics")), allow(dead_code))]
+pub(crate) struct CallSpan {
+    #[cfg(feature = "metrics")]
+    method: &'static str,
+    started: Instant,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl CallSpan {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(method: &'static str, url: &str) -> Self {
+        #[cfg(feature = "metrics")]
+        metrics_impl::describe();
+
+        Self {
+            #[cfg(feature = "metrics")]
+            method,
+            started: Instant::now(),
+            #[cfg(feature = "tracing")]
+            span: tracing_impl::span(method, url),
+        }
+    }
+
+    /// Runs `call` inside the span and records its outcome.
+    pub(crate) async fn run<R>(self, call: impl Future<Output = Result<R>>) -> Result<R> {
+        #[cfg(feature = "tracing")]
+        let result = {
+            use tracing::Instrument;
+            call.instrument(self.span.clone()).await
+        };
+        #[cfg(not(feature = "tracing"))]
+        let result = call.await;
+
+        #[cfg(feature = "tracing")]
+        tracing_impl::finish(&self.span, self.started.elapsed(), result.as_ref().err());
+        #[cfg(feature = "metrics")]
+        metrics_impl::finish(self.method, self.started.elapsed(), result.as_ref().err());
+        result
+    }
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record_request_id(id: u64) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("rpc.jsonrpc.request_id", id);
+}
+
+/// Records one HTTP exchange of a call.
+#[cfg_attr(
+    not(any(feature = "tracing", feature = "metrics")),
+    allow(unused_variables)
+)]
+pub(crate) fn record_response(
+    method: &str,
+    status: u16,
+    request_size: usize,
+    response_size: usize,
+) {
+    #[cfg(not(feature = "tracing"))]
+    let _ = status;
+    #[cfg(not(feature = "metrics"))]
+    let _ = (method, request_size);
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        span.record("http.response.status_code", status);
+        span.record("http.response.body.size", response_size as u64);
+    }
+    #[cfg(feature = "metrics")]
+    {
+        let method = method.to_string();
+        metrics::counter!(metrics_impl::REQUEST_BYTES, "method" => method.clone())
+            .increment(request_size as u64);
+        metrics::counter!(metrics_impl::RESPONSE_BYTES, "method" => method)
+            .increment(response_size as u64);
+    }
+}
+
+/// Records how many HTTP requests the middleware chain made for one call.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_attempts(method: &'static str, attempts: u32) {
+    #[cfg(feature = "metrics")]
+    if attempts > 1 {
+        metrics::counter!(metrics_impl::RETRIES, "method" => method)
+            .increment(u64::from(attempts - 1));
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_cache_hit(method: &'static str) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("near.cache_hit", true);
+    #[cfg(feature = "metrics")]
+    metrics::counter!(metrics_impl::CACHE_HITS, "method" => method).increment(1);
+}
+
+/// How an error shows up on a span or in metric labels.
+#[cfg_attr(not(any(feature = "tracing", feature = "metrics")), allow(dead_code))]
+#[derive(Debug, PartialEq, Eq)]
+struct ErrorClass<'a> {
+    /// The JSON-RPC error name (`HANDLER_ERROR`, `REQUEST_VALIDATION_ERROR`,
+    /// `INTERNAL_ERROR`), or the kind of client-side failure.
+    error_type: &'a str,
+    code: Option<i64>,
+    cause: Option<&'a str>,
+}
+
+#[cfg_attr(not(any(feature = "tracing", feature = "metrics")), allow(dead_code))]
+fn classify(err: &Error) -> ErrorClass<'_> {
+    let client_side = |error_type| ErrorClass {
+        error_type,
+        code: None,
+        cause: None,
+    };
+    match err {
+        Error::Rpc(err) => ErrorClass {
+            error_type: err.name.as_deref().unwrap_or("RPC_ERROR"),
+            code: Some(err.code),
+            cause: err.cause_name(),
+        },
+        Error::LegacyQueryResult(_) => client_side("HANDLER_ERROR"),
+        Error::Http(err) if err.is_timeout() => client_side("timeout"),
+        Error::Http(_) => client_side("http"),
+        Error::Json(_) => client_side("json"),
+        _ => client_side("client"),
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod tracing_impl {
+    use std::time::Duration;
+
+    use tracing::field::Empty;
+
+    use super::classify;
+    use crate::client::Error;
+
+    pub(super) fn span(method: &str, url: &str) -> tracing::Span {
+        let url = reqwest::Url::parse(url).ok();
+        let address = url
+            .as_ref()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let port = url.as_ref().and_then(reqwest::Url::port_or_known_default);
+        tracing::info_span!(
+            "jsonrpc",
+            otel.name = method,
+            otel.kind = "client",
+            otel.status_code = Empty,
+            rpc.system = "jsonrpc",
+            rpc.method = method,
+            rpc.jsonrpc.version = "2.0",
+            rpc.jsonrpc.request_id = Empty,
+            rpc.jsonrpc.error_code = Empty,
+            rpc.jsonrpc.error_message = Empty,
+            server.address = address,
+            server.port = port,
+            http.response.status_code = Empty,
+            http.response.body.size = Empty,
+            error.type = Empty,
+            near.error.cause = Empty,
+            near.cache_hit = Empty,
+            duration_ms = Empty,
+        )
+    }
+
+    pub(super) fn finish(span: &tracing::Span, elapsed: Duration, err: Option<&Error>) {
+        span.record("duration_ms", elapsed.as_millis() as u64);
+        let Some(err) = err else {
+            span.record("otel.status_code", "OK");
+            return;
+        };
+        let class = classify(err);
+        span.record("otel.status_code", "ERROR");
+        span.record("error.type", class.error_type);
+        if let Some(code) = class.code {
+            span.record("rpc.jsonrpc.error_code", code);
+        }
+        if let Some(cause) = class.cause {
+            span.record("near.error.cause", cause);
+        }
+        span.record("rpc.jsonrpc.error_message", error_message(err));
+    }
+
+    /// `err`'s message, without the request URL that reqwest appends to
+    /// HTTP errors.
+    pub(super) fn error_message(err: &Error) -> String {
+        let message = err.to_string();
+        match err {
+            Error::Http(http) => match http.url() {
+                Some(url) => message.replace(&format!(" for url ({url})"), ""),
+                None => message,
+            },
+            _ => message,
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+mod metrics_impl {
+    use std::sync::Once;
+    use std::time::Duration;
+
+    use metrics::Unit;
+
+    use super::classify;
+    use crate::client::Error;
+
+    pub(super) const REQUESTS: &str = "near_rpc_requests_total";
+    pub(super) const FAILURES: &str = "near_rpc_failures_total";
+    pub(super) const RETRIES: &str = "near_rpc_retries_total";
+    pub(super) const CACHE_HITS: &str = "near_rpc_cache_hits_total";
+    pub(super) const DURATION: &str = "near_rpc_request_duration_seconds";
+    pub(super) const REQUEST_BYTES: &str = "near_rpc_request_bytes_total";
+    pub(super) const RESPONSE_BYTES: &str = "near_rpc_response_bytes_total";
+
+    pub(super) fn describe() {
+        static DESCRIBED: Once = Once::new();
+        DESCRIBED.call_once(|| {
+            metrics::describe_counter!(REQUESTS, "NEAR RPC calls made");
+            metrics::describe_counter!(FAILURES, "NEAR RPC calls that returned an error");
+            metrics::describe_counter!(RETRIES, "HTTP requests beyond the first for a call");
+            metrics::describe_counter!(CACHE_HITS, "NEAR RPC calls served from the cache");
+            metrics::describe_histogram!(DURATION, Unit::Seconds, "NEAR RPC call latency");
+            metrics::describe_counter!(REQUEST_BYTES, Unit::Bytes, "NEAR RPC request bytes sent");
+            metrics::describe_counter!(
+                RESPONSE_BYTES,
+                Unit::Bytes,
+                "NEAR RPC response bytes received"
+            );
+        });
+    }
+
+    pub(super) fn finish(method: &'static str, elapsed: Duration, err: Option<&Error>) {
+        metrics::counter!(REQUESTS, "method" => method).increment(1);
+        metrics::histogram!(DURATION, "method" => method).record(elapsed.as_secs_f64());
+        if let Some(err) = err {
+            let class = classify(err);
+            metrics::counter!(
+                FAILURES,
+                "method" => method,
+                "error_type" => class.error_type.to_string(),
+                "cause" => class.cause.unwrap_or("").to_string(),
+            )
+            .increment(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_rpc_errors_by_name_and_cause() {
+        let rpc: crate::errors::RpcError = serde_json::from_value(serde_json::json!({
+            "code": -32000,
+            "message": "Server error",
+            "name": "HANDLER_ERROR",
+            "cause": { "name": "UNKNOWN_BLOCK", "info": {} }
+        }))
+        .unwrap();
+        let err = Error::from(rpc);
+        assert_eq!(
+            classify(&err),
+            ErrorClass {
+                error_type: "HANDLER_ERROR",
+                code: Some(-32000),
+                cause: Some("UNKNOWN_BLOCK"),
+            }
+        );
+
+        let json = Error::Json(serde_json::from_str::<u8>("x").unwrap_err());
+        assert_eq!(classify(&json).error_type, "json");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn leaves_the_url_out_of_http_error_messages() {
+        let url = "http://127.0.0.1:9/v1/secret-api-key?apikey=secret";
+        let err = Error::from(reqwest::get(url).await.unwrap_err());
+        assert!(err.to_string().contains("secret"));
+
+        let message = tracing_impl::error_message(&err);
+        assert!(!message.contains("secret"), "{message}");
+        assert!(message.starts_with("HTTP error: "), "{message}");
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn records_call_metrics() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+        use serde_json::json;
+
+        use crate::NearRpcClient;
+        use crate::middleware::{HandleFuture, Middleware, Next, RpcCall};
+
+        /// Sends twice, then answers with an RPC error.
+        struct RetryOnce;
+
+        impl Middleware for RetryOnce {
+            fn handle<'a>(&'a self, call: RpcCall, next: Next<'a>) -> HandleFuture<'a> {
+                Box::pin(async move {
+                    let _ = next.run(call.clone()).await;
+                    let _ = next.run(call.clone()).await;
+                    Ok(json!({
+                        "jsonrpc": "2.0",
+                        "id": call.id,
+                        "error": {
+                            "code": -32000,
+                            "message": "Server error",
+                            "name": "HANDLER_ERROR",
+                            "cause": { "name": "UNKNOWN_BLOCK", "info": {} }
+                        }
+                    }))
+                })
+            }
+        }
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let client = NearRpcClient::new("http://127.0.0.1:9").with_middleware(RetryOnce);
+            let result = runtime.block_on(client.health());
+            assert!(matches!(result, Err(Error::Rpc(_))));
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let counter = |name: &str| {
+            snapshot
+                .iter()
+                .find(|(key, ..)| key.key().name() == name)
+                .map(|(key, _, _, value)| match value {
+                    DebugValue::Counter(count) => (key.key().labels().collect::<Vec<_>>(), *count),
+                    _ => panic!("{name} is not a counter"),
+                })
+                .unwrap()
+        };
+        let (labels, failures) = counter(metrics_impl::FAILURES);
+        assert!(labels.iter().any(|label| label.value() == "UNKNOWN_BLOCK"));
+        assert_eq!(failures, 1);
+        assert_eq!(counter(metrics_impl::REQUESTS).1, 1);
+        assert_eq!(counter(metrics_impl::RETRIES).1, 1);
+    }
+}
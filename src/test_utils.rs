@@ -0,0 +1,245 @@
+//! JSON factories and sample data used by unit tests.
+
+#[cfg(feature = "client")]
+use serde_json::Value;
+use serde_json::json;
+
+#[cfg(feature = "client")]
+pub(crate) const HASH: &str = "11111111111111111111111111111111";
+#[cfg(feature = "client")]
+pub(crate) const SIGNATURE: &str =
+    "ed25519:1111111111111111111111111111111111111111111111111111111111111111";
+
+#[cfg(feature = "client")]
+/// A chunk header for `shard_id` that was last included at `height_included`.
+pub(crate) fn chunk_header(shard_id: u64, height_included: u64) -> Value {
+    json!({
+        "balance_burnt": "0",
+        "chunk_hash": HASH,
+        "encoded_length": 0,
+        "encoded_merkle_root": HASH,
+        "gas_limit": 0,
+        "gas_used": 0,
+        "height_created": height_included,
+        "height_included": height_included,
+        "outcome_root": HASH,
+        "outgoing_receipts_root": HASH,
+        "prev_block_hash": HASH,
+        "prev_state_root": HASH,
+        "shard_id": shard_id,
+        "signature": SIGNATURE,
+        "tx_root": HASH,
+        "validator_proposals": []
+    })
+}
+
+#[cfg(feature = "client")]
+/// A block header at `height` with one chunk mask entry per shard.
+pub(crate) fn block_header(height: u64, chunk_mask: &[bool]) -> Value {
+    json!({
+        "approvals": [],
+        "block_merkle_root": HASH,
+        "challenges_result": [],
+        "challenges_root": HASH,
+        "chunk_headers_root": HASH,
+        "chunk_mask": chunk_mask,
+        "chunk_receipts_root": HASH,
+        "chunk_tx_root": HASH,
+        "chunks_included": chunk_mask.iter().filter(|included| **included).count(),
+        "epoch_id": HASH,
+        "gas_price": "100",
+        "hash": "4reLvkAWfqk5fsqio1KLudk46cqRz9erQdaHkWZKMJDZ",
+        "height": height,
+        "last_ds_final_block": HASH,
+        "last_final_block": HASH,
+        "latest_protocol_version": 80,
+        "next_bp_hash": HASH,
+        "next_epoch_id": HASH,
+        "outcome_root": HASH,
+        "prev_hash": HASH,
+        "prev_state_root": HASH,
+        "random_value": HASH,
+        "signature": SIGNATURE,
+        "timestamp": 0,
+        "timestamp_nanosec": "0",
+        "total_supply": "0",
+        "validator_proposals": []
+    })
+}
+
+/// A block response at `height` with one new chunk on shard 0.
+#[cfg(feature = "client")]
+pub(crate) fn block(height: u64, hash: &str, prev_hash: &str) -> Value {
+    let mut header = block_header(height, &[true]);
+    header["hash"] = json!(hash);
+    header["prev_hash"] = json!(prev_hash);
+    json!({
+        "author": "node.near",
+        "chunks": [chunk_header(0, height)],
+        "header": header,
+    })
+}
+
+/// A chunk response with the given transactions and receipts.
+#[cfg(all(feature = "stream", feature = "testing"))]
+pub(crate) fn chunk(header: Value, transactions: Value, receipts: Value) -> Value {
+    json!({
+        "author": "node.near",
+        "header": header,
+        "transactions": transactions,
+        "receipts": receipts,
+    })
+}
+
+/// A chain of blocks served by a [`MockRpc`](crate::testing::MockRpc).
+///
+/// `block` calls are answered from the canonical chain: by finality with the
+/// highest block, by height or hash with that block or `UNKNOWN_BLOCK`.
+/// Blocks rewound away are still found by hash, as a node keeps them until
+/// they are garbage collected.
+/// `chunk` calls are answered with an empty chunk.
+#[cfg(all(feature = "stream", feature = "testing"))]
+#[derive(Clone, Default)]
+pub(crate) struct MockChain {
+    blocks: std::sync::Arc<std::sync::Mutex<Vec<Value>>>,
+    abandoned: std::sync::Arc<std::sync::Mutex<Vec<Value>>>,
+}
+
+#[cfg(all(feature = "stream", feature = "testing"))]
+impl MockChain {
+    pub(crate) fn serve(&self, mock: &crate::testing::MockRpc) {
+        use crate::testing::Reply;
+
+        let blocks = self.blocks.clone();
+        let abandoned = self.abandoned.clone();
+        mock.on_call("block", move |params| {
+            let blocks = blocks.lock().unwrap();
+            let block_id = &params["block_id"];
+            let found = match block_id {
+                Value::Null => blocks.last().cloned(),
+                Value::Number(_) => blocks
+                    .iter()
+                    .find(|block| block["header"]["height"] == *block_id)
+                    .cloned(),
+                _ => blocks
+                    .iter()
+                    .chain(abandoned.lock().unwrap().iter())
+                    .find(|block| block["header"]["hash"] == *block_id)
+                    .cloned(),
+            };
+            match found {
+                Some(block) => Reply::Result(block),
+                None => Reply::handler_error("UNKNOWN_BLOCK", json!({ "block_info": block_id })),
+            }
+        })
+        .on_call("chunk", |_| {
+            Reply::Result(chunk(chunk_header(0, 0), json!([]), json!([])))
+        });
+    }
+
+    /// Appends `block` to the canonical chain.
+    pub(crate) fn push(&self, block: Value) {
+        self.blocks.lock().unwrap().push(block);
+    }
+
+    /// Drops every block above `height` from the canonical chain.
+    pub(crate) fn rewind(&self, height: u64) {
+        let mut blocks = self.blocks.lock().unwrap();
+        let (kept, abandoned) = blocks
+            .drain(..)
+            .partition(|block| block["header"]["height"].as_u64().unwrap() <= height);
+        *blocks = kept;
+        self.abandoned.lock().unwrap().extend::<Vec<_>>(abandoned);
+    }
+}
+
+/// The light client view of block 879765 of a localnet run by nearcore, from
+/// the sample data of near-lake-framework. Its hash is
+/// `95K8Je1iAVqieVU8ZuGgSdbvYs8T9rL6ER1XnRekMGbj`; `inner_rest_hash` was
+/// computed with near-primitives from the full header.
+#[cfg(feature = "light-client")]
+pub(crate) fn sample_block() -> crate::types::LightClientBlockLiteView {
+    serde_json::from_value(json!({
+        "inner_lite": {
+            "block_merkle_root": "CqRoDd8BR4su7Z8vSfvg45HrugZnwbMbnXHRTWYQkWfZ",
+            "epoch_id": "Hp4sw9ZGSceYadnvh7NpYJVVK7rcdir48jfrsxvwKQu9",
+            "height": 879765,
+            "next_bp_hash": "EtsYQonaJ7n5nRt32XJC5dBxxBxh7a9UVApykmmt8fCQ",
+            "next_epoch_id": "4h5mecoLYVFeZxAMAX3Mq3GQfEnuvSAPPo9kEpr4rGUL",
+            "outcome_root": "7tkzFg8RHBmMw1ncRJZCCZAizgq4rwCftTKYLce8RU8t",
+            "prev_state_root": "6zDM1UGLsZ7HnyUofDrTF73gv5vk2N614ViDkXBkq4ej",
+            "timestamp": 1676913656724153000u64,
+            "timestamp_nanosec": "1676913656724153000"
+        },
+        "inner_rest_hash": "BBJjERxZX6nZMzcWJ1j62imHdxGhctiDC3XP5U9hhkfT",
+        "prev_block_hash": "9Da84RTsubZPcLxzK1K6JkCnDnMn4DxaSRzJPtnYJXUM"
+    }))
+    .unwrap()
+}
+
+/// The two execution outcomes of [`sample_block`]'s chunk, a transaction's
+/// and its receipt's, each with the other as its outcome proof sibling.
+#[cfg(feature = "light-client")]
+pub(crate) fn sample_outcomes() -> [crate::types::ExecutionOutcomeWithIdView; 2] {
+    [
+        serde_json::from_value(json!({
+            "proof": [
+                {
+                    "hash": "7kPZTTVYJHvUg4g3S7SFErkKs18Ex1kN4rESnZwtJb2U",
+                    "direction": "Right"
+                }
+            ],
+            "block_hash": "95K8Je1iAVqieVU8ZuGgSdbvYs8T9rL6ER1XnRekMGbj",
+            "id": "EZnJpyJDnkwnadB1V8PqjVMx7oe2zLhUMtJ8v6EUh1NQ",
+            "outcome": {
+                "logs": [],
+                "receipt_ids": [
+                    "AQDQ9G4QpK7x2inV3GieVEbqeoCGF9nmvrViQ2UgEXDQ"
+                ],
+                "gas_burnt": 409824625000u64,
+                "tokens_burnt": "40982462500000000000",
+                "executor_id": "test.near",
+                "status": {
+                    "SuccessReceiptId": "AQDQ9G4QpK7x2inV3GieVEbqeoCGF9nmvrViQ2UgEXDQ"
+                },
+                "metadata": {
+                    "version": 1,
+                    "gas_profile": null
+                }
+            }
+        }))
+        .unwrap(),
+        serde_json::from_value(json!({
+            "proof": [
+                {
+                    "hash": "6vBgNYcwx6pcESfrw5YRBRamatBH8red3GEt3s3ntefm",
+                    "direction": "Left"
+                }
+            ],
+            "block_hash": "95K8Je1iAVqieVU8ZuGgSdbvYs8T9rL6ER1XnRekMGbj",
+            "id": "AQDQ9G4QpK7x2inV3GieVEbqeoCGF9nmvrViQ2UgEXDQ",
+            "outcome": {
+                "logs": [],
+                "receipt_ids": [
+                    "5rc8UEhD4hmNQ3pJJM5Xc3VHeLXpCQqkA3ep8ag4aaDA"
+                ],
+                "gas_burnt": 308059500000u64,
+                "tokens_burnt": "30805950000000000000",
+                "executor_id": "test.near",
+                "status": {
+                    "Failure": {
+                        "ActionError": {
+                            "index": 0,
+                            "kind": "DelegateActionExpired"
+                        }
+                    }
+                },
+                "metadata": {
+                    "version": 3,
+                    "gas_profile": []
+                }
+            }
+        }))
+        .unwrap(),
+    ]
+}
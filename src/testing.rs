@@ -0,0 +1,348 @@
+//! An in-process mock of a NEAR RPC node, for testing code that uses
+//! [`NearRpcClient`].
+//!
+//! [`MockRpc`] stands in for the HTTP transport of the clients it creates:
+//! it answers every call from replies registered per JSON-RPC method, after
+//! any [`Middleware`](crate::middleware::Middleware) added to the client. It
+//! records the calls it receives so tests can assert on their params and
+//! headers, and can inject node errors (including nearcore's legacy query
+//! error shape), latency and calls that never complete.
+//!
+//! Replies are registered under the JSON-RPC method name the client sends,
+//! e.g. `EXPERIMENTAL_view_account` for [`NearRpcClient::view_account`]. A
+//! method with no reply is answered with the node's `METHOD_NOT_FOUND` error.
+//!
+//! # Example
+//!
+//! ```
+//! use near_openrpc_client::testing::{MockRpc, Reply};
+//! use near_openrpc_client::types::*;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mock = MockRpc::new();
+//!     mock.on(
+//!         "EXPERIMENTAL_view_account",
+//!         Reply::handler_error("UNKNOWN_ACCOUNT", serde_json::json!({
+//!             "requested_account_id": "nobody.near",
+//!             "block_height": 1,
+//!             "block_hash": "11111111111111111111111111111111",
+//!         })),
+//!     );
+//!
+//!     let client = mock.client();
+//!     let err = client
+//!         .view_account(RpcViewAccountRequest::FinalityAccountId {
+//!             account_id: "nobody.near".parse().unwrap(),
+//!             finality: Finality::Final,
+//!         })
+//!         .await
+//!         .unwrap_err();
+//!     assert!(err.to_string().contains("Server error"));
+//!     assert_eq!(mock.params("EXPERIMENTAL_view_account")[0]["account_id"], "nobody.near");
+//! }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::client::{NearRpcClient, Result};
+use crate::errors::{LegacyQueryError, RpcError, RpcErrorCause};
+use crate::middleware::RpcCall;
+
+/// How the mock answers one call.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    /// A successful response with this `result`.
+    Result(Value),
+    /// A JSON-RPC error response.
+    Error(RpcError),
+    /// nearcore's legacy query error, sent as a successful `result`.
+    LegacyQueryError(LegacyQueryError),
+    /// `reply`, after waiting `delay`.
+    Delayed(Duration, Box<Reply>),
+    /// Never answers, as a node that stopped responding; pair with a
+    /// timeout in the code under test.
+    Hang,
+}
+
+impl Reply {
+    /// A successful response with `result` serialized as JSON.
+    pub fn result(result: impl Serialize) -> Self {
+        Self::Result(serde_json::to_value(result).expect("mock result must serialize"))
+    }
+
+    /// A `HANDLER_ERROR` with the given cause, e.g. `UNKNOWN_ACCOUNT`.
+    pub fn handler_error(cause: &str, info: Value) -> Self {
+        Self::error("HANDLER_ERROR", cause, info)
+    }
+
+    /// A JSON-RPC error with the given `name` (`HANDLER_ERROR`,
+    /// `REQUEST_VALIDATION_ERROR` or `INTERNAL_ERROR`) and cause.
+    pub fn error(name: &str, cause: &str, info: Value) -> Self {
+        let code = match name {
+            "REQUEST_VALIDATION_ERROR" => -32700,
+            _ => -32000,
+        };
+        Self::Error(RpcError {
+            code,
+            message: "Server error".to_string(),
+            data: None,
+            name: Some(name.to_string()),
+            cause: Some(RpcErrorCause {
+                name: cause.to_string(),
+                info: Some(info),
+            }),
+        })
+    }
+
+    /// nearcore's legacy query error with this message, as returned for a
+    /// missing access key or a failed view call.
+    pub fn legacy_query_error(error: impl Into<String>) -> Self {
+        Self::LegacyQueryError(LegacyQueryError {
+            error: error.into(),
+            logs: Vec::new(),
+            block_height: None,
+            block_hash: None,
+        })
+    }
+
+    /// This reply, sent after `delay`.
+    pub fn after(self, delay: Duration) -> Self {
+        Self::Delayed(delay, Box::new(self))
+    }
+}
+
+type Handler = Arc<dyn Fn(&Value) -> Reply + Send + Sync>;
+
+#[derive(Default)]
+struct State {
+    /// Replies used once each, before the method's handler.
+    queued: HashMap<String, VecDeque<Reply>>,
+    handlers: HashMap<String, Handler>,
+    calls: Vec<RpcCall>,
+    latency: Duration,
+}
+
+/// A mock NEAR RPC node. Clones share the same replies and recorded calls.
+#[derive(Clone, Default)]
+pub struct MockRpc {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A client whose calls are all answered by this mock. Middleware added
+    /// to it runs before the mock, as it would before the HTTP transport.
+    pub fn client(&self) -> NearRpcClient {
+        NearRpcClient::new("http://mock.invalid").with_mock(self.clone())
+    }
+
+    /// Answers every call to `method` with `reply`.
+    pub fn on(&self, method: &str, reply: Reply) -> &Self {
+        self.on_call(method, move |_| reply.clone())
+    }
+
+    /// Answers calls to `method` with the reply `handler` builds from their
+    /// params.
+    pub fn on_call(
+        &self,
+        method: &str,
+        handler: impl Fn(&Value) -> Reply + Send + Sync + 'static,
+    ) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .handlers
+            .insert(method.to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Answers the next call to `method` with `reply`. Queued replies are
+    /// used in order, before any reply registered with [`on`](Self::on).
+    pub fn once(&self, method: &str, reply: Reply) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .queued
+            .entry(method.to_string())
+            .or_default()
+            .push_back(reply);
+        self
+    }
+
+    /// Delays every reply by `latency`.
+    pub fn with_latency(&self, latency: Duration) -> &Self {
+        self.state.lock().unwrap().latency = latency;
+        self
+    }
+
+    /// Every call received so far, in order.
+    pub fn calls(&self) -> Vec<RpcCall> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// The params of every call to `method` received so far, in order.
+    pub fn params(&self, method: &str) -> Vec<Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|call| call.method == method)
+            .map(|call| call.params.clone())
+            .collect()
+    }
+
+    fn reply(&self, call: &RpcCall) -> (Reply, Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(call.clone());
+        let latency = state.latency;
+        if let Some(reply) = state
+            .queued
+            .get_mut(&call.method)
+            .and_then(VecDeque::pop_front)
+        {
+            return (reply, latency);
+        }
+        let Some(handler) = state.handlers.get(&call.method).cloned() else {
+            return (method_not_found(&call.method), latency);
+        };
+        drop(state);
+        (handler(&call.params), latency)
+    }
+
+    /// Answers `call` in place of the HTTP transport.
+    pub(crate) async fn answer(&self, call: RpcCall) -> Result<Value> {
+        let (mut reply, latency) = self.reply(&call);
+        tokio::time::sleep(latency).await;
+        let (field, value) = loop {
+            match reply {
+                Reply::Result(result) => break ("result", result),
+                Reply::Error(error) => break ("error", serde_json::to_value(error)?),
+                Reply::LegacyQueryError(error) => {
+                    break ("result", serde_json::to_value(error)?);
+                }
+                Reply::Delayed(delay, inner) => {
+                    tokio::time::sleep(delay).await;
+                    reply = *inner;
+                }
+                Reply::Hang => futures::future::pending::<()>().await,
+            }
+        };
+        let mut response = json!({ "jsonrpc": "2.0", "id": call.id });
+        response[field] = value;
+        Ok(response)
+    }
+}
+
+fn method_not_found(method: &str) -> Reply {
+    Reply::Error(RpcError {
+        code: -32601,
+        message: "Method not found".to_string(),
+        data: Some(json!(method)),
+        name: Some("REQUEST_VALIDATION_ERROR".to_string()),
+        cause: Some(RpcErrorCause {
+            name: "METHOD_NOT_FOUND".to_string(),
+            info: Some(json!({ "method_name": method })),
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Error;
+    use crate::middleware::{HandleFuture, Middleware, Next};
+    use crate::types::*;
+
+    fn access_key_request() -> RpcViewAccessKeyRequest {
+        serde_json::from_value(json!({
+            "account_id": "alice.near",
+            "public_key": "ed25519:11111111111111111111111111111111",
+            "finality": "final"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn answers_from_registered_replies() {
+        let mock = MockRpc::new();
+        mock.on("health", Reply::result(()))
+            .once(
+                "EXPERIMENTAL_view_access_key",
+                Reply::legacy_query_error("access key does not exist while viewing"),
+            )
+            .on(
+                "EXPERIMENTAL_view_access_key",
+                Reply::handler_error("UNKNOWN_BLOCK", json!({})),
+            );
+        let client = mock.client();
+
+        client.health().await.unwrap();
+        let Err(Error::LegacyQueryResult(legacy)) =
+            client.view_access_key(access_key_request()).await
+        else {
+            panic!("expected the legacy error first");
+        };
+        assert!(legacy.error.contains("does not exist"));
+        let Err(Error::Rpc(err)) = client.view_access_key(access_key_request()).await else {
+            panic!("expected a handler error");
+        };
+        assert_eq!(err.cause_name(), Some("UNKNOWN_BLOCK"));
+        let Err(Error::Rpc(err)) = client.status().await else {
+            panic!("expected METHOD_NOT_FOUND");
+        };
+        assert_eq!(err.cause_name(), Some("METHOD_NOT_FOUND"));
+
+        let params = mock.params("EXPERIMENTAL_view_access_key");
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0]["account_id"], "alice.near");
+        assert_eq!(mock.calls().len(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn simulates_latency_and_hangs() {
+        let mock = MockRpc::new();
+        mock.with_latency(Duration::from_millis(50))
+            .on(
+                "health",
+                Reply::result(()).after(Duration::from_millis(100)),
+            )
+            .on("status", Reply::Hang);
+        let client = mock.client();
+
+        let started = tokio::time::Instant::now();
+        client.health().await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_millis(150));
+
+        let status = tokio::time::timeout(Duration::from_secs(5), client.status()).await;
+        assert!(status.is_err());
+    }
+
+    struct Tag;
+
+    impl Middleware for Tag {
+        fn handle<'a>(&'a self, mut call: RpcCall, next: Next<'a>) -> HandleFuture<'a> {
+            call.headers.insert("x-tag", "tagged".parse().unwrap());
+            Box::pin(next.run(call))
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_after_client_middleware() {
+        let mock = MockRpc::new();
+        mock.on("health", Reply::result(()));
+        let client = mock.client().with_middleware(Tag);
+
+        client.health().await.unwrap();
+        assert_eq!(mock.calls()[0].headers["x-tag"], "tagged");
+    }
+}
@@ -0,0 +1,458 @@
+//! Building and signing NEAR transactions.
+//!
+//! The RPC types describe transactions as JSON views, but nodes only accept
+//! transactions as base64-encoded Borsh. [`Transaction`] serializes the
+//! generated action types into nearcore's Borsh layout (`TransactionV0`) and
+//! signs the SHA-256 hash of that encoding with an [`InMemorySigner`].
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sha2::{Digest, Sha256};
+
+use crate::keystore::InMemorySigner;
+use crate::types::*;
+
+/// Errors returned while encoding or signing a transaction.
+#[derive(Debug, thiserror::Error)]
+pub enum TransactionError {
+    #[error("invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("invalid crypto hash: {0}")]
+    InvalidHash(String),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("invalid base64 in {field}: {source}")]
+    InvalidBase64 {
+        field: &'static str,
+        #[source]
+        source: base64::DecodeError,
+    },
+    #[error("action {0} cannot be signed by this client yet")]
+    UnsupportedAction(&'static str),
+}
+
+/// An unsigned transaction.
+///
+/// # Example
+///
+/// ```no_run
+/// use near_openrpc_client::{NearRpcClient, keystore::Keystore, types::*};
+///
+/// #[tokio::main]
+/// async fn main() -> near_openrpc_client::client::Result<()> {
+///     let client = NearRpcClient::testnet();
+///     let outcome = client
+///         .send_transaction_as(
+///             &Keystore::from_home()?,
+///             &"alice.testnet".parse().unwrap(),
+///             "bob.testnet".parse().unwrap(),
+///             vec![NonDelegateAction::Transfer(TransferAction {
+///                 deposit: NearToken("1000000000000000000000000".to_string()),
+///             })],
+///         )
+///         .await?;
+///     println!("{outcome:?}");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub signer_id: AccountId,
+    pub public_key: PublicKey,
+    pub nonce: u64,
+    pub receiver_id: AccountId,
+    pub block_hash: CryptoHash,
+    pub actions: Vec<NonDelegateAction>,
+}
+
+impl Transaction {
+    /// Returns the Borsh encoding of this transaction.
+    pub fn to_borsh(&self) -> Result<Vec<u8>, TransactionError> {
+        let mut buf = Vec::new();
+        write_string(&mut buf, &self.signer_id);
+        write_public_key(&mut buf, &self.public_key)?;
+        buf.extend_from_slice(&self.nonce.to_le_bytes());
+        write_string(&mut buf, &self.receiver_id);
+        write_hash(&mut buf, &self.block_hash)?;
+        write_len(&mut buf, self.actions.len());
+        for action in &self.actions {
+            write_action(&mut buf, action)?;
+        }
+        Ok(buf)
+    }
+
+    /// Returns the transaction hash (SHA-256 of the Borsh encoding).
+    pub fn hash(&self) -> Result<CryptoHash, TransactionError> {
+        let digest = Sha256::digest(self.to_borsh()?);
+        Ok(CryptoHash(bs58::encode(digest).into_string()))
+    }
+
+    /// Signs the transaction and returns it in the form accepted by `send_tx`.
+    pub fn sign(&self, signer: &InMemorySigner) -> Result<SignedTransaction, TransactionError> {
+        let mut buf = self.to_borsh()?;
+        let signature = signer.sign(&Sha256::digest(&buf));
+        write_signature(&mut buf, &signature)?;
+        Ok(SignedTransaction(STANDARD.encode(buf)))
+    }
+}
+
+// ── Borsh encoding ───────────────────────────────────────────────────────
+
+fn write_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_len(buf, bytes.len());
+    buf.extend_from_slice(bytes);
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    write_bytes(buf, s.as_bytes());
+}
+
+fn write_u128(buf: &mut Vec<u8>, amount: &NearToken) -> Result<(), TransactionError> {
+    let value: u128 = amount
+        .parse()
+        .map_err(|_| TransactionError::InvalidAmount(amount.0.clone()))?;
+    buf.extend_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+fn write_base64(
+    buf: &mut Vec<u8>,
+    field: &'static str,
+    encoded: &str,
+) -> Result<(), TransactionError> {
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|source| TransactionError::InvalidBase64 { field, source })?;
+    write_bytes(buf, &bytes);
+    Ok(())
+}
+
+/// Decodes a `<curve>:<base58>` key or signature into its Borsh form: a curve
+/// tag byte (0 = ed25519, 1 = secp256k1) followed by the raw bytes.
+fn decode_curve_prefixed(s: &str, ed25519_len: usize, secp256k1_len: usize) -> Option<Vec<u8>> {
+    let (tag, len, encoded) = if let Some(rest) = s.strip_prefix("ed25519:") {
+        (0u8, ed25519_len, rest)
+    } else if let Some(rest) = s.strip_prefix("secp256k1:") {
+        (1u8, secp256k1_len, rest)
+    } else {
+        (0u8, ed25519_len, s)
+    };
+    let bytes = bs58::decode(encoded).into_vec().ok()?;
+    if bytes.len() != len {
+        return None;
+    }
+    let mut out = Vec::with_capacity(len + 1);
+    out.push(tag);
+    out.extend_from_slice(&bytes);
+    Some(out)
+}
+
+fn write_public_key(buf: &mut Vec<u8>, key: &PublicKey) -> Result<(), TransactionError> {
+    let bytes = decode_curve_prefixed(key, 32, 64)
+        .ok_or_else(|| TransactionError::InvalidPublicKey(key.0.clone()))?;
+    buf.extend_from_slice(&bytes);
+    Ok(())
+}
+
+fn write_signature(buf: &mut Vec<u8>, signature: &Signature) -> Result<(), TransactionError> {
+    let bytes = decode_curve_prefixed(signature, 64, 65)
+        .ok_or_else(|| TransactionError::InvalidSignature(signature.0.clone()))?;
+    buf.extend_from_slice(&bytes);
+    Ok(())
+}
+
+fn write_hash(buf: &mut Vec<u8>, hash: &CryptoHash) -> Result<(), TransactionError> {
+    let bytes = bs58::decode(hash.as_str())
+        .into_vec()
+        .ok()
+        .filter(|bytes| bytes.len() == 32)
+        .ok_or_else(|| TransactionError::InvalidHash(hash.0.clone()))?;
+    buf.extend_from_slice(&bytes);
+    Ok(())
+}
+
+fn write_access_key(buf: &mut Vec<u8>, access_key: &AccessKey) -> Result<(), TransactionError> {
+    buf.extend_from_slice(&access_key.nonce.to_le_bytes());
+    match &access_key.permission {
+        AccessKeyPermission::FunctionCall(permission) => {
+            buf.push(0);
+            match &permission.allowance {
+                Some(allowance) => {
+                    buf.push(1);
+                    write_u128(buf, allowance)?;
+                }
+                None => buf.push(0),
+            }
+            write_string(buf, &permission.receiver_id);
+            write_len(buf, permission.method_names.len());
+            for method_name in &permission.method_names {
+                write_string(buf, method_name);
+            }
+        }
+        AccessKeyPermission::FullAccess => buf.push(1),
+        AccessKeyPermission::GasKeyFunctionCall(..) | AccessKeyPermission::GasKeyFullAccess(_) => {
+            return Err(TransactionError::UnsupportedAction("AddKey (gas key)"));
+        }
+    }
+    Ok(())
+}
+
+/// Writes an action using nearcore's `Action` enum discriminants.
+fn write_action(buf: &mut Vec<u8>, action: &NonDelegateAction) -> Result<(), TransactionError> {
+    match action {
+        NonDelegateAction::CreateAccount(_) => buf.push(0),
+        NonDelegateAction::DeployContract(action) => {
+            buf.push(1);
+            write_base64(buf, "code", &action.code)?;
+        }
+        NonDelegateAction::FunctionCall(action) => {
+            buf.push(2);
+            write_string(buf, &action.method_name);
+            write_base64(buf, "args", &action.args)?;
+            buf.extend_from_slice(&action.gas.0.to_le_bytes());
+            write_u128(buf, &action.deposit)?;
+        }
+        NonDelegateAction::Transfer(action) => {
+            buf.push(3);
+            write_u128(buf, &action.deposit)?;
+        }
+        NonDelegateAction::Stake(action) => {
+            buf.push(4);
+            write_u128(buf, &action.stake)?;
+            write_public_key(buf, &action.public_key)?;
+        }
+        NonDelegateAction::AddKey(action) => {
+            buf.push(5);
+            write_public_key(buf, &action.public_key)?;
+            write_access_key(buf, &action.access_key)?;
+        }
+        NonDelegateAction::DeleteKey(action) => {
+            buf.push(6);
+            write_public_key(buf, &action.public_key)?;
+        }
+        NonDelegateAction::DeleteAccount(action) => {
+            buf.push(7);
+            write_string(buf, &action.beneficiary_id);
+        }
+        NonDelegateAction::DeployGlobalContract(action) => {
+            buf.push(9);
+            write_base64(buf, "code", &action.code)?;
+            buf.push(match action.deploy_mode {
+                GlobalContractDeployMode::CodeHash => 0,
+                GlobalContractDeployMode::AccountId => 1,
+            });
+        }
+        NonDelegateAction::UseGlobalContract(action) => {
+            buf.push(10);
+            match &action.contract_identifier {
+                GlobalContractIdentifier::CodeHash(hash) => {
+                    buf.push(0);
+                    write_hash(buf, hash)?;
+                }
+                GlobalContractIdentifier::AccountId(account_id) => {
+                    buf.push(1);
+                    write_string(buf, account_id);
+                }
+            }
+        }
+        NonDelegateAction::DeterministicStateInit(_) => {
+            return Err(TransactionError::UnsupportedAction(
+                "DeterministicStateInit",
+            ));
+        }
+        NonDelegateAction::TransferToGasKey(_) => {
+            return Err(TransactionError::UnsupportedAction("TransferToGasKey"));
+        }
+        NonDelegateAction::WithdrawFromGasKey(_) => {
+            return Err(TransactionError::UnsupportedAction("WithdrawFromGasKey"));
+        }
+    }
+    Ok(())
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use super::Transaction;
+    use crate::client::{NearRpcClient, Result};
+    use crate::keystore::{InMemorySigner, Keystore, KeystoreError};
+    use crate::types::*;
+
+    impl NearRpcClient {
+        /// Builds and signs a transaction for `signer`.
+        ///
+        /// Fetches the access key nonce and the latest final block hash, then
+        /// signs a transaction with nonce `access_key.nonce + 1`.
+        pub async fn sign_transaction(
+            &self,
+            signer: &InMemorySigner,
+            receiver_id: AccountId,
+            actions: Vec<NonDelegateAction>,
+        ) -> Result<SignedTransaction> {
+            let access_key = self
+                .view_access_key(RpcViewAccessKeyRequest::FinalityAccountId {
+                    account_id: signer.account_id().clone(),
+                    finality: Finality::Final,
+                    public_key: signer.public_key(),
+                })
+                .await?;
+            let block = self
+                .block(RpcBlockRequest::Finality(Finality::Final))
+                .await?;
+
+            let transaction = Transaction {
+                signer_id: signer.account_id().clone(),
+                public_key: signer.public_key(),
+                nonce: access_key.nonce + 1,
+                receiver_id,
+                block_hash: block.header.hash,
+                actions,
+            };
+            Ok(transaction.sign(signer)?)
+        }
+
+        /// Signs a transaction for `signer` and sends it with `send_tx`.
+        pub async fn send_transaction(
+            &self,
+            signer: &InMemorySigner,
+            receiver_id: AccountId,
+            actions: Vec<NonDelegateAction>,
+        ) -> Result<RpcTransactionResponse> {
+            let signed_tx_base64 = self.sign_transaction(signer, receiver_id, actions).await?;
+            self.send_tx(RpcSendTransactionRequest {
+                signed_tx_base64,
+                wait_until: TxExecutionStatus::ExecutedOptimistic,
+            })
+            .await
+        }
+
+        /// Loads `signer_id`'s key for this client's network from `keystore`,
+        /// then signs and sends a transaction.
+        ///
+        /// The network name comes from the constructor (`mainnet()`,
+        /// `testnet()`, ...) or [`with_network`](NearRpcClient::with_network).
+        pub async fn send_transaction_as(
+            &self,
+            keystore: &Keystore,
+            signer_id: &AccountId,
+            receiver_id: AccountId,
+            actions: Vec<NonDelegateAction>,
+        ) -> Result<RpcTransactionResponse> {
+            let network = self.network().ok_or(KeystoreError::UnknownNetwork)?;
+            let signer = keystore.signer(network, signer_id)?;
+            self.send_transaction(&signer, receiver_id, actions).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keystore::SecretKey;
+
+    fn signer() -> InMemorySigner {
+        InMemorySigner::new("alice.near".parse().unwrap(), SecretKey::from_seed([1; 32]))
+    }
+
+    fn transaction(actions: Vec<NonDelegateAction>) -> Transaction {
+        let signer = signer();
+        Transaction {
+            signer_id: signer.account_id().clone(),
+            public_key: signer.public_key(),
+            nonce: 1,
+            receiver_id: "bob.near".parse().unwrap(),
+            block_hash: CryptoHash(bs58::encode([0u8; 32]).into_string()),
+            actions,
+        }
+    }
+
+    #[test]
+    fn borsh_layout_of_transfer() {
+        let tx = transaction(vec![NonDelegateAction::Transfer(TransferAction {
+            deposit: NearToken("1".to_string()),
+        })]);
+        let bytes = tx.to_borsh().unwrap();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&10u32.to_le_bytes());
+        expected.extend_from_slice(b"alice.near");
+        expected.push(0);
+        expected.extend_from_slice(
+            &bs58::decode(signer().public_key().strip_prefix("ed25519:").unwrap())
+                .into_vec()
+                .unwrap(),
+        );
+        expected.extend_from_slice(&1u64.to_le_bytes());
+        expected.extend_from_slice(&8u32.to_le_bytes());
+        expected.extend_from_slice(b"bob.near");
+        expected.extend_from_slice(&[0u8; 32]);
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.push(3);
+        expected.extend_from_slice(&1u128.to_le_bytes());
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn signed_transaction_appends_verifiable_signature() {
+        use ed25519_dalek::{Signature as DalekSignature, Verifier, VerifyingKey};
+
+        let tx = transaction(vec![NonDelegateAction::FunctionCall(FunctionCallAction {
+            args: STANDARD.encode(br#"{"a":1}"#),
+            deposit: NearToken("0".to_string()),
+            gas: NearGas(30_000_000_000_000),
+            method_name: "set".to_string(),
+        })]);
+        let unsigned = tx.to_borsh().unwrap();
+        let signed = STANDARD.decode(tx.sign(&signer()).unwrap().0).unwrap();
+
+        assert_eq!(&signed[..unsigned.len()], &unsigned[..]);
+        assert_eq!(signed.len(), unsigned.len() + 65);
+        assert_eq!(signed[unsigned.len()], 0);
+
+        let public_key: [u8; 32] =
+            bs58::decode(signer().public_key().strip_prefix("ed25519:").unwrap())
+                .into_vec()
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let signature: [u8; 64] = signed[unsigned.len() + 1..].try_into().unwrap();
+        VerifyingKey::from_bytes(&public_key)
+            .unwrap()
+            .verify(
+                &Sha256::digest(&unsigned),
+                &DalekSignature::from_bytes(&signature),
+            )
+            .expect("signature should verify");
+    }
+
+    #[test]
+    fn unsupported_actions_are_rejected() {
+        let tx = transaction(vec![NonDelegateAction::WithdrawFromGasKey(
+            WithdrawFromGasKeyAction {
+                amount: NearToken("1".to_string()),
+                public_key: signer().public_key(),
+            },
+        )]);
+        assert!(matches!(
+            tx.to_borsh(),
+            Err(TransactionError::UnsupportedAction("WithdrawFromGasKey"))
+        ));
+    }
+
+    #[test]
+    fn invalid_amount_is_rejected() {
+        let tx = transaction(vec![NonDelegateAction::Transfer(TransferAction {
+            deposit: NearToken("lots".to_string()),
+        })]);
+        assert!(matches!(
+            tx.to_borsh(),
+            Err(TransactionError::InvalidAmount(_))
+        ));
+    }
+}
@@ -0,0 +1,468 @@
+//! Per-validator metrics and epoch-to-epoch diffs from `validators` responses.
+//!
+//! [`EpochAnalytics::from_response`] turns an [`RpcValidatorResponse`] into
+//! production ratios, stake shares and seat status for every current
+//! validator, plus a [`ValidatorSetDiff`] between `current_validators` and
+//! `next_validators`.
+//!
+//! Validators are selected two epochs ahead, so `prev_epoch_kickout` (the
+//! kickouts decided at the start of the current epoch) explains why a current
+//! validator has no seat in the next epoch.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use near_openrpc_client::{NearRpcClient, types::*};
+//!
+//! #[tokio::main]
+//! async fn main() -> near_openrpc_client::client::Result<()> {
+//!     let client = NearRpcClient::mainnet();
+//!     let epoch = client
+//!         .epoch_analytics(RpcValidatorRequest::Latest)
+//!         .await?;
+//!
+//!     for validator in &epoch.validators {
+//!         println!(
+//!             "{}: {:.2}% of stake, uptime {:?}",
+//!             validator.account_id,
+//!             validator.stake_share * 100.0,
+//!             validator.uptime(),
+//!         );
+//!     }
+//!     println!("{} joining next epoch", epoch.diff.joining.len());
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::types::*;
+
+/// Errors specific to validator analytics.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidatorAnalyticsError {
+    #[error("invalid stake for {account_id}: {value}")]
+    InvalidStake {
+        account_id: AccountId,
+        value: String,
+    },
+}
+
+/// Produced versus expected blocks, chunks or endorsements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ratio {
+    pub produced: u64,
+    pub expected: u64,
+}
+
+impl Ratio {
+    pub fn new(produced: u64, expected: u64) -> Self {
+        Self { produced, expected }
+    }
+
+    /// `produced / expected`, or `None` if nothing was expected.
+    pub fn fraction(&self) -> Option<f64> {
+        (self.expected > 0).then(|| self.produced as f64 / self.expected as f64)
+    }
+
+    /// Expected but not produced.
+    pub fn missed(&self) -> u64 {
+        self.expected.saturating_sub(self.produced)
+    }
+}
+
+/// A [`Ratio`] for one shard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardRatio {
+    pub shard_id: u64,
+    pub ratio: Ratio,
+}
+
+/// Whether a current validator keeps its seat in the next epoch.
+#[derive(Debug, Clone)]
+pub enum SeatStatus {
+    Retained,
+    /// Not among `next_validators`; `reason` is set when the validator was
+    /// in `prev_epoch_kickout`.
+    Leaving {
+        reason: Option<ValidatorKickoutReason>,
+    },
+}
+
+/// Metrics for one current-epoch validator.
+#[derive(Debug, Clone)]
+pub struct ValidatorMetrics {
+    pub account_id: AccountId,
+    /// Stake in yoctoNEAR.
+    pub stake: u128,
+    /// Fraction of the total stake of the current validators, in `0.0..=1.0`.
+    pub stake_share: f64,
+    pub is_slashed: bool,
+    pub blocks: Ratio,
+    pub chunks: Ratio,
+    pub endorsements: Ratio,
+    /// Chunk production per shard the validator produces chunks for.
+    pub chunks_per_shard: Vec<ShardRatio>,
+    /// Chunk endorsements per shard the validator validates.
+    pub endorsements_per_shard: Vec<ShardRatio>,
+    pub seat: SeatStatus,
+}
+
+impl ValidatorMetrics {
+    /// The mean of the block, chunk and endorsement production fractions the
+    /// validator had any expectations for, or `None` if it had none so far.
+    pub fn uptime(&self) -> Option<f64> {
+        let fractions: Vec<f64> = [self.blocks, self.chunks, self.endorsements]
+            .iter()
+            .filter_map(Ratio::fraction)
+            .collect();
+        (!fractions.is_empty()).then(|| fractions.iter().sum::<f64>() / fractions.len() as f64)
+    }
+}
+
+/// A validator whose stake differs between the current and next epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakeChange {
+    pub account_id: AccountId,
+    pub before: u128,
+    pub after: u128,
+}
+
+/// A validator whose chunk-producing shards differ between the current and
+/// next epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardAssignmentChange {
+    pub account_id: AccountId,
+    pub before: Vec<u64>,
+    pub after: Vec<u64>,
+}
+
+/// Differences between `current_validators` and `next_validators`.
+#[derive(Debug, Clone, Default)]
+pub struct ValidatorSetDiff {
+    /// In the next epoch but not the current one.
+    pub joining: Vec<NextEpochValidatorInfo>,
+    /// In the current epoch but not the next one.
+    pub leaving: Vec<AccountId>,
+    pub stake_changes: Vec<StakeChange>,
+    pub shard_changes: Vec<ShardAssignmentChange>,
+}
+
+/// Analytics for one `validators` response.
+#[derive(Debug, Clone)]
+pub struct EpochAnalytics {
+    pub epoch_height: u64,
+    pub epoch_start_height: u64,
+    /// Total stake of the current validators, in yoctoNEAR.
+    pub total_stake: u128,
+    /// Total stake of the next epoch's validators, in yoctoNEAR.
+    pub next_total_stake: u128,
+    /// Current validators, in response order.
+    pub validators: Vec<ValidatorMetrics>,
+    pub diff: ValidatorSetDiff,
+    pub kickouts: Vec<ValidatorKickoutView>,
+}
+
+impl EpochAnalytics {
+    pub fn from_response(response: &RpcValidatorResponse) -> Result<Self, ValidatorAnalyticsError> {
+        let stakes = response
+            .current_validators
+            .iter()
+            .map(|info| parse_stake(&info.account_id, &info.stake))
+            .collect::<Result<Vec<_>, _>>()?;
+        let total_stake: u128 = stakes.iter().sum();
+        let next_total_stake = response
+            .next_validators
+            .iter()
+            .map(|info| parse_stake(&info.account_id, &info.stake))
+            .sum::<Result<u128, _>>()?;
+
+        let next: HashMap<&AccountId, &NextEpochValidatorInfo> = response
+            .next_validators
+            .iter()
+            .map(|info| (&info.account_id, info))
+            .collect();
+        let kickouts: HashMap<&AccountId, &ValidatorKickoutReason> = response
+            .prev_epoch_kickout
+            .iter()
+            .map(|kickout| (&kickout.account_id, &kickout.reason))
+            .collect();
+
+        let validators = response
+            .current_validators
+            .iter()
+            .zip(stakes)
+            .map(|(info, stake)| {
+                let seat = if next.contains_key(&info.account_id) {
+                    SeatStatus::Retained
+                } else {
+                    SeatStatus::Leaving {
+                        reason: kickouts.get(&info.account_id).map(|&reason| reason.clone()),
+                    }
+                };
+                ValidatorMetrics {
+                    account_id: info.account_id.clone(),
+                    stake,
+                    stake_share: share(stake, total_stake),
+                    is_slashed: info.is_slashed,
+                    blocks: Ratio::new(info.num_produced_blocks, info.num_expected_blocks),
+                    chunks: Ratio::new(info.num_produced_chunks, info.num_expected_chunks),
+                    endorsements: Ratio::new(
+                        info.num_produced_endorsements,
+                        info.num_expected_endorsements,
+                    ),
+                    chunks_per_shard: per_shard(
+                        &info.shards,
+                        &info.num_produced_chunks_per_shard,
+                        &info.num_expected_chunks_per_shard,
+                    ),
+                    endorsements_per_shard: per_shard(
+                        &info.shards_endorsed,
+                        &info.num_produced_endorsements_per_shard,
+                        &info.num_expected_endorsements_per_shard,
+                    ),
+                    seat,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            epoch_height: response.epoch_height,
+            epoch_start_height: response.epoch_start_height,
+            total_stake,
+            next_total_stake,
+            validators,
+            diff: validator_set_diff(&response.current_validators, &response.next_validators)?,
+            kickouts: response.prev_epoch_kickout.clone(),
+        })
+    }
+
+    /// Metrics for `account_id`, if it is a current validator.
+    pub fn validator(&self, account_id: &AccountId) -> Option<&ValidatorMetrics> {
+        self.validators
+            .iter()
+            .find(|metrics| &metrics.account_id == account_id)
+    }
+}
+
+/// Compares the current and next validator sets. Lists keep the order of the
+/// input they come from.
+pub fn validator_set_diff(
+    current: &[CurrentEpochValidatorInfo],
+    next: &[NextEpochValidatorInfo],
+) -> Result<ValidatorSetDiff, ValidatorAnalyticsError> {
+    let current_by_id: HashMap<&AccountId, &CurrentEpochValidatorInfo> = current
+        .iter()
+        .map(|info| (&info.account_id, info))
+        .collect();
+    let next_by_id: HashMap<&AccountId, &NextEpochValidatorInfo> =
+        next.iter().map(|info| (&info.account_id, info)).collect();
+
+    let mut diff = ValidatorSetDiff {
+        leaving: current
+            .iter()
+            .filter(|info| !next_by_id.contains_key(&info.account_id))
+            .map(|info| info.account_id.clone())
+            .collect(),
+        ..Default::default()
+    };
+
+    for info in next {
+        let Some(before) = current_by_id.get(&info.account_id) else {
+            diff.joining.push(info.clone());
+            continue;
+        };
+
+        let stake_before = parse_stake(&before.account_id, &before.stake)?;
+        let stake_after = parse_stake(&info.account_id, &info.stake)?;
+        if stake_before != stake_after {
+            diff.stake_changes.push(StakeChange {
+                account_id: info.account_id.clone(),
+                before: stake_before,
+                after: stake_after,
+            });
+        }
+
+        let shards_before: Vec<u64> = before.shards.iter().map(|shard| shard.0).collect();
+        let shards_after: Vec<u64> = info.shards.iter().map(|shard| shard.0).collect();
+        if shards_before != shards_after {
+            diff.shard_changes.push(ShardAssignmentChange {
+                account_id: info.account_id.clone(),
+                before: shards_before,
+                after: shards_after,
+            });
+        }
+    }
+    Ok(diff)
+}
+
+fn parse_stake(account_id: &AccountId, stake: &NearToken) -> Result<u128, ValidatorAnalyticsError> {
+    stake
+        .parse()
+        .map_err(|_| ValidatorAnalyticsError::InvalidStake {
+            account_id: account_id.clone(),
+            value: stake.0.clone(),
+        })
+}
+
+fn share(stake: u128, total: u128) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        stake as f64 / total as f64
+    }
+}
+
+fn per_shard(shards: &[ShardId], produced: &[u64], expected: &[u64]) -> Vec<ShardRatio> {
+    shards
+        .iter()
+        .zip(produced.iter().zip(expected))
+        .map(|(shard, (&produced, &expected))| ShardRatio {
+            shard_id: shard.0,
+            ratio: Ratio::new(produced, expected),
+        })
+        .collect()
+}
+
+// ── Client integration ───────────────────────────────────────────────────
+
+#[cfg(feature = "client")]
+mod client_ext {
+    use super::EpochAnalytics;
+    use crate::client::{NearRpcClient, Result};
+    use crate::types::*;
+
+    impl NearRpcClient {
+        /// Calls `validators` and computes [`EpochAnalytics`] for the response.
+        pub async fn epoch_analytics(
+            &self,
+            request: RpcValidatorRequest,
+        ) -> Result<EpochAnalytics> {
+            let response = self.validators(request).await?;
+            Ok(EpochAnalytics::from_response(&response)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> RpcValidatorResponse {
+        serde_json::from_value(serde_json::json!({
+            "current_fishermen": [],
+            "current_proposals": [],
+            "current_validators": [
+                {
+                    "account_id": "alice.near",
+                    "is_slashed": false,
+                    "num_expected_blocks": 100,
+                    "num_produced_blocks": 90,
+                    "num_expected_chunks": 40,
+                    "num_produced_chunks": 30,
+                    "num_expected_chunks_per_shard": [20, 20],
+                    "num_produced_chunks_per_shard": [20, 10],
+                    "num_expected_endorsements": 0,
+                    "num_produced_endorsements": 0,
+                    "public_key": "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+                    "shards": [0, 1],
+                    "stake": "3000"
+                },
+                {
+                    "account_id": "bob.near",
+                    "is_slashed": false,
+                    "num_expected_blocks": 0,
+                    "num_produced_blocks": 0,
+                    "num_expected_endorsements": 10,
+                    "num_produced_endorsements": 5,
+                    "num_expected_endorsements_per_shard": [10],
+                    "num_produced_endorsements_per_shard": [5],
+                    "public_key": "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+                    "shards": [],
+                    "shards_endorsed": [1],
+                    "stake": "1000"
+                }
+            ],
+            "epoch_height": 7,
+            "epoch_start_height": 700,
+            "next_fishermen": [],
+            "next_validators": [
+                {
+                    "account_id": "alice.near",
+                    "public_key": "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+                    "shards": [1],
+                    "stake": "3500"
+                },
+                {
+                    "account_id": "carol.near",
+                    "public_key": "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp",
+                    "shards": [0],
+                    "stake": "2000"
+                }
+            ],
+            "prev_epoch_kickout": [
+                {
+                    "account_id": "bob.near",
+                    "reason": { "NotEnoughChunkEndorsements": { "expected": 10, "produced": 2 } }
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn computes_validator_metrics() {
+        let epoch = EpochAnalytics::from_response(&response()).unwrap();
+        assert_eq!(epoch.total_stake, 4000);
+        assert_eq!(epoch.next_total_stake, 5500);
+
+        let alice = epoch.validator(&"alice.near".parse().unwrap()).unwrap();
+        assert_eq!(alice.stake_share, 0.75);
+        assert_eq!(alice.blocks.fraction(), Some(0.9));
+        assert_eq!(alice.chunks.missed(), 10);
+        assert_eq!(alice.endorsements.fraction(), None);
+        assert_eq!(
+            alice.chunks_per_shard,
+            vec![
+                ShardRatio {
+                    shard_id: 0,
+                    ratio: Ratio::new(20, 20)
+                },
+                ShardRatio {
+                    shard_id: 1,
+                    ratio: Ratio::new(10, 20)
+                },
+            ]
+        );
+        assert_eq!(alice.uptime(), Some((0.9 + 0.75) / 2.0));
+        assert!(matches!(alice.seat, SeatStatus::Retained));
+
+        let bob = epoch.validator(&"bob.near".parse().unwrap()).unwrap();
+        assert_eq!(bob.uptime(), Some(0.5));
+        assert_eq!(bob.endorsements_per_shard[0].shard_id, 1);
+        assert!(matches!(
+            bob.seat,
+            SeatStatus::Leaving {
+                reason: Some(ValidatorKickoutReason::NotEnoughChunkEndorsements { .. })
+            }
+        ));
+    }
+
+    #[test]
+    fn diffs_current_and_next_validators() {
+        let diff = EpochAnalytics::from_response(&response()).unwrap().diff;
+        let joining: Vec<_> = diff.joining.iter().map(|v| v.account_id.as_str()).collect();
+        assert_eq!(joining, ["carol.near"]);
+        assert_eq!(diff.leaving, ["bob.near".parse::<AccountId>().unwrap()]);
+        assert_eq!(
+            diff.stake_changes,
+            vec![StakeChange {
+                account_id: "alice.near".parse().unwrap(),
+                before: 3000,
+                after: 3500,
+            }]
+        );
+        assert_eq!(diff.shard_changes[0].before, [0, 1]);
+        assert_eq!(diff.shard_changes[0].after, [1]);
+    }
+}
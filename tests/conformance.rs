@@ -0,0 +1,411 @@
+//! Conformance of the generated types with `openrpc.json`.
+//!
+//! For the result of every method in the spec, a corpus of responses is
+//! checked three ways: each response must validate against the method's
+//! result schema, deserialize into the generated type, and serialize back
+//! without losing or changing any field.
+//!
+//! The corpus is
+//!
+//! - every fixture under `tests/fixtures`, in the format written by
+//!   `fixtures::Fixtures`: the responses under `localnet` are the block and
+//!   chunk of near-lake-framework's sample block 879765, and
+//!   `tests/mainnet_fixtures.rs` records more from mainnet, and
+//! - instances synthesized from each result schema: one with only required
+//!   fields and one with every field, for several choices of `oneOf` and
+//!   `anyOf` branches and enum values. nearcore's spec carries no
+//!   `examples`, so these stand in for them.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::Path;
+
+use near_openrpc_client::types::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+
+/// How deep synthesized instances fill in optional fields and array items.
+const MAX_DEPTH: usize = 12;
+/// How many branch and enum choices are synthesized per schema.
+const VARIANTS: usize = 6;
+
+/// Schemas whose top-level `oneOf` branches overlap, so real responses match
+/// more than one, with the reason. They are checked as `anyOf`, which is how
+/// the generated untagged enums read them.
+const OVERLAPPING_ONE_OF: &[(&str, &str)] = &[(
+    "RpcTransactionResponse",
+    "an outcome with receipts also matches the outcome without them, and \
+     both match the branch with only `final_execution_status`",
+)];
+
+struct Spec {
+    /// `components.schemas`, with `$ref`s rewritten to `#/definitions/`.
+    schemas: Map<String, Value>,
+    /// Each method with the schema name of its result.
+    methods: Vec<(String, String)>,
+}
+
+impl Spec {
+    fn load() -> Self {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/openrpc.json");
+        let spec = std::fs::read_to_string(path)
+            .unwrap()
+            .replace("#/components/schemas/", "#/definitions/");
+        let spec: Value = serde_json::from_str(&spec).unwrap();
+        let methods = spec["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|method| {
+                let name = method["name"].as_str().unwrap().to_string();
+                let schema = method["result"]["schema"]["$ref"].as_str().unwrap();
+                (
+                    name,
+                    schema.trim_start_matches("#/definitions/").to_string(),
+                )
+            })
+            .collect();
+        let schemas = spec["components"]["schemas"].as_object().unwrap().clone();
+        Self { schemas, methods }
+    }
+
+    fn result_schema(&self, method: &str) -> Option<&str> {
+        self.methods
+            .iter()
+            .find(|(name, _)| name == method)
+            .map(|(_, schema)| schema.as_str())
+    }
+
+    /// A validator for the schema named `schema`, with the `oneOf` of each
+    /// schema in [`OVERLAPPING_ONE_OF`] checked as `anyOf`.
+    fn validator(&self, schema: &str) -> jsonschema::Validator {
+        let mut definitions = self.schemas.clone();
+        for (name, _) in OVERLAPPING_ONE_OF {
+            let overlapping = definitions[*name].as_object_mut().unwrap();
+            let branches = overlapping.remove("oneOf").unwrap();
+            overlapping.insert("anyOf".to_string(), branches);
+        }
+        let root = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "definitions": definitions,
+            "$ref": format!("#/definitions/{schema}"),
+        });
+        jsonschema::draft7::new(&root).unwrap()
+    }
+}
+
+/// Synthesizes an instance of a schema.
+struct Synthesizer<'a> {
+    schemas: &'a Map<String, Value>,
+    /// Picks `oneOf`/`anyOf` branches and enum values.
+    variant: usize,
+    /// Whether to fill in optional fields and array items.
+    full: bool,
+}
+
+impl Synthesizer<'_> {
+    fn instance(&self, schema: &Value, depth: usize) -> Value {
+        let Some(schema) = schema.as_object() else {
+            return json!({});
+        };
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.trim_start_matches("#/definitions/");
+            return self.instance(&self.schemas[name], depth + 1);
+        }
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            return values[self.variant % values.len()].clone();
+        }
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(branches) = schema.get(keyword).and_then(Value::as_array) {
+                // Past the depth limit, take the first branch, which keeps
+                // recursive schemas finite.
+                let index = if depth < MAX_DEPTH {
+                    (self.variant + depth) % branches.len()
+                } else {
+                    0
+                };
+                let mut value = self.instance(&branches[index], depth + 1);
+                merge(&mut value, self.object(schema, depth));
+                return value;
+            }
+        }
+        if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+            let mut parts = parts.iter().map(|part| self.instance(part, depth + 1));
+            let mut value = parts.next().unwrap_or(json!({}));
+            for part in parts {
+                merge(&mut value, part);
+            }
+            merge(&mut value, self.object(schema, depth));
+            return value;
+        }
+
+        let types: Vec<&str> = match schema.get("type") {
+            Some(Value::String(ty)) => vec![ty.as_str()],
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+            _ if schema.contains_key("properties") => vec!["object"],
+            _ => return json!({}),
+        };
+        let ty = match types.iter().find(|ty| **ty != "null") {
+            Some(ty) if self.full || !types.contains(&"null") => *ty,
+            _ => "null",
+        };
+        match ty {
+            "object" => self.object(schema, depth),
+            "array" => self.array(schema, depth),
+            "string" => match schema.get("format").and_then(Value::as_str) {
+                Some("date-time") => json!("2020-07-21T16:55:51.591948Z"),
+                _ => json!("11111111111111111111111111111111"),
+            },
+            "integer" => {
+                let minimum = schema.get("minimum").and_then(Value::as_u64).unwrap_or(0);
+                let maximum = schema.get("maximum").and_then(Value::as_u64);
+                json!(maximum.map_or(minimum + 1, |maximum| (minimum + 1).min(maximum)))
+            }
+            "number" => json!(0.5),
+            "boolean" => json!(self.variant.is_multiple_of(2)),
+            _ => Value::Null,
+        }
+    }
+
+    fn object(&self, schema: &Map<String, Value>, depth: usize) -> Value {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let fill = self.full && depth < MAX_DEPTH;
+        let mut object = Map::new();
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                if fill || required.contains(&name.as_str()) {
+                    object.insert(name.clone(), self.instance(property, depth + 1));
+                }
+            }
+        }
+        if fill {
+            if let Some(values) = schema.get("additionalProperties").filter(|s| s.is_object()) {
+                object.insert("key".to_string(), self.instance(values, depth + 1));
+            }
+            if let Some(patterns) = schema.get("patternProperties").and_then(Value::as_object) {
+                for values in patterns.values() {
+                    object.insert("0".to_string(), self.instance(values, depth + 1));
+                }
+            }
+        }
+        Value::Object(object)
+    }
+
+    fn array(&self, schema: &Map<String, Value>, depth: usize) -> Value {
+        let bound = |keyword| {
+            schema
+                .get(keyword)
+                .and_then(Value::as_u64)
+                .map(|n| n as usize)
+        };
+        let items = schema.get("items").cloned().unwrap_or(json!({}));
+        if let Value::Array(tuple) = &items {
+            return tuple
+                .iter()
+                .map(|item| self.instance(item, depth + 1))
+                .collect();
+        }
+        let wanted = if self.full && depth < MAX_DEPTH { 1 } else { 0 };
+        let len = wanted
+            .max(bound("minItems").unwrap_or(0))
+            .min(bound("maxItems").unwrap_or(usize::MAX));
+        (0..len).map(|_| self.instance(&items, depth + 1)).collect()
+    }
+}
+
+fn merge(into: &mut Value, from: Value) {
+    if let (Value::Object(into), Value::Object(from)) = (into, from) {
+        into.extend(from);
+    }
+}
+
+/// Deserializes `value` as `T` and serializes it back.
+fn round_trip<T: DeserializeOwned + Serialize>(value: &Value) -> Result<Value, String> {
+    let typed: T = serde_json::from_value(value.clone()).map_err(|err| err.to_string())?;
+    serde_json::to_value(typed).map_err(|err| err.to_string())
+}
+
+/// Round-trips `value` through the generated type named `schema`.
+fn round_trip_as(schema: &str, value: &Value) -> Result<Value, String> {
+    macro_rules! types {
+        ($($ty:ident),* $(,)?) => {
+            match schema {
+                $(stringify!($ty) => round_trip::<$ty>(value),)*
+                other => panic!("no generated type listed for result schema {other}"),
+            }
+        };
+    }
+    types!(
+        BlockHeightRanges,
+        CryptoHash,
+        GenesisConfig,
+        RpcBlockResponse,
+        RpcCallFunctionResponse,
+        RpcChunkResponse,
+        RpcClientConfigResponse,
+        RpcCongestionLevelResponse,
+        RpcGasPriceResponse,
+        RpcHealthResponse,
+        RpcLightClientBlockProofResponse,
+        RpcLightClientExecutionProofResponse,
+        RpcLightClientNextBlockResponse,
+        RpcNetworkInfoResponse,
+        RpcProtocolConfigResponse,
+        RpcReceiptResponse,
+        RpcSplitStorageInfoResponse,
+        RpcStateChangesInBlockByTypeResponse,
+        RpcStateChangesInBlockResponse,
+        RpcStatusResponse,
+        RpcTransactionResponse,
+        RpcValidatorResponse,
+        RpcViewAccessKeyListResponse,
+        RpcViewAccessKeyResponse,
+        RpcViewAccountResponse,
+        RpcViewCodeResponse,
+        RpcViewStateResponse,
+        ValidatorStakeViews,
+    )
+}
+
+/// Paths in `original` that are missing from or different in `round_tripped`.
+/// A `null` that round-trips to an absent field is not a loss.
+fn lossy_fields(original: &Value, round_tripped: &Value, path: &str, lossy: &mut Vec<String>) {
+    match (original, round_tripped) {
+        (Value::Object(original), Value::Object(round_tripped)) => {
+            for (key, value) in original {
+                let path = format!("{path}.{key}");
+                match round_tripped.get(key) {
+                    Some(other) => lossy_fields(value, other, &path, lossy),
+                    None if value.is_null() => {}
+                    None => lossy.push(format!("{path} dropped")),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(round_tripped))
+            if original.len() == round_tripped.len() =>
+        {
+            for (index, (value, other)) in original.iter().zip(round_tripped).enumerate() {
+                lossy_fields(value, other, &format!("{path}[{index}]"), lossy);
+            }
+        }
+        (Value::Number(a), Value::Number(b)) if a.as_f64() == b.as_f64() => {}
+        (a, b) if a == b => {}
+        (a, b) => lossy.push(format!("{path} changed from {a} to {b}")),
+    }
+}
+
+/// Checks one response against its method's result schema and type,
+/// returning a description of each failure.
+fn check(validator: &jsonschema::Validator, schema: &str, value: &Value) -> Vec<String> {
+    let mut failures: Vec<String> = validator
+        .iter_errors(value)
+        .map(|err| format!("schema: {} at {}", err, err.instance_path()))
+        .collect();
+    match round_trip_as(schema, value) {
+        Ok(round_tripped) => {
+            let mut lossy = Vec::new();
+            lossy_fields(value, &round_tripped, "$", &mut lossy);
+            failures.extend(lossy.into_iter().map(|field| format!("lossy: {field}")));
+        }
+        Err(err) => failures.push(format!("deserialize: {err}")),
+    }
+    failures
+}
+
+#[test]
+fn synthesized_results_conform() {
+    let spec = Spec::load();
+    let mut failures = Vec::new();
+    let mut schemas: Vec<&str> = spec
+        .methods
+        .iter()
+        .map(|(_, schema)| schema.as_str())
+        .collect();
+    schemas.sort();
+    schemas.dedup();
+    for schema in schemas {
+        let validator = spec.validator(schema);
+        let mut seen = Vec::new();
+        for variant in 0..VARIANTS {
+            for full in [false, true] {
+                let synthesizer = Synthesizer {
+                    schemas: &spec.schemas,
+                    variant,
+                    full,
+                };
+                let value =
+                    synthesizer.instance(&json!({ "$ref": format!("#/definitions/{schema}") }), 0);
+                if seen.contains(&value) {
+                    continue;
+                }
+                for failure in check(&validator, schema, &value) {
+                    failures.push(format!(
+                        "{schema} (variant {variant}, full {full}): {failure}"
+                    ));
+                }
+                seen.push(value);
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} failures:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn recorded_results_conform() {
+    let spec = Spec::load();
+    let mut failures = Vec::new();
+    let mut files = Vec::new();
+    collect_json(
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures")),
+        &mut files,
+    );
+    let mut checked = 0;
+    for file in files {
+        let fixture: Value = serde_json::from_slice(&std::fs::read(&file).unwrap()).unwrap();
+        let method = fixture["method"].as_str().unwrap();
+        let Some(result) = fixture["response"].get("result") else {
+            continue;
+        };
+        let schema = spec
+            .result_schema(method)
+            .unwrap_or_else(|| panic!("{}: method {method} is not in the spec", file.display()));
+        for failure in check(&spec.validator(schema), schema, result) {
+            failures.push(format!("{}: {failure}", file.display()));
+        }
+        checked += 1;
+    }
+    assert!(checked > 0, "no recorded results under tests/fixtures");
+    assert!(
+        failures.is_empty(),
+        "{} failures:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+fn collect_json(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_json(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+}
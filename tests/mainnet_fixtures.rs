@@ -0,0 +1,89 @@
+//! Deserialization of real mainnet responses, replayed from
+//! `tests/fixtures/mainnet`.
+//!
+//! The tests are ignored until fixtures are recorded, which needs network
+//! access to mainnet:
+//!
+//! ```text
+//! NEAR_RPC_RECORD=1 cargo test --features testing --test mainnet_fixtures -- --ignored
+//! ```
+//!
+//! Each test derives its later requests from earlier responses, so a replay
+//! makes exactly the calls that were recorded.
+
+#![cfg(feature = "testing")]
+
+use near_openrpc_client::client::Result;
+use near_openrpc_client::fixtures::Fixtures;
+use near_openrpc_client::{NearRpcClient, types::*};
+
+/// How far [`transaction`] walks back looking for a block with transactions.
+const MAX_BLOCKS_WITHOUT_TRANSACTIONS: usize = 100;
+
+fn client() -> NearRpcClient {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mainnet");
+    NearRpcClient::mainnet().with_middleware(Fixtures::from_env(dir))
+}
+
+#[tokio::test]
+#[ignore = "needs fixtures recorded from mainnet, see the module docs"]
+async fn block_and_chunks() -> Result<()> {
+    let client = client();
+    let head = client
+        .block_with_chunks(RpcBlockRequest::Finality(Finality::Final))
+        .await?;
+    let by_hash = client
+        .block(RpcBlockRequest::BlockId(BlockId::CryptoHash(
+            head.block.header.hash.clone(),
+        )))
+        .await?;
+    assert_eq!(by_hash.header.height, head.block.header.height);
+    assert_eq!(head.shards.len(), head.block.chunks.len());
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs fixtures recorded from mainnet, see the module docs"]
+async fn transaction() -> Result<()> {
+    let client = client();
+    // Walk back from the final block to the first one with a transaction.
+    let mut block = client
+        .block_with_chunks(RpcBlockRequest::Finality(Finality::Final))
+        .await?;
+    for _ in 0..MAX_BLOCKS_WITHOUT_TRANSACTIONS {
+        if block.transactions().next().is_some() {
+            break;
+        }
+        let prev_hash = block.block.header.prev_hash.clone();
+        block = client
+            .block_with_chunks(RpcBlockRequest::BlockId(BlockId::CryptoHash(prev_hash)))
+            .await?;
+    }
+    let (_, tx) = block.transactions().next().unwrap_or_else(|| {
+        panic!(
+            "no transactions in the {MAX_BLOCKS_WITHOUT_TRANSACTIONS} blocks before the final one"
+        )
+    });
+    let outcome = client
+        .tx(RpcTransactionStatusRequest::TxHashSenderAccountId {
+            sender_account_id: tx.signer_id.clone(),
+            tx_hash: tx.hash.clone(),
+            wait_until: TxExecutionStatus::Final,
+        })
+        .await?;
+    let (RpcTransactionResponse::FinalExecutionOutcomeWithReceiptView { transaction, .. }
+    | RpcTransactionResponse::FinalExecutionOutcomeView { transaction, .. }) = outcome
+    else {
+        panic!("expected an execution outcome, got {outcome:?}");
+    };
+    assert_eq!(transaction.hash, tx.hash);
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs fixtures recorded from mainnet, see the module docs"]
+async fn genesis_config() -> Result<()> {
+    let genesis = client().genesis_config().await?;
+    assert_eq!(genesis.chain_id, "mainnet");
+    Ok(())
+}
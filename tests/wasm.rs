@@ -0,0 +1,53 @@
+//! Client tests for `wasm32-unknown-unknown`, run under Node with
+//! `wasm-bindgen-test-runner`:
+//!
+//! ```text
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test --target wasm32-unknown-unknown --test wasm
+//! ```
+//!
+//! Responses come from middleware, so no network is needed.
+
+#![cfg(target_arch = "wasm32")]
+
+use near_openrpc_client::NearRpcClient;
+use near_openrpc_client::client::Error;
+use near_openrpc_client::middleware::{HandleFuture, Middleware, Next, RpcCall};
+use serde_json::{Value, json};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// Answers every call with `response`, in place of the fetch transport.
+struct Respond(Value);
+
+impl Middleware for Respond {
+    fn handle<'a>(&'a self, call: RpcCall, _next: Next<'a>) -> HandleFuture<'a> {
+        Box::pin(async move {
+            let mut response = self.0.clone();
+            response["jsonrpc"] = json!("2.0");
+            response["id"] = json!(call.id);
+            Ok(response)
+        })
+    }
+}
+
+#[wasm_bindgen_test]
+async fn decodes_results() {
+    let client = NearRpcClient::testnet().with_middleware(Respond(json!({ "result": null })));
+    client.health().await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn decodes_rpc_errors() {
+    let client = NearRpcClient::testnet().with_middleware(Respond(json!({
+        "error": {
+            "code": -32000,
+            "message": "Server error",
+            "name": "HANDLER_ERROR",
+            "cause": { "name": "UNKNOWN_BLOCK", "info": {} }
+        }
+    })));
+    let Err(Error::Rpc(err)) = client.status().await else {
+        panic!("expected an RPC error");
+    };
+    assert_eq!(err.cause_name(), Some("UNKNOWN_BLOCK"));
+}